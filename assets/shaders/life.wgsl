@@ -0,0 +1,49 @@
+// one generation of a 2-state rule, read from `input` and written to `output`
+// the birth and survival bit masks of the rule are filled in before the shader is compiled
+
+@group(0) @binding(0)
+var input: texture_2d<f32>;
+
+@group(0) @binding(1)
+var output: texture_storage_2d<rgba8unorm, write>;
+
+fn is_alive(location: vec2<i32>, size: vec2<i32>) -> u32 {
+#ifdef TOROIDAL
+    let p = (location + size) % size;
+#else
+    if (any(location < vec2<i32>(0)) || any(location >= size)) {
+        return 0u;
+    }
+    let p = location;
+#endif
+    return u32(textureLoad(input, p, 0).g < 0.5);
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn update(@builtin(global_invocation_id) invocation_id: vec3<u32>) {
+    let size = vec2<i32>(textureDimensions(input));
+    let location = vec2<i32>(invocation_id.xy);
+    if (any(location >= size)) {
+        return;
+    }
+
+    var neighbours = 0u;
+    for (var dx = -1; dx <= 1; dx = dx + 1) {
+        for (var dy = -1; dy <= 1; dy = dy + 1) {
+            if (dx != 0 || dy != 0) {
+                neighbours = neighbours + is_alive(location + vec2<i32>(dx, dy), size);
+            }
+        }
+    }
+
+    var mask = {BIRTH}u;
+    if (is_alive(location, size) == 1u) {
+        mask = {SURVIVAL}u;
+    }
+    // the same colors as the sprites, live cells are dark
+    var color = vec4<f32>(1.0, 1.0, 1.0, 1.0);
+    if ((mask & (1u << neighbours)) != 0u) {
+        color = vec4<f32>(0.1, 0.1, 0.1, 1.0);
+    }
+    textureStore(output, location, color);
+}
@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+
+use crate::library::SyncIndex;
+use crate::panel::BesidePanel;
+use crate::sim::Keys;
+
+#[derive(Component)]
+struct AboutPanel;
+
+pub struct AboutPlugin;
+
+impl Plugin for AboutPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(spawn_panel).add_system(toggle_panel);
+    }
+}
+
+fn spawn_panel(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("Kid Marker.ttf");
+    let style = TextStyle {
+        font,
+        font_size: 25.0,
+        color: Color::WHITE,
+    };
+    commands
+        .spawn((
+            TextBundle::from_sections([
+                TextSection::new("Conway's Game of Life\n", style.clone()),
+                TextSection::new(
+                    format!(
+                        "{} v{}\n",
+                        env!("CARGO_PKG_NAME"),
+                        env!("CARGO_PKG_VERSION")
+                    ),
+                    TextStyle {
+                        font_size: 18.0,
+                        ..style.clone()
+                    },
+                ),
+                // the web build has no downloads
+                TextSection::new(
+                    match cfg!(target_arch = "wasm32") {
+                        true => "",
+                        false => "Shift+F1 syncs the pattern library index\n",
+                    },
+                    TextStyle {
+                        font_size: 18.0,
+                        color: Color::GRAY,
+                        ..style.clone()
+                    },
+                ),
+                TextSection::new(
+                    "written in Rust / Bevy",
+                    TextStyle {
+                        font_size: 18.0,
+                        color: Color::GRAY,
+                        ..style
+                    },
+                ),
+            ])
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(10.0),
+                    right: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            }),
+            AboutPanel,
+            BesidePanel,
+        ))
+        .insert((
+            BackgroundColor(Color::rgba(0.05, 0.05, 0.05, 0.9)),
+            Visibility { is_visible: false },
+        ));
+}
+
+// F1       -> show/hide the about panel
+// Shift+F1 -> download the pattern index again, from library_index_url in settings.txt
+fn toggle_panel(
+    key: Keys,
+    mut syncs: EventWriter<SyncIndex>,
+    mut query: Query<&mut Visibility, With<AboutPanel>>,
+) {
+    if key.just_pressed(|k| &k.about) && key.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+        syncs.send(SyncIndex);
+    } else if key.just_pressed(|k| &k.about) {
+        for mut visibility in query.iter_mut() {
+            visibility.is_visible = !visibility.is_visible;
+        }
+    }
+}
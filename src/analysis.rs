@@ -0,0 +1,212 @@
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+use crate::board::Board;
+use crate::sim::Keys;
+
+// side in cells of the blocks the densities are measured over
+const SCALES: [u32; 5] = [1, 2, 4, 8, 16];
+// generations kept for the plot, one bar each
+const HISTORY: usize = 120;
+const PLOT_HEIGHT: f32 = 60.0;
+
+// order/disorder measures of one generation
+#[derive(Clone, Copy, Default)]
+struct Sample {
+    entropy: f32,                  // of the 2x2 block patterns, in bits per cell (0..=1)
+    variance: [f32; SCALES.len()], // of the block densities at each scale
+}
+
+// only computed while the panel is open
+#[derive(Resource, Default)]
+struct Analysis {
+    show: bool,
+    history: VecDeque<Sample>, // newest first
+}
+
+#[derive(Component)]
+struct AnalysisPanel;
+
+#[derive(Component)]
+struct AnalysisText;
+
+// the i-th newest sample
+#[derive(Component)]
+struct PlotBar(usize);
+
+pub struct AnalysisPlugin;
+
+impl Plugin for AnalysisPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Analysis>()
+            .add_startup_system(spawn_panel)
+            .add_system(handle_keyboard)
+            // after the generation has been stepped
+            .add_system_to_stage(CoreStage::PostUpdate, sample)
+            .add_system_to_stage(CoreStage::PostUpdate, update_panel.after(sample));
+    }
+}
+
+// complete s x s blocks only, a strip along the right or top edge is left out
+fn blocks(size: UVec2, s: u32) -> impl Iterator<Item = UVec2> {
+    (0..size.x / s).flat_map(move |bx| (0..size.y / s).map(move |by| UVec2::new(bx, by) * s))
+}
+
+// Shannon entropy of the 16 possible 2x2 patterns, 1 for a random soup at density 0.5
+fn block_entropy(map: &Board) -> f32 {
+    let mut counts = [0u32; 16];
+    for min in blocks(map.size(), 2) {
+        let pattern = (0..4).fold(0, |bits, i| {
+            bits | (map.alive(min.x + i % 2, min.y + i / 2) as usize) << i
+        });
+        counts[pattern] += 1;
+    }
+    let total: u32 = counts.iter().sum();
+    if total == 0 {
+        return 0.0;
+    }
+    let bits: f32 = counts
+        .iter()
+        .filter(|&&n| n > 0)
+        .map(|&n| {
+            let p = n as f32 / total as f32;
+            -p * p.log2()
+        })
+        .sum();
+    bits / 4.0
+}
+
+// how much the live fraction varies from block to block, high at the scale of the structures
+fn density_variance(map: &Board, s: u32) -> f32 {
+    let densities: Vec<f32> = blocks(map.size(), s)
+        .map(|min| {
+            let alive = (0..s * s)
+                .filter(|i| map.alive(min.x + i % s, min.y + i / s))
+                .count();
+            alive as f32 / (s * s) as f32
+        })
+        .collect();
+    if densities.is_empty() {
+        return 0.0;
+    }
+    let n = densities.len() as f32;
+    let mean = densities.iter().sum::<f32>() / n;
+    densities.iter().map(|d| (d - mean).powi(2)).sum::<f32>() / n
+}
+
+fn spawn_panel(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        bottom: Val::Px(10.0),
+                        left: Val::Px(10.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                visibility: Visibility { is_visible: false },
+                ..default()
+            },
+            AnalysisPanel,
+        ))
+        .with_children(|panel| {
+            // the text ends up below the plot, UI columns grow from the bottom
+            panel.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("Kid Marker.ttf"),
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                AnalysisText,
+            ));
+            panel
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(HISTORY as f32 * 3.0), Val::Px(PLOT_HEIGHT)),
+                        align_items: AlignItems::FlexStart,
+                        flex_direction: FlexDirection::RowReverse,
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                    ..default()
+                })
+                .with_children(|plot| {
+                    // newest on the right
+                    for i in 0..HISTORY {
+                        plot.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    size: Size::new(Val::Px(3.0), Val::Px(0.0)),
+                                    ..default()
+                                },
+                                background_color: Color::rgb(0.4, 0.7, 1.0).into(),
+                                ..default()
+                            },
+                            PlotBar(i),
+                        ));
+                    }
+                });
+        });
+}
+
+// F2 -> show/hide the entropy and density spectrum panel
+fn handle_keyboard(key: Keys, mut analysis: ResMut<Analysis>) {
+    if key.just_pressed(|k| &k.analysis) {
+        analysis.show = !analysis.show;
+        analysis.history.clear();
+    }
+}
+
+fn sample(map: Res<Board>, mut analysis: ResMut<Analysis>) {
+    // a freshly opened panel starts from the current board
+    if !analysis.show || (!map.is_changed() && !analysis.history.is_empty()) {
+        return;
+    }
+    let mut sample = Sample {
+        entropy: block_entropy(&map),
+        ..default()
+    };
+    for (v, &s) in sample.variance.iter_mut().zip(SCALES.iter()) {
+        *v = density_variance(&map, s);
+    }
+    analysis.history.push_front(sample);
+    analysis.history.truncate(HISTORY);
+}
+
+fn update_panel(
+    analysis: Res<Analysis>,
+    mut panel: Query<&mut Visibility, With<AnalysisPanel>>,
+    mut text: Query<&mut Text, With<AnalysisText>>,
+    mut bars: Query<(&PlotBar, &mut Style)>,
+) {
+    if !analysis.is_changed() {
+        return;
+    }
+    for mut visibility in panel.iter_mut() {
+        visibility.is_visible = analysis.show;
+    }
+    let (Some(now), Ok(mut text)) = (analysis.history.front(), text.get_single_mut()) else {
+        return;
+    };
+    let spectrum: Vec<String> = SCALES
+        .iter()
+        .zip(now.variance.iter())
+        .map(|(s, v)| format!("{}: {:.3}", s, v))
+        .collect();
+    text.sections[0].value = format!(
+        "block entropy {:.3} bits/cell\ndensity variance {}",
+        now.entropy,
+        spectrum.join("  ")
+    );
+    for (bar, mut style) in bars.iter_mut() {
+        let entropy = analysis.history.get(bar.0).map_or(0.0, |s| s.entropy);
+        style.size.height = Val::Px(entropy * PLOT_HEIGHT);
+    }
+}
@@ -0,0 +1,134 @@
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
+use image::{Rgb, RgbImage};
+use std::fs;
+use std::path::Path;
+
+use crate::board::{Boundary, FrozenRegions};
+use crate::rule::Ruleset;
+use crate::sim::{GridConfig, SimRng, StartupScene};
+
+const FONT: &[u8] = include_bytes!("../assets/Kid Marker.ttf");
+const CELL_PIXELS: u32 = 4;
+const LABEL_HEIGHT: u32 = 28;
+const STANDARD_DENSITY: f64 = 0.3;
+const STANDARD_SEED: u64 = 0;
+
+// one picture per rule, all started from the same board
+pub struct Atlas {
+    pub rules: Vec<Ruleset>,
+    pub out: String, // directory, created if missing
+    pub generations: u64,
+}
+
+// what every rule starts from, the same options the app starts with
+pub struct AtlasSetup {
+    pub scene: StartupScene,
+    pub seed: Option<u64>,
+    pub grid: GridConfig,
+    pub boundary: Boundary,
+}
+
+// "B3/S23" -> "B3_S23.png"
+fn file_name(rule: &Ruleset) -> String {
+    format!("{}.png", rule.to_string().replace('/', "_"))
+}
+
+// the board after `generations` with the rule written above it, colors as in the app
+fn render(atlas: &Atlas, setup: &AtlasSetup, rule: Ruleset, font: &FontRef) -> RgbImage {
+    // every rule gets the same rng stream, so they all start from the same board
+    let mut rng = SimRng::new(setup.seed);
+    let mut map = setup.scene.build(&setup.grid, &mut rng);
+    map.set_boundary(setup.boundary);
+    map.set_rule(rule);
+    let frozen = FrozenRegions::default();
+    for _ in 0..atlas.generations {
+        map = map.step_auto(&frozen);
+    }
+
+    let label = match rule.name() {
+        Some(name) => format!("{}  {}  gen {}", rule, name, atlas.generations),
+        None => format!("{}  gen {}", rule, atlas.generations),
+    };
+    let font = font.as_scaled(PxScale::from(LABEL_HEIGHT as f32 * 0.7));
+    let label_width: f32 = label
+        .chars()
+        .map(|c| font.h_advance(font.glyph_id(c)))
+        .sum();
+
+    // small boards get a wider strip so the label fits, the board stays on the left
+    let size = map.size();
+    let mut image = RgbImage::from_pixel(
+        (size.x * CELL_PIXELS).max(label_width.ceil() as u32 + 12),
+        size.y * CELL_PIXELS + LABEL_HEIGHT,
+        Rgb([13, 13, 13]),
+    );
+    for x in 0..size.x {
+        for y in 0..size.y {
+            let v = if map.alive(x, y) {
+                26
+            } else if map.dying(x, y) > 0 {
+                128
+            } else {
+                255
+            };
+            // image rows go down, the board's y goes up
+            let top = LABEL_HEIGHT + (size.y - 1 - y) * CELL_PIXELS;
+            for px in 0..CELL_PIXELS {
+                for py in 0..CELL_PIXELS {
+                    image.put_pixel(x * CELL_PIXELS + px, top + py, Rgb([v, v, v]));
+                }
+            }
+        }
+    }
+
+    let mut caret = 6.0;
+    for c in label.chars() {
+        let mut glyph = font.scaled_glyph(c);
+        glyph.position = ab_glyph::point(caret, font.ascent() + 2.0);
+        caret += font.h_advance(glyph.id);
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|x, y, coverage| {
+            let (x, y) = (
+                bounds.min.x as i32 + x as i32,
+                bounds.min.y as i32 + y as i32,
+            );
+            if x >= 0 && y >= 0 && (x as u32) < image.width() && (y as u32) < LABEL_HEIGHT {
+                let pixel = image.get_pixel_mut(x as u32, y as u32);
+                let v = (13.0 + (255.0 - 13.0) * coverage) as u8;
+                *pixel = Rgb([v.max(pixel.0[0]); 3]);
+            }
+        });
+    }
+    image
+}
+
+// Ok with a summary line, Err with the first io error
+// without --scene and --seed every rule starts from the same standard soup
+pub fn run(atlas: &Atlas, setup: &AtlasSetup) -> Result<String, String> {
+    let setup = AtlasSetup {
+        scene: match setup.scene {
+            StartupScene::Empty => StartupScene::Soup {
+                density: STANDARD_DENSITY,
+            },
+            ref scene => scene.clone(),
+        },
+        seed: Some(setup.seed.unwrap_or(STANDARD_SEED)),
+        ..*setup
+    };
+    let font = FontRef::try_from_slice(FONT).map_err(|e| format!("bad font: {}", e))?;
+    fs::create_dir_all(&atlas.out).map_err(|e| format!("could not create {}: {}", atlas.out, e))?;
+    for &rule in atlas.rules.iter() {
+        let path = Path::new(&atlas.out).join(file_name(&rule));
+        render(atlas, &setup, rule, &font)
+            .save(&path)
+            .map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+    }
+    Ok(format!(
+        "wrote {} rules to {}",
+        atlas.rules.len(),
+        atlas.out
+    ))
+}
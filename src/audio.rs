@@ -0,0 +1,218 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+
+use crate::settings::Settings;
+use crate::sim::{SimStats, Stabilization};
+
+// a theme is a directory in here with some of birth.ogg, death.ogg, stabilize.ogg and click.ogg
+const THEMES_DIR: &str = "assets/audio";
+
+// at most this many sounds started in the last VOICE_SECONDS, births and deaths past it are dropped
+const MAX_VOICES: usize = 6;
+const VOICE_SECONDS: f64 = 0.25;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sound {
+    Birth,     // a generation with births
+    Death,     // a generation with deaths
+    Stabilize, // the board stopped evolving
+    Click,     // a button of the menus or the control panel
+}
+
+impl Sound {
+    const ALL: [Sound; 4] = [Sound::Birth, Sound::Death, Sound::Stabilize, Sound::Click];
+
+    // the file in the theme directory is <name>.ogg, its volume the setting volume_<name>
+    fn name(&self) -> &'static str {
+        match self {
+            Sound::Birth => "birth",
+            Sound::Death => "death",
+            Sound::Stabilize => "stabilize",
+            Sound::Click => "click",
+        }
+    }
+
+    // births and deaths come every generation, so they are quieter
+    fn default_volume(&self) -> f32 {
+        match self {
+            Sound::Birth | Sound::Death => 0.3,
+            Sound::Stabilize => 0.8,
+            Sound::Click => 0.5,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct SoundThemes {
+    pub names: Vec<String>,     // the directories in THEMES_DIR, sorted
+    pub current: Option<usize>, // None is silence
+    volumes: HashMap<Sound, f32>,
+    handles: HashMap<Sound, Handle<AudioSource>>, // the sounds the current theme has
+    started: VecDeque<f64>,                       // when the recent sounds started, for the limiter
+}
+
+impl SoundThemes {
+    // the themes on disk, and the one picked last time with the volumes from the settings file
+    fn load() -> Self {
+        let mut names: Vec<String> = fs::read_dir(THEMES_DIR)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.path().is_dir())
+                    .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        let settings = Settings::load();
+        let picked = settings.get("sound_theme");
+        let volumes = Sound::ALL
+            .iter()
+            .map(|sound| {
+                let key = format!("volume_{}", sound.name());
+                let volume = settings.get(&key).and_then(|v| v.parse().ok());
+                (
+                    *sound,
+                    volume.unwrap_or(sound.default_volume()).clamp(0.0, 1.0),
+                )
+            })
+            .collect();
+        SoundThemes {
+            current: picked.and_then(|picked| names.iter().position(|name| name == picked)),
+            names,
+            volumes,
+            ..default()
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.current.map_or("off", |i| &self.names[i])
+    }
+
+    // off, then every theme, then off again
+    pub fn next(&mut self) {
+        self.current = match self.current {
+            None if !self.names.is_empty() => Some(0),
+            Some(i) if i + 1 < self.names.len() => Some(i + 1),
+            _ => None,
+        };
+    }
+
+    fn play(&mut self, sound: Sound, audio: &Audio, now: f64) {
+        let Some(handle) = self.handles.get(&sound) else {
+            return;
+        };
+        while self
+            .started
+            .front()
+            .is_some_and(|&t| now - t > VOICE_SECONDS)
+        {
+            self.started.pop_front();
+        }
+        // a dense board has births and deaths every frame, the rarer sounds always get through
+        let busy = self.started.len() >= MAX_VOICES;
+        if busy && matches!(sound, Sound::Birth | Sound::Death) {
+            return;
+        }
+        let volume = self
+            .volumes
+            .get(&sound)
+            .copied()
+            .unwrap_or(sound.default_volume());
+        audio.play_with_settings(handle.clone(), PlaybackSettings::ONCE.with_volume(volume));
+        self.started.push_back(now);
+    }
+}
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SoundThemes::load())
+            .add_system(load_theme)
+            .add_system(play_sounds.after(load_theme));
+    }
+}
+
+// (re)loads the sounds of the picked theme, and remembers the pick
+fn load_theme(assets: Res<AssetServer>, mut themes: ResMut<SoundThemes>) {
+    if !themes.is_changed() {
+        return;
+    }
+    let name = themes.current.map(|i| themes.names[i].clone());
+    let themes = themes.bypass_change_detection();
+    themes.handles.clear();
+    if let Some(name) = &name {
+        for sound in Sound::ALL {
+            let file = format!("audio/{}/{}.ogg", name, sound.name());
+            // a theme doesn't need every sound
+            if Path::new("assets").join(&file).exists() {
+                themes.handles.insert(sound, assets.load(&file));
+            }
+        }
+    }
+    let mut settings = Settings::load();
+    if settings.get("sound_theme") == name.as_deref() {
+        return;
+    }
+    match &name {
+        Some(name) => settings.set("sound_theme", name.clone()),
+        None => settings.remove("sound_theme"),
+    }
+    if let Err(e) = settings.save() {
+        warn!("{}", e);
+    }
+}
+
+// the generation and whether the board was evolving the last time it was looked at
+#[derive(Default)]
+struct Heard {
+    generation: u64,
+    evolving: bool,
+}
+
+fn play_sounds(
+    audio: Option<Res<Audio>>,
+    time: Res<Time>,
+    stats: Res<SimStats>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<Button>)>,
+    mut themes: ResMut<SoundThemes>,
+    mut heard: Local<Heard>,
+) {
+    // without an audio plugin, or with the sound off, nothing to do
+    let Some(audio) = audio else {
+        return;
+    };
+    if themes.current.is_none() {
+        return;
+    }
+    let themes = themes.bypass_change_detection();
+    let now = time.elapsed_seconds_f64();
+    if buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Clicked)
+    {
+        themes.play(Sound::Click, &audio, now);
+    }
+    if stats.generation == heard.generation {
+        return;
+    }
+    // one sound each per generation, however many cells were born or died
+    if stats.births > 0 {
+        themes.play(Sound::Birth, &audio, now);
+    }
+    if stats.deaths > 0 {
+        themes.play(Sound::Death, &audio, now);
+    }
+    let evolving = stats.stabilization == Stabilization::Evolving;
+    if heard.evolving && !evolving {
+        themes.play(Sound::Stabilize, &audio, now);
+    }
+    *heard = Heard {
+        generation: stats.generation,
+        evolving,
+    };
+}
@@ -0,0 +1,822 @@
+use bevy::math::{IVec2, UVec2};
+use std::hash::{Hash, Hasher};
+
+use crate::rule::{block, Ruleset};
+
+// the Life rules on a plain grid of cells, nothing in here knows about the ECS:
+// sim.rs draws and edits a Board resource, trace/atlas/--verify step one headless
+
+// inclusive rectangle of cells
+#[derive(Clone, Copy)]
+pub struct Region {
+    pub min: UVec2,
+    pub max: UVec2,
+}
+
+impl Region {
+    pub fn new(a: UVec2, b: UVec2) -> Self {
+        Region {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        (self.min.x..=self.max.x).contains(&x) && (self.min.y..=self.max.y).contains(&y)
+    }
+
+    // the part of the region on a board of `size`, None when none of it is
+    pub fn clamped(&self, size: UVec2) -> Option<Region> {
+        if self.min.x >= size.x || self.min.y >= size.y {
+            return None;
+        }
+        Some(Region {
+            min: self.min,
+            max: self.max.min(size - UVec2::ONE),
+        })
+    }
+
+    pub fn corners(&self) -> [UVec2; 4] {
+        [
+            self.min,
+            UVec2::new(self.max.x, self.min.y),
+            self.max,
+            UVec2::new(self.min.x, self.max.y),
+        ]
+    }
+}
+
+// areas excluded from the simulation, their cells keep whatever state they had
+// with an `active` region set, everything outside of it is frozen as well
+#[derive(Default, Clone)]
+pub struct FrozenRegions {
+    pub regions: Vec<Region>,
+    pub active: Option<Region>,
+}
+
+impl FrozenRegions {
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        self.regions.iter().any(|r| r.contains(x, y))
+            || self.active.is_some_and(|r| !r.contains(x, y))
+    }
+}
+
+// the board area only, the rows reserved for the text are not part of it
+#[derive(Clone)]
+pub struct Board {
+    vec: Vec<Vec<bool>>,
+    dying: Vec<Vec<u8>>, // decay state of each cell under a Generations rule, 0 when not dying
+    age: Vec<Vec<u32>>,  // generations each live cell has been alive, 0 for newborn and dead cells
+    data: Option<CellData>, // optional extra value per cell (energy, team, age...)
+    boundary: Boundary,  // copied from the Boundary resource
+    rule: Ruleset,       // copied from the Ruleset resource
+    hash: u64,           // XOR of cell_key over the live cells, kept up to date by `set`
+    // live cells in each column and each row and the dying cells, kept up to date like the hash
+    // so the population and the bounding box don't need a pass over the area
+    columns: Vec<u32>,
+    rows: Vec<u32>,
+    dying_cells: usize,
+}
+
+// key of a cell in decay state k (0 for k = 0), boards are well under 2^24 cells wide
+// so putting k in the top byte of x never collides with a live key
+fn dying_key(x: u32, y: u32, k: u8) -> u64 {
+    match k {
+        0 => 0,
+        k => cell_key(x | (k as u32) << 24, y),
+    }
+}
+
+// Zobrist key of a cell, splitmix64 of its coordinates so no table is needed and every platform agrees
+fn cell_key(x: u32, y: u32) -> u64 {
+    let mut z = ((x as u64) << 32 | y as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// what lies past the edges of the board
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    #[default]
+    Finite, // dead cells
+    Toroidal, // the opposite edge, left-right and top-bottom
+}
+
+// the type of the values of the data channel
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DataKind {
+    U8, // saturates at 0 and 255
+    F32,
+}
+
+// extra per-cell channel that rules and scripts can read/write
+#[derive(Clone)]
+enum CellData {
+    U8(Vec<Vec<u8>>),
+    F32(Vec<Vec<f32>>),
+}
+
+// `v` copied into a `size` grid with every value moved by `offset`
+fn moved<T: Copy + Default>(v: &[Vec<T>], size: UVec2, offset: IVec2) -> Vec<Vec<T>> {
+    let mut out = vec![vec![T::default(); size.y as usize]; size.x as usize];
+    for (x, col) in v.iter().enumerate() {
+        for (y, &value) in col.iter().enumerate() {
+            let to = IVec2::new(x as i32, y as i32) + offset;
+            if to.cmpge(IVec2::ZERO).all() && to.cmplt(size.as_ivec2()).all() {
+                out[to.x as usize][to.y as usize] = value;
+            }
+        }
+    }
+    out
+}
+
+impl CellData {
+    fn new(kind: DataKind, size: UVec2) -> Self {
+        match kind {
+            DataKind::U8 => CellData::U8(vec![vec![0; size.y as usize]; size.x as usize]),
+            DataKind::F32 => CellData::F32(vec![vec![0.0; size.y as usize]; size.x as usize]),
+        }
+    }
+
+    fn kind(&self) -> DataKind {
+        match self {
+            CellData::U8(_) => DataKind::U8,
+            CellData::F32(_) => DataKind::F32,
+        }
+    }
+
+    // same kind of channel at another size, see Board::resized
+    fn resized(&self, size: UVec2, offset: IVec2) -> Self {
+        match self {
+            CellData::U8(v) => CellData::U8(moved(v, size, offset)),
+            CellData::F32(v) => CellData::F32(moved(v, size, offset)),
+        }
+    }
+
+    fn get(&self, x: u32, y: u32) -> f32 {
+        match self {
+            CellData::U8(v) => v[x as usize][y as usize] as f32,
+            CellData::F32(v) => v[x as usize][y as usize],
+        }
+    }
+
+    // u8 channels saturate, so `value` is clamped to 0..=255
+    fn set(&mut self, x: u32, y: u32, value: f32) {
+        match self {
+            CellData::U8(v) => v[x as usize][y as usize] = value.clamp(0.0, 255.0) as u8,
+            CellData::F32(v) => v[x as usize][y as usize] = value,
+        }
+    }
+
+    fn clear(&mut self) {
+        match self {
+            CellData::U8(v) => v.iter_mut().for_each(|col| col.fill(0)),
+            CellData::F32(v) => v.iter_mut().for_each(|col| col.fill(0.0)),
+        }
+    }
+}
+
+impl Board {
+    pub fn new(size: UVec2) -> Self {
+        Board {
+            vec: vec![vec![false; size.y as usize]; size.x as usize],
+            dying: vec![vec![0; size.y as usize]; size.x as usize],
+            age: vec![vec![0; size.y as usize]; size.x as usize],
+            data: None,
+            boundary: Boundary::Finite,
+            rule: Ruleset::default(),
+            hash: 0,
+            columns: vec![0; size.x as usize],
+            rows: vec![0; size.y as usize],
+            dying_cells: 0,
+        }
+    }
+
+    // the channel is opt-in, the built-in rules don't need it
+    pub fn with_data(size: UVec2, kind: DataKind) -> Self {
+        let mut map = Board::new(size);
+        map.enable_data(kind);
+        map
+    }
+
+    // the same board at another size with every cell moved by `offset`, cells past the new edges are dropped
+    pub fn resized(&self, size: UVec2, offset: IVec2) -> Self {
+        let mut map = Board::new(size);
+        map.vec = moved(&self.vec, size, offset);
+        map.dying = moved(&self.dying, size, offset);
+        map.age = moved(&self.age, size, offset);
+        map.data = self.data.as_ref().map(|d| d.resized(size, offset));
+        map.boundary = self.boundary;
+        map.rule = self.rule;
+        map.rehash();
+        map
+    }
+
+    pub fn has_data(&self) -> bool {
+        self.data.is_some()
+    }
+
+    pub fn data_kind(&self) -> Option<DataKind> {
+        self.data.as_ref().map(CellData::kind)
+    }
+
+    // a zeroed channel, a channel of the same kind is kept as it is
+    pub fn enable_data(&mut self, kind: DataKind) {
+        if self.data_kind() != Some(kind) {
+            self.data = Some(CellData::new(kind, self.size()));
+        }
+    }
+
+    pub fn disable_data(&mut self) {
+        self.data = None;
+    }
+
+    pub fn data(&self, x: u32, y: u32) -> Option<f32> {
+        self.data.as_ref().map(|d| d.get(x, y))
+    }
+
+    // ignored without a channel
+    pub fn set_data(&mut self, x: u32, y: u32, value: f32) {
+        if let Some(d) = self.data.as_mut() {
+            d.set(x, y, value);
+        }
+    }
+
+    // the 8 cells around (x, y), past an edge they are either dropped or wrapped around
+    // on boards narrower than 3 cells a wrapped neighbour can show up twice, like on a real torus
+    pub fn neighbourhood(&self, x: u32, y: u32) -> impl Iterator<Item = (u32, u32)> {
+        let (x, y) = (x as i32, y as i32);
+        let size = self.size().as_ivec2();
+        let wrap = self.boundary == Boundary::Toroidal;
+        ((x - 1)..(x + 2))
+            .flat_map(move |i| ((y - 1)..(y + 2)).map(move |j| (i, j)))
+            .filter(move |&(i, j)| i != x || j != y)
+            .filter_map(move |(i, j)| {
+                if wrap {
+                    Some((i.rem_euclid(size.x), j.rem_euclid(size.y)))
+                } else if i >= 0 && j >= 0 && i < size.x && j < size.y {
+                    Some((i, j))
+                } else {
+                    None
+                }
+            })
+            .map(|(i, j)| (i as u32, j as u32))
+    }
+
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.boundary = boundary;
+    }
+
+    pub fn rule(&self) -> Ruleset {
+        self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Ruleset) {
+        self.rule = rule;
+    }
+
+    pub fn neighbours(&self, x: u32, y: u32) -> u8 {
+        self.neighbourhood(x, y)
+            .filter(|&(i, j)| self.vec[i as usize][j as usize])
+            .count() as u8
+    }
+
+    // whether the cell is alive next generation, leaving decay aside
+    pub fn next_alive(&self, x: u32, y: u32) -> bool {
+        if self.rule.is_totalistic() {
+            return self
+                .rule
+                .next_state(self.alive(x, y), self.neighbours(x, y));
+        }
+        let size = self.size().as_ivec2();
+        let wrap = self.boundary == Boundary::Toroidal;
+        let cells = block(|dx, dy| {
+            let (i, j) = (x as i32 + dx, y as i32 + dy);
+            if wrap {
+                self.alive(i.rem_euclid(size.x) as u32, j.rem_euclid(size.y) as u32)
+            } else {
+                i >= 0 && j >= 0 && i < size.x && j < size.y && self.alive(i as u32, j as u32)
+            }
+        });
+        self.rule.next_block(cells)
+    }
+
+    // every write to a cell goes through here or set_dying so the hash stays incremental
+    // setting a cell alive or dead ends its decay
+    pub fn set(&mut self, x: u32, y: u32, alive: bool) {
+        self.set_dying(x, y, 0);
+        let cell = &mut self.vec[x as usize][y as usize];
+        if *cell != alive {
+            *cell = alive;
+            self.hash ^= cell_key(x, y);
+            self.age[x as usize][y as usize] = 0;
+            let (column, row) = (&mut self.columns[x as usize], &mut self.rows[y as usize]);
+            match alive {
+                true => (*column += 1, *row += 1),
+                false => (*column -= 1, *row -= 1),
+            };
+        }
+    }
+
+    pub fn age(&self, x: u32, y: u32) -> u32 {
+        self.age[x as usize][y as usize]
+    }
+
+    // ages are not part of the state, they don't change the hash
+    pub fn set_age(&mut self, x: u32, y: u32, age: u32) {
+        if self.alive(x, y) {
+            self.age[x as usize][y as usize] = age;
+        }
+    }
+
+    // ages `generations` after `prev` for the cells of min..=max outside `frozen`:
+    // cells alive on both boards count on, a cell that died and came back in between can't be told
+    // apart from one that lived on
+    fn age_from(
+        &mut self,
+        prev: &Board,
+        generations: u64,
+        (min, max): (UVec2, UVec2),
+        frozen: &FrozenRegions,
+    ) {
+        let generations = generations.min(u32::MAX as u64) as u32;
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                if frozen.contains(x, y) {
+                    continue;
+                }
+                let age = match (prev.alive(x, y), self.alive(x, y)) {
+                    (true, true) => prev.age(x, y).saturating_add(generations),
+                    _ => 0,
+                };
+                self.age[x as usize][y as usize] = age;
+            }
+        }
+    }
+
+    // for backends that build the next board from scratch, see age_from
+    pub fn carry_ages(&mut self, prev: &Board, generations: u64) {
+        let max = self.size() - UVec2::ONE;
+        self.age_from(
+            prev,
+            generations,
+            (UVec2::ZERO, max),
+            &FrozenRegions::default(),
+        );
+    }
+
+    pub fn set_dying(&mut self, x: u32, y: u32, k: u8) {
+        let cell = &mut self.dying[x as usize][y as usize];
+        if *cell != k {
+            self.hash ^= dying_key(x, y, *cell) ^ dying_key(x, y, k);
+            match (*cell, k) {
+                (0, _) => self.dying_cells += 1,
+                (_, 0) => self.dying_cells -= 1,
+                _ => {}
+            }
+            *cell = k;
+        }
+    }
+
+    // full recompute, for bulk replacements of the cells
+    fn rehash(&mut self) {
+        self.hash = 0;
+        for (x, col) in self.vec.iter().enumerate() {
+            for (y, &alive) in col.iter().enumerate() {
+                let (x, y) = (x as u32, y as u32);
+                if alive {
+                    self.hash ^= cell_key(x, y);
+                }
+                self.hash ^= dying_key(x, y, self.dying(x, y));
+            }
+        }
+        self.recount();
+    }
+
+    // the counts alone, for writes that kept the hash up to date themselves
+    fn recount(&mut self) {
+        self.columns.fill(0);
+        self.rows.fill(0);
+        for (x, col) in self.vec.iter().enumerate() {
+            for (y, _) in col.iter().enumerate().filter(|(_, &alive)| alive) {
+                self.columns[x] += 1;
+                self.rows[y] += 1;
+            }
+        }
+        self.dying_cells = self.dying.iter().flatten().filter(|&&k| k > 0).count();
+    }
+
+    // decay state of a Generations rule, 0 when the cell is alive, dead or the rule has none
+    pub fn dying(&self, x: u32, y: u32) -> u8 {
+        self.dying[x as usize][y as usize]
+    }
+
+    // no live and no dying cells
+    pub fn is_empty(&self) -> bool {
+        self.population() == 0 && self.dying_cells == 0
+    }
+
+    pub fn alive(&self, x: u32, y: u32) -> bool {
+        self.vec[x as usize][y as usize]
+    }
+
+    pub fn size(&self) -> UVec2 {
+        UVec2::new(self.vec.len() as u32, self.vec[0].len() as u32)
+    }
+
+    pub fn population(&self) -> usize {
+        self.columns.iter().map(|&n| n as usize).sum()
+    }
+
+    // (min, max) corners of the live cells, None on an empty board
+    pub fn bounding_box(&self) -> Option<(UVec2, UVec2)> {
+        let first = |counts: &[u32]| counts.iter().position(|&n| n > 0).map(|i| i as u32);
+        let last = |counts: &[u32]| counts.iter().rposition(|&n| n > 0).map(|i| i as u32);
+        Some((
+            UVec2::new(first(&self.columns)?, first(&self.rows)?),
+            UVec2::new(last(&self.columns)?, last(&self.rows)?),
+        ))
+    }
+
+    // same value on every platform and toolchain, traces recorded on desktop can be checked on wasm
+    pub fn state_hash(&self) -> u64 {
+        self.hash
+    }
+
+    // hash of the live cells relative to their bounding box, equal for translated copies
+    pub fn shape_hash(&self, (min, max): (UVec2, UVec2)) -> u64 {
+        let mut hasher = StableHasher::default();
+        (max - min).hash(&mut hasher);
+        for x in min.x..=max.x {
+            self.vec[x as usize][min.y as usize..=max.y as usize].hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    // (min, max) corners of the cells a step visits,
+    // outside the active region everything is frozen, no need to visit it
+    fn stepped(&self, frozen: &FrozenRegions) -> (UVec2, UVec2) {
+        let size = self.size();
+        match frozen.active {
+            Some(region) => (region.min, region.max.min(size - UVec2::ONE)),
+            None => (UVec2::ZERO, size - UVec2::ONE),
+        }
+    }
+
+    // (alive, dying state) of a cell that is not frozen in the next generation
+    // the rule only does integer math
+    fn next_cell(&self, x: u32, y: u32) -> (bool, u8) {
+        let decay_states = self.rule.decay_states();
+        let k = self.dying(x, y);
+        if k > 0 {
+            return (false, if k < decay_states { k + 1 } else { 0 });
+        }
+        let alive = self.next_alive(x, y);
+        let dying = self.alive(x, y) && !alive && decay_states > 0;
+        (alive, dying as u8)
+    }
+
+    // the next generation, cells inside `frozen` keep their state
+    // cells are visited in a fixed x-then-y order
+    pub fn step(&self, frozen: &FrozenRegions) -> Board {
+        let mut next = self.clone();
+        let (min, max) = self.stepped(frozen);
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                if frozen.contains(x, y) {
+                    continue;
+                }
+                let (alive, k) = self.next_cell(x, y);
+                next.set(x, y, alive);
+                next.set_dying(x, y, k);
+                if self.alive(x, y) && !alive {
+                    next.set_data(x, y, 0.0);
+                }
+            }
+        }
+        next.age_from(self, 1, (min, max), frozen);
+        next
+    }
+
+    // same result as `step`, with bands of columns computed on the rayon thread pool
+    // every band returns its part of the hash update, xor doesn't care in which order they come back
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn step_parallel(&self, frozen: &FrozenRegions) -> Board {
+        use rayon::prelude::*;
+
+        let mut next = self.clone();
+        let (min, max) = self.stepped(frozen);
+        let (x0, x1) = (min.x as usize, max.x as usize);
+        let delta = next.vec[x0..=x1]
+            .par_iter_mut()
+            .zip(next.dying[x0..=x1].par_iter_mut())
+            .enumerate()
+            .with_min_len(PARALLEL_BAND)
+            .map(|(i, (col, dying))| {
+                let x = (x0 + i) as u32;
+                let mut delta = 0;
+                for y in min.y..=max.y {
+                    if frozen.contains(x, y) {
+                        continue;
+                    }
+                    let (alive, k) = self.next_cell(x, y);
+                    let cell = &mut col[y as usize];
+                    if *cell != alive {
+                        *cell = alive;
+                        delta ^= cell_key(x, y);
+                    }
+                    let old = &mut dying[y as usize];
+                    if *old != k {
+                        delta ^= dying_key(x, y, *old) ^ dying_key(x, y, k);
+                        *old = k;
+                    }
+                }
+                delta
+            })
+            .reduce(|| 0, |a, b| a ^ b);
+        next.hash ^= delta;
+        next.recount();
+        if next.data.is_some() {
+            for x in min.x..=max.x {
+                for y in min.y..=max.y {
+                    if self.alive(x, y) && !next.alive(x, y) && !frozen.contains(x, y) {
+                        next.set_data(x, y, 0.0);
+                    }
+                }
+            }
+        }
+        next.age_from(self, 1, (min, max), frozen);
+        next
+    }
+
+    // wasm has no threads to spread the work over
+    #[cfg(target_arch = "wasm32")]
+    pub fn step_parallel(&self, frozen: &FrozenRegions) -> Board {
+        self.step(frozen)
+    }
+
+    // step_parallel for big boards, `step` otherwise
+    pub fn step_auto(&self, frozen: &FrozenRegions) -> Board {
+        if self.size().x * self.size().y >= PARALLEL_MIN_CELLS {
+            return self.step_parallel(frozen);
+        }
+        self.step(frozen)
+    }
+
+    // same size, boundary and rule with every cell dead
+    pub fn cleared(&self) -> Board {
+        let mut map = self.clone();
+        map.clear();
+        map
+    }
+
+    // kills every cell, the data channel (if any) is kept but zeroed
+    pub fn clear(&mut self) {
+        self.vec.iter_mut().for_each(|col| col.fill(false));
+        self.dying.iter_mut().for_each(|col| col.fill(0));
+        self.age.iter_mut().for_each(|col| col.fill(0));
+        self.hash = 0;
+        self.columns.fill(0);
+        self.rows.fill(0);
+        self.dying_cells = 0;
+        if let Some(d) = self.data.as_mut() {
+            d.clear();
+        }
+    }
+}
+
+// a way of computing generations, Board::step is the reference the others have to match
+pub trait SimBackend {
+    // false when the board uses something the backend doesn't implement
+    fn supports(&self, map: &Board, frozen: &FrozenRegions) -> bool;
+    // the board `generations` later, with the ages carried like Board::step does
+    fn advance(&mut self, map: &Board, frozen: &FrozenRegions, generations: u64) -> Board;
+    // the same on the board itself, a backend that knows what changed only writes those cells
+    fn advance_in_place(&mut self, map: &mut Board, frozen: &FrozenRegions, generations: u64) {
+        *map = self.advance(map, frozen, generations);
+    }
+}
+
+// one cell at a time, runs every board
+pub struct NaiveBackend;
+
+// columns per rayon task, narrower bands cost more in scheduling than they save
+#[cfg(not(target_arch = "wasm32"))]
+const PARALLEL_BAND: usize = 8;
+// boards smaller than this are stepped on one thread, see `step_auto`
+const PARALLEL_MIN_CELLS: u32 = 10_000;
+
+// Board::step_parallel
+pub struct ParallelBackend;
+
+impl SimBackend for ParallelBackend {
+    fn supports(&self, _map: &Board, _frozen: &FrozenRegions) -> bool {
+        true
+    }
+
+    fn advance(&mut self, map: &Board, frozen: &FrozenRegions, generations: u64) -> Board {
+        let mut map = map.clone();
+        for _ in 0..generations {
+            map = map.step_parallel(frozen);
+        }
+        map
+    }
+}
+
+impl SimBackend for NaiveBackend {
+    fn supports(&self, _map: &Board, _frozen: &FrozenRegions) -> bool {
+        true
+    }
+
+    fn advance(&mut self, map: &Board, frozen: &FrozenRegions, generations: u64) -> Board {
+        let mut map = map.clone();
+        for _ in 0..generations {
+            map = map.step(frozen);
+        }
+        map
+    }
+}
+
+// FNV-1a, unlike DefaultHasher its output is specified, and usize is always hashed as 8 bytes
+pub struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_u32(&mut self, n: u32) {
+        self.write(&n.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.write(&n.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, n: usize) {
+        self.write_u64(n as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashlife::HashLife;
+    use crate::sim::{SimRng, StartupScene};
+    use crate::sparse::SparseBackend;
+
+    // (width, height, toroidal), hashlife wraps only power of two squares
+    const SIZES: [(u32, u32, bool); 6] = [
+        (64, 64, true),
+        (50, 40, false),
+        (128, 128, true),
+        (100, 100, false),
+        (16, 16, true),
+        (5, 5, false),
+    ];
+    const GENERATIONS: [u64; 6] = [1, 3, 7, 64, 100, 1000];
+
+    fn soup(width: u32, height: u32, toroidal: bool) -> Board {
+        let scene = StartupScene::Soup { density: 0.3 };
+        let mut map = scene.build_board(UVec2::new(width, height), &mut SimRng::new(Some(7)));
+        if toroidal {
+            map.set_boundary(Boundary::Toroidal);
+        }
+        map
+    }
+
+    fn cells(map: &Board) -> Vec<bool> {
+        map.vec.iter().flatten().copied().collect()
+    }
+
+    // the population and bounding box from a pass over every cell, what the counts stand in for
+    fn scanned(map: &Board) -> (usize, Option<(UVec2, UVec2)>) {
+        let live: Vec<UVec2> = (0..map.size().x)
+            .flat_map(|x| (0..map.size().y).map(move |y| UVec2::new(x, y)))
+            .filter(|p| map.alive(p.x, p.y))
+            .collect();
+        let bounds = live.iter().fold(None, |bounds, &p| match bounds {
+            Some((min, max)) => Some((p.min(min), p.max(max))),
+            None => Some((p, p)),
+        });
+        (live.len(), bounds)
+    }
+
+    // every backend against Board::step, one generation at a time
+    fn check(name: &str, backend: impl Fn() -> Box<dyn SimBackend>) {
+        let frozen = FrozenRegions::default();
+        for (width, height, toroidal) in SIZES {
+            let start = soup(width, height, toroidal);
+            let mut expected = start.clone();
+            let mut stepped = 0;
+            for generations in GENERATIONS {
+                while stepped < generations {
+                    expected = expected.step(&frozen);
+                    stepped += 1;
+                }
+                let mut backend = backend();
+                assert!(
+                    backend.supports(&start, &frozen),
+                    "{} on {}x{}",
+                    name,
+                    width,
+                    height
+                );
+                let got = backend.advance(&start, &frozen, generations);
+                assert_eq!(
+                    (cells(&got), got.state_hash()),
+                    (cells(&expected), expected.state_hash()),
+                    "{} on {}x{} toroidal {} after {} generations",
+                    name,
+                    width,
+                    height,
+                    toroidal,
+                    generations
+                );
+                assert_eq!((got.population(), got.bounding_box()), scanned(&got));
+            }
+        }
+    }
+
+    #[test]
+    fn naive_matches_step() {
+        check("naive", || Box::new(NaiveBackend));
+    }
+
+    #[test]
+    fn parallel_matches_step() {
+        check("parallel", || Box::new(ParallelBackend));
+    }
+
+    #[test]
+    fn hashlife_matches_step() {
+        check("hashlife", || Box::<HashLife>::default());
+    }
+
+    #[test]
+    fn sparse_matches_step() {
+        check("sparse", || Box::<SparseBackend>::default());
+    }
+
+    // the board the sim steps in place, a generation at a time, counts and all
+    #[test]
+    fn sparse_in_place_matches_step() {
+        let frozen = FrozenRegions::default();
+        let mut backend = SparseBackend::default();
+        let mut map = soup(100, 100, false);
+        let mut expected = map.clone();
+        for _ in 0..200 {
+            backend.advance_in_place(&mut map, &frozen, 1);
+            expected = expected.step(&frozen);
+            assert_eq!(cells(&map), cells(&expected));
+            assert_eq!((map.population(), map.bounding_box()), scanned(&map));
+        }
+        map.clear();
+        assert!(map.is_empty());
+    }
+
+    // one long jump lands where the naive backend gets one generation at a time
+    #[test]
+    fn hashlife_jumps_like_naive() {
+        let frozen = FrozenRegions::default();
+        let start = soup(128, 128, true);
+        let naive = NaiveBackend.advance(&start, &frozen, 1000);
+        let jumped = HashLife::default().advance(&start, &frozen, 1000);
+        assert_eq!(naive.state_hash(), jumped.state_hash());
+    }
+
+    // a glider is itself again after 4 generations, a cell down and to the right
+    #[test]
+    fn glider_moves() {
+        let mut map = Board::new(UVec2::new(10, 10));
+        for (x, y) in [(1, 2), (2, 1), (0, 0), (1, 0), (2, 0)] {
+            map.set(x + 3, y + 3, true);
+        }
+        let frozen = FrozenRegions::default();
+        let moved = (0..4).fold(map.clone(), |map, _| map.step(&frozen));
+        assert_eq!(moved.population(), 5);
+        assert_eq!(
+            moved
+                .resized(UVec2::new(10, 10), IVec2::new(-1, 1))
+                .state_hash(),
+            map.state_hash()
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use bevy::input::mouse::MouseWheel;
+use bevy::prelude::*;
+
+use crate::sim::Keys;
+
+// widest brush, in cells
+const MAX_BRUSH: u32 = 25;
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum BrushShape {
+    #[default]
+    Square,
+    Circle,
+}
+
+// what the draw tool paints or erases around the cursor
+#[derive(Resource)]
+pub struct Brush {
+    pub size: u32, // width in cells, always odd so the cursor is the center
+    pub shape: BrushShape,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Brush {
+            size: 1,
+            shape: BrushShape::Square,
+        }
+    }
+}
+
+impl Brush {
+    // a single cell, clicks toggle it instead of painting
+    pub fn is_single(&self) -> bool {
+        self.size == 1
+    }
+
+    // every cell under the brush centered on `center`, the ones past the board left out
+    pub fn cells(&self, center: UVec2, board: UVec2) -> Vec<UVec2> {
+        let radius = (self.size / 2) as i32;
+        // the half cell rounds the disc, a circle of 3 is the full 3x3
+        let reach = (radius as f32 + 0.5).powi(2);
+        let mut cells = Vec::new();
+        for dx in -radius..=radius {
+            for dy in -radius..=radius {
+                if self.shape == BrushShape::Circle && (dx * dx + dy * dy) as f32 > reach {
+                    continue;
+                }
+                let cell = center.as_ivec2() + IVec2::new(dx, dy);
+                if cell.cmpge(IVec2::ZERO).all() && cell.cmplt(board.as_ivec2()).all() {
+                    cells.push(cell.as_uvec2());
+                }
+            }
+        }
+        cells
+    }
+
+    pub fn label(&self) -> String {
+        match self.shape {
+            _ if self.is_single() => String::new(),
+            BrushShape::Square => format!("  [brush {}x{}]", self.size, self.size),
+            BrushShape::Circle => format!("  [brush circle {}]", self.size),
+        }
+    }
+}
+
+pub struct BrushPlugin;
+
+impl Plugin for BrushPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Brush>()
+            .add_system(handle_keyboard)
+            .add_system(handle_scroll);
+    }
+}
+
+// Z -> switch the brush between a square and a circle
+fn handle_keyboard(key: Keys, mut brush: ResMut<Brush>) {
+    if key.just_pressed(|k| &k.brush_shape) {
+        brush.shape = match brush.shape {
+            BrushShape::Square => BrushShape::Circle,
+            BrushShape::Circle => BrushShape::Square,
+        };
+    }
+}
+
+// Ctrl + scroll -> grow/shrink the brush by a cell on each side
+fn handle_scroll(key: Keys, mut wheel: EventReader<MouseWheel>, mut brush: ResMut<Brush>) {
+    let ctrl = key.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    for event in wheel.iter() {
+        if !ctrl || event.y == 0.0 {
+            continue;
+        }
+        brush.size = match event.y > 0.0 {
+            true => (brush.size + 2).min(MAX_BRUSH),
+            false => brush.size.saturating_sub(2).max(1),
+        };
+    }
+}
@@ -0,0 +1,43 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::sim::{GridConfig, Keys, SimRng, ToggleCell};
+
+// how many random cells one perturbation flips
+#[derive(Resource, Clone, Copy)]
+pub struct Chaos(pub u32);
+
+impl Default for Chaos {
+    fn default() -> Self {
+        Chaos(10)
+    }
+}
+
+pub struct ChaosPlugin;
+
+impl Plugin for ChaosPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Chaos>().add_system(handle_keyboard);
+    }
+}
+
+// X -> flip random cells, sent as regular toggles so they end up in the recipe too
+fn handle_keyboard(
+    key: Keys,
+    chaos: Res<Chaos>,
+    grid: Res<GridConfig>,
+    mut rng: ResMut<SimRng>,
+    mut toggles: EventWriter<ToggleCell>,
+) {
+    // Ctrl + X cuts the selection
+    let ctrl = key.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    if key.just_pressed(|k| &k.chaos) && !ctrl {
+        let board = grid.board_size();
+        for _ in 0..chaos.0 {
+            toggles.send(ToggleCell {
+                x: rng.0.gen_range(0..board.x),
+                y: rng.0.gen_range(0..board.y),
+            });
+        }
+    }
+}
@@ -0,0 +1,523 @@
+use bevy::app::AppExit;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+use crate::board::{Board, Boundary, DataKind};
+use crate::rle::{self, Pattern};
+use crate::rule::Ruleset;
+use crate::sim::{GridConfig, Keys, RestoreBoard, SimRng, SimStats, State, MAX_GRID, MIN_GRID};
+use crate::toast::Toast;
+
+const CHECKPOINT_FILE: &str = "checkpoint.gz";
+// saved from the pause menu, in the same format but never removed on exit
+const SAVE_FILE: &str = "save.gz";
+
+// bumped whenever a field is added or changes meaning, with a migration from the version before
+const FORMAT_VERSION: u32 = 2;
+
+// the "key value" lines of the header
+type Fields = HashMap<String, String>;
+
+// MIGRATIONS[v] turns the fields of a version v checkpoint into those of version v + 1
+const MIGRATIONS: [fn(&mut Fields); FORMAT_VERSION as usize] = [
+    // the first checkpoints had no version line, the fields are the same
+    |_| {},
+    // version 2 keeps the per-cell data channel
+    |fields| {
+        fields.insert("data".to_string(), "none".to_string());
+    },
+];
+
+// --checkpoint <seconds>, None writes no checkpoints
+#[derive(Resource, Default)]
+pub struct CheckpointInterval(pub Option<f32>);
+
+// everything a run continues from
+struct Checkpoint {
+    grid: GridConfig,
+    generation: u64,
+    seed: u64, // the rng is reseeded with this when the checkpoint is taken
+    rule: Ruleset,
+    boundary: Boundary,
+    map: Board,
+}
+
+impl Checkpoint {
+    // the rng can't be written out, so it continues from a fresh seed that can
+    fn take(map: &Board, generation: u64, grid: GridConfig, rng: &mut SimRng) -> Self {
+        Checkpoint {
+            grid,
+            generation,
+            seed: rng.reseed(),
+            rule: map.rule(),
+            boundary: map.boundary(),
+            map: map.clone(),
+        }
+    }
+
+    // a few "key value" lines, the dying cells and the data channel, then the board as RLE
+    fn to_text(&self) -> String {
+        let size = self.map.size();
+        let mut alive = Vec::new();
+        let mut dying = Vec::new();
+        let mut data = vec![match self.map.data_kind() {
+            None => "none",
+            Some(DataKind::U8) => "u8",
+            Some(DataKind::F32) => "f32",
+        }
+        .to_string()];
+        for x in 0..size.x {
+            for y in 0..size.y {
+                if self.map.alive(x, y) {
+                    alive.push(UVec2::new(x, y));
+                }
+                if self.map.dying(x, y) > 0 {
+                    dying.push(format!("{},{},{}", x, y, self.map.dying(x, y)));
+                }
+                // only the cells that have a value, like the dying ones
+                if let Some(value) = self.map.data(x, y).filter(|&v| v != 0.0) {
+                    data.push(format!("{},{},{}", x, y, value));
+                }
+            }
+        }
+        let boundary = match self.boundary {
+            Boundary::Finite => "finite",
+            Boundary::Toroidal => "toroidal",
+        };
+        format!(
+            "# bevy_conway checkpoint\nversion {}\nsize {}x{}\ngeneration {}\nrng {:016x}\nrule {}\nboundary {}\ndying {}\ndata {}\n{}",
+            FORMAT_VERSION,
+            self.grid.width,
+            self.grid.height,
+            self.generation,
+            self.seed,
+            self.rule,
+            boundary,
+            dying.join(" "),
+            data.join(" "),
+            rle::to_text(&Pattern { size, alive }, &self.rule.to_string())
+        )
+    }
+
+    fn parse(text: &str) -> Result<Checkpoint, String> {
+        let (header, body) = text
+            .find("\nx =")
+            .map(|i| text.split_at(i + 1))
+            .ok_or("the checkpoint has no board")?;
+        let mut fields = Fields::new();
+        for line in header.lines().filter(|l| !l.starts_with('#')) {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            fields.insert(key.to_string(), value.to_string());
+        }
+        migrate(&mut fields)?;
+        let field = |key: &str| {
+            fields
+                .get(key)
+                .map(String::as_str)
+                .ok_or(format!("missing {}", key))
+        };
+        let bad = |key: &str| format!("bad {}", key);
+
+        let (width, height) = field("size")?.split_once('x').ok_or_else(|| bad("size"))?;
+        let grid = GridConfig {
+            width: width.parse().map_err(|_| bad("size"))?,
+            height: height.parse().map_err(|_| bad("size"))?,
+        };
+        // a hand-edited or damaged size would make an empty or a huge board
+        let sides = MIN_GRID..=MAX_GRID;
+        if !sides.contains(&grid.width) || !sides.contains(&grid.height) {
+            return Err(format!(
+                "size {}x{} is not between {2}x{2} and {3}x{3}",
+                grid.width, grid.height, MIN_GRID, MAX_GRID
+            ));
+        }
+        let rule = Ruleset::parse(field("rule")?)?;
+        let boundary = match field("boundary")? {
+            "finite" => Boundary::Finite,
+            "toroidal" => Boundary::Toroidal,
+            _ => return Err(bad("boundary")),
+        };
+        let pattern = rle::parse(body)?;
+        let board = grid.board_size();
+        if pattern.size != board {
+            return Err(format!(
+                "the board does not match size {}x{}",
+                grid.width, grid.height
+            ));
+        }
+        let mut map = Board::new(board);
+        map.set_boundary(boundary);
+        map.set_rule(rule);
+        for cell in pattern.alive.iter() {
+            map.set(cell.x, cell.y, true);
+        }
+        for (x, y, k) in cell_values(field("dying")?, board).ok_or_else(|| bad("dying"))? {
+            map.set_dying(x, y, k);
+        }
+        let (kind, values) = field("data")?
+            .split_once(' ')
+            .unwrap_or((field("data")?, ""));
+        match kind {
+            "none" => {}
+            "u8" => map.enable_data(DataKind::U8),
+            "f32" => map.enable_data(DataKind::F32),
+            _ => return Err(bad("data")),
+        }
+        for (x, y, value) in cell_values(values, board).ok_or_else(|| bad("data"))? {
+            map.set_data(x, y, value);
+        }
+        Ok(Checkpoint {
+            grid,
+            generation: field("generation")?
+                .parse()
+                .map_err(|_| bad("generation"))?,
+            seed: u64::from_str_radix(field("rng")?, 16).map_err(|_| bad("rng"))?,
+            rule,
+            boundary,
+            map,
+        })
+    }
+
+    // written to a temporary file first and renamed over `path`,
+    // a crash mid-write leaves the last one intact
+    fn save(&self, path: &str) -> Result<(), String> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        let bytes = encoder
+            .write_all(self.to_text().as_bytes())
+            .and_then(|()| encoder.finish())
+            .map_err(|e| e.to_string())?;
+        let temp = format!("{}.tmp", path);
+        fs::write(&temp, bytes)
+            .and_then(|()| fs::rename(&temp, path))
+            .map_err(|e| format!("could not write {}: {}", path, e))
+    }
+
+    // None when there is no such file, for the checkpoint: the app exited cleanly last time
+    fn load(path: &str) -> Option<Result<Checkpoint, String>> {
+        let bytes = fs::read(path).ok()?;
+        let mut text = String::new();
+        Some(
+            GzDecoder::new(bytes.as_slice())
+                .read_to_string(&mut text)
+                .map_err(|e| e.to_string())
+                .and_then(|_| Checkpoint::parse(&text))
+                .map_err(|e| format!("{} is damaged: {}", path, e)),
+        )
+    }
+}
+
+// "x,y,value" triples separated by spaces, None if one is malformed or off the board
+fn cell_values<T: FromStr>(text: &str, board: UVec2) -> Option<Vec<(u32, u32, T)>> {
+    text.split_whitespace()
+        .map(|cell| {
+            let mut parts = cell.split(',');
+            let x: u32 = parts.next()?.parse().ok()?;
+            let y: u32 = parts.next()?.parse().ok()?;
+            let value = parts.next()?.parse().ok()?;
+            (parts.next().is_none() && x < board.x && y < board.y).then_some((x, y, value))
+        })
+        .collect()
+}
+
+// the fields of an older format brought up to FORMAT_VERSION, newer ones can't be read
+fn migrate(fields: &mut Fields) -> Result<(), String> {
+    let version: u32 = match fields.get("version") {
+        Some(version) => version.parse().map_err(|_| "bad version".to_string())?,
+        None => 0,
+    };
+    if version > FORMAT_VERSION {
+        return Err(format!(
+            "it was written by a newer version (format {}, this one reads up to {})",
+            version, FORMAT_VERSION
+        ));
+    }
+    for step in &MIGRATIONS[version as usize..] {
+        step(fields);
+    }
+    fields.insert("version".to_string(), FORMAT_VERSION.to_string());
+    Ok(())
+}
+
+// a checkpoint found at startup, waiting for Y
+#[derive(Resource, Default)]
+struct Offer(Option<Checkpoint>);
+
+#[derive(Resource)]
+struct CheckpointTimer(Timer);
+
+// writes the board to save.gz, from the pause menu
+pub struct SaveBoard;
+
+// continues from save.gz, from the pause menu
+pub struct LoadBoard;
+
+// continue from save.gz at startup instead of the scene, `--scene last`
+#[derive(Resource, Default)]
+pub struct ResumeSave(pub bool);
+
+// what save.gz holds, for the pause menu to show, None without a readable one
+#[derive(Resource, Default)]
+pub struct SavedBoard(pub Option<(u64, Board)>); // generation and board
+
+// what continuing from a checkpoint replaces
+#[derive(SystemParam)]
+struct Resume<'w, 's> {
+    rng: ResMut<'w, SimRng>,
+    rule: ResMut<'w, Ruleset>,
+    boundary: ResMut<'w, Boundary>,
+    restores: EventWriter<'w, 's, RestoreBoard>,
+}
+
+impl Resume<'_, '_> {
+    fn from(&mut self, checkpoint: Checkpoint) {
+        *self.rng = SimRng::new(Some(checkpoint.seed));
+        *self.rule = checkpoint.rule;
+        *self.boundary = checkpoint.boundary;
+        info!("resumed generation {}", checkpoint.generation);
+        self.restores.send(RestoreBoard {
+            map: checkpoint.map,
+            generation: checkpoint.generation,
+            grid: checkpoint.grid,
+        });
+    }
+}
+
+pub struct CheckpointPlugin;
+
+impl Plugin for CheckpointPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CheckpointInterval>()
+            .init_resource::<Offer>()
+            .init_resource::<SavedBoard>()
+            .init_resource::<ResumeSave>()
+            .add_event::<SaveBoard>()
+            .add_event::<LoadBoard>()
+            .add_startup_system(find_checkpoint)
+            .add_startup_system(find_save)
+            .add_startup_system(resume_save)
+            .add_system(handle_keyboard)
+            .add_system(write_checkpoint)
+            .add_system(save_board)
+            .add_system(load_board)
+            .add_system_to_stage(CoreStage::Last, remove_on_exit);
+    }
+}
+
+fn find_checkpoint(
+    mut commands: Commands,
+    interval: Res<CheckpointInterval>,
+    mut offer: ResMut<Offer>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if let Some(seconds) = interval.0 {
+        let timer = Timer::from_seconds(seconds, TimerMode::Repeating);
+        commands.insert_resource(CheckpointTimer(timer));
+    }
+    match Checkpoint::load(CHECKPOINT_FILE) {
+        None => {}
+        Some(Ok(checkpoint)) => {
+            toasts.send(Toast(format!(
+                "the last run stopped at generation {}, press Y to resume it",
+                checkpoint.generation
+            )));
+            offer.0 = Some(checkpoint);
+        }
+        Some(Err(e)) => toasts.send(Toast(e)),
+    }
+}
+
+fn find_save(mut saved: ResMut<SavedBoard>) {
+    if let Some(Ok(checkpoint)) = Checkpoint::load(SAVE_FILE) {
+        saved.0 = Some((checkpoint.generation, checkpoint.map));
+    }
+}
+
+fn resume_save(resume_save: Res<ResumeSave>, mut resume: Resume, mut toasts: EventWriter<Toast>) {
+    if !resume_save.0 {
+        return;
+    }
+    match Checkpoint::load(SAVE_FILE) {
+        Some(Ok(checkpoint)) => resume.from(checkpoint),
+        Some(Err(e)) => toasts.send(Toast(e)),
+        None => toasts.send(Toast(format!("there is no {} to continue", SAVE_FILE))),
+    }
+}
+
+// Y -> resume the checkpoint found at startup, until the board is first played
+fn handle_keyboard(key: Keys, state: Res<State>, mut offer: ResMut<Offer>, mut resume: Resume) {
+    if state.0 {
+        offer.0 = None;
+    }
+    if !key.just_pressed(|k| &k.resume) {
+        return;
+    }
+    if let Some(checkpoint) = offer.0.take() {
+        resume.from(checkpoint);
+    }
+}
+
+fn write_checkpoint(
+    time: Res<Time>,
+    timer: Option<ResMut<CheckpointTimer>>,
+    map: Res<Board>,
+    stats: Res<SimStats>,
+    grid: Res<GridConfig>,
+    mut rng: ResMut<SimRng>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let Some(mut timer) = timer else {
+        return;
+    };
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    let checkpoint = Checkpoint::take(&map, stats.generation, *grid, &mut rng);
+    if let Err(e) = checkpoint.save(CHECKPOINT_FILE) {
+        toasts.send(Toast(e));
+    }
+}
+
+fn save_board(
+    mut saves: EventReader<SaveBoard>,
+    map: Res<Board>,
+    stats: Res<SimStats>,
+    grid: Res<GridConfig>,
+    mut rng: ResMut<SimRng>,
+    mut saved: ResMut<SavedBoard>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if saves.iter().count() == 0 {
+        return;
+    }
+    let checkpoint = Checkpoint::take(&map, stats.generation, *grid, &mut rng);
+    match checkpoint.save(SAVE_FILE) {
+        Ok(()) => {
+            saved.0 = Some((stats.generation, map.clone()));
+            toasts.send(Toast(format!(
+                "generation {} saved to {}",
+                stats.generation, SAVE_FILE
+            )))
+        }
+        Err(e) => toasts.send(Toast(e)),
+    }
+}
+
+fn load_board(
+    mut loads: EventReader<LoadBoard>,
+    mut resume: Resume,
+    mut toasts: EventWriter<Toast>,
+) {
+    if loads.iter().count() == 0 {
+        return;
+    }
+    match Checkpoint::load(SAVE_FILE) {
+        Some(Ok(checkpoint)) => resume.from(checkpoint),
+        Some(Err(e)) => toasts.send(Toast(e)),
+        None => toasts.send(Toast(format!("there is no {} to load", SAVE_FILE))),
+    }
+}
+
+// a clean exit leaves no checkpoint, so the next start only offers one after a crash
+fn remove_on_exit(exits: EventReader<AppExit>, timer: Option<Res<CheckpointTimer>>) {
+    if !exits.is_empty() && timer.is_some() {
+        let _ = fs::remove_file(CHECKPOINT_FILE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRID: GridConfig = GridConfig {
+        width: 20,
+        height: 24,
+    };
+
+    fn checkpoint() -> Checkpoint {
+        let mut map = Board::new(GRID.board_size());
+        map.set_rule(Ruleset::parse("B2/S/4").unwrap());
+        map.set_boundary(Boundary::Toroidal);
+        map.enable_data(DataKind::F32);
+        for (x, y) in [(1, 1), (2, 1), (5, 7), (19, 0)] {
+            map.set(x, y, true);
+        }
+        map.set_dying(3, 3, 2);
+        map.set_data(4, 4, 0.5);
+        Checkpoint::take(&map, 42, GRID, &mut SimRng::new(Some(3)))
+    }
+
+    // the board and every header line with one of them replaced
+    fn edited(key: &str, value: Option<&str>) -> String {
+        checkpoint()
+            .to_text()
+            .lines()
+            .filter_map(|line| match line.split_once(' ') {
+                Some((k, _)) if k == key => value.map(|v| format!("{} {}", key, v)),
+                _ => Some(line.to_string()),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn round_trips() {
+        let saved = checkpoint();
+        let read = Checkpoint::parse(&saved.to_text()).unwrap();
+        assert!(read.grid == saved.grid);
+        assert_eq!(read.generation, 42);
+        assert_eq!(read.seed, saved.seed);
+        assert_eq!(read.rule, saved.rule);
+        assert!(read.boundary == Boundary::Toroidal);
+        assert_eq!(read.map.state_hash(), saved.map.state_hash());
+        assert_eq!(read.map.population(), 4);
+        assert_eq!(read.map.dying(3, 3), 2);
+        assert_eq!(read.map.data_kind(), Some(DataKind::F32));
+        assert_eq!(read.map.data(4, 4), Some(0.5));
+        assert_eq!(read.map.data(5, 5), Some(0.0));
+    }
+
+    #[test]
+    fn migrates_older_versions() {
+        // version 1 had no data channel, the first checkpoints no version line either
+        let v1 = edited("data", None).replace("version 2\n", "version 1\n");
+        let v0 = v1.replace("version 1\n", "");
+        for text in [v1, v0] {
+            let read = Checkpoint::parse(&text).unwrap();
+            assert_eq!(read.map.data_kind(), None);
+            assert_eq!(read.map.population(), 4);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_checkpoints() {
+        let bad = [
+            ("no board", "version 2\nsize 20x24".to_string()),
+            ("newer", edited("version", Some("3"))),
+            ("version", edited("version", Some("two"))),
+            ("missing size", edited("size", None)),
+            ("size", edited("size", Some("20"))),
+            ("empty", edited("size", Some("0x0"))),
+            ("too big", edited("size", Some("1000x1000"))),
+            ("other size", edited("size", Some("30x30"))),
+            ("missing rule", edited("rule", None)),
+            ("rule", edited("rule", Some("B3/S9"))),
+            ("boundary", edited("boundary", Some("sphere"))),
+            ("generation", edited("generation", Some("-1"))),
+            ("rng", edited("rng", Some("xyz"))),
+            ("dying", edited("dying", Some("3,3"))),
+            ("dying off the board", edited("dying", Some("20,0,1"))),
+            ("data kind", edited("data", Some("u16 1,1,1"))),
+            ("data value", edited("data", Some("f32 1,1,a"))),
+        ];
+        for (what, text) in bad {
+            assert!(Checkpoint::parse(&text).is_err(), "{}", what);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use bevy::prelude::IVec2;
+
+use crate::atlas::Atlas;
+use crate::board::Boundary;
+use crate::chaos::Chaos;
+use crate::convert::Convert;
+use crate::density::DensityMap;
+use crate::drift::Drift;
+use crate::gpu;
+use crate::idle::IdleTimeout;
+use crate::library;
+use crate::rule::Ruleset;
+use crate::sim::{GridConfig, StartupScene, MAX_GRID, MIN_GRID};
+use crate::stress::Stress;
+use crate::trace::{TraceBackend, TraceCommand};
+
+const USAGE: &str = "usage: bevy_app [convert <in> <out>] [--scene empty|soup|stripes|checker|seeds|<pattern>|last] [--density <0..1>] [--density-map <png>] [--seed <u64>] [--drift <dx>,<dy>,<every>] [--chaos <cells>] [--size <width>x<height>] [--boundary finite|toroidal] [--rule <B3/S23>] [--checkpoint <seconds>] [--idle <seconds>] [--record-trace <file> [--generations <n>] | --verify <file>] [--backend naive|hashlife|sparse|parallel] [--worker] [--validate <backend>,<backend>|gpu,<backend>] [--gpu <side> [--gpu-stats <generations>]] [--atlas presets|<rule>,<rule>.. [--out <dir>]] [--new-rule-template <name>]";
+
+// command line options, wasm builds never get any and always use the defaults
+#[derive(Default)]
+pub struct Args {
+    pub scene: StartupScene,
+    pub resume: bool, // --scene last, continue from save.gz
+    pub seed: Option<u64>,
+    pub drift: Drift,
+    pub chaos: Chaos,
+    pub grid: GridConfig,
+    pub boundary: Boundary,
+    pub rule: Ruleset,
+    pub checkpoint: Option<f32>, // seconds between checkpoints
+    pub idle: IdleTimeout,
+    pub trace: Option<TraceCommand>, // run headless instead of opening the window
+    pub backend: TraceBackend,
+    pub worker: bool, // step the board on its own thread while playing
+    pub validate: Option<(TraceBackend, TraceBackend)>, // step every generation on both and compare
+    pub atlas: Option<Atlas>, // write one picture per rule instead of opening the window
+    pub gpu: Option<u32>, // side of the square board run by the compute shader instead
+    pub gpu_stats: Option<u64>, // generations between readbacks of the gpu board
+    pub gpu_validate: Option<TraceBackend>, // cpu backend every readback is diffed with
+    pub new_rule: Option<String>, // scaffold a rule plugin crate and exit
+    pub convert: Option<Convert>, // convert a pattern file between formats and exit
+}
+
+pub fn parse() -> Result<Args, String> {
+    let mut args = Args::default();
+    let mut scene = "empty".to_string();
+    let mut rule_given = false;
+    let mut density = 0.3;
+    let mut generations = 100;
+    let mut density_map = None;
+    let mut out = "atlas".to_string();
+
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().ok_or(format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--scene" => scene = value()?,
+            "--density" => {
+                density = value()?
+                    .parse()
+                    .map_err(|_| "density must be a number".to_string())?;
+                if !(0.0..=1.0).contains(&density) {
+                    return Err("density must be between 0 and 1".to_string());
+                }
+            }
+            "--density-map" => density_map = Some(value()?),
+            "--seed" => {
+                args.seed = Some(
+                    value()?
+                        .parse()
+                        .map_err(|_| "seed must be an unsigned integer".to_string())?,
+                )
+            }
+            "--drift" => args.drift = parse_drift(&value()?)?,
+            "--chaos" => {
+                args.chaos = Chaos(
+                    value()?
+                        .parse()
+                        .map_err(|_| "chaos must be an unsigned integer".to_string())?,
+                )
+            }
+            "--size" => args.grid = parse_size(&value()?)?,
+            "--rule" => {
+                args.rule = Ruleset::parse(&value()?)?;
+                rule_given = true;
+            }
+            "--boundary" => {
+                args.boundary = match value()?.as_str() {
+                    "finite" => Boundary::Finite,
+                    "toroidal" => Boundary::Toroidal,
+                    other => return Err(format!("unknown boundary {}\n{}", other, USAGE)),
+                }
+            }
+            "--checkpoint" => {
+                let seconds: f32 = value()?
+                    .parse()
+                    .map_err(|_| "checkpoint interval must be a number of seconds".to_string())?;
+                if seconds < 1.0 {
+                    return Err("checkpoint interval must be at least 1 second".to_string());
+                }
+                args.checkpoint = Some(seconds);
+            }
+            "--idle" => {
+                let seconds: f32 = value()?
+                    .parse()
+                    .map_err(|_| "idle timeout must be a number of seconds".to_string())?;
+                // 0 keeps the app redrawing at full rate
+                args.idle = IdleTimeout((seconds > 0.0).then_some(seconds));
+            }
+            "--record-trace" => {
+                args.trace = Some(TraceCommand::Record {
+                    path: value()?,
+                    generations: 0,
+                })
+            }
+            "--generations" => {
+                generations = value()?
+                    .parse()
+                    .map_err(|_| "generations must be an unsigned integer".to_string())?
+            }
+            "--verify" => args.trace = Some(TraceCommand::Verify { path: value()? }),
+            "--atlas" => {
+                let rules = match value()?.as_str() {
+                    "presets" => Ruleset::presets().collect(),
+                    list => list
+                        .split(',')
+                        .map(Ruleset::parse)
+                        .collect::<Result<Vec<_>, _>>()?,
+                };
+                args.atlas = Some(Atlas {
+                    rules,
+                    out: String::new(),
+                    generations: 0,
+                });
+            }
+            "--out" => out = value()?,
+            "--new-rule-template" => args.new_rule = Some(value()?),
+            // formats go by extension: .rle, .cells, .png in and out, .mc and .npy out only
+            "convert" => {
+                args.convert = Some(Convert {
+                    input: value()?,
+                    output: value()?,
+                    rule: None,
+                })
+            }
+            "--gpu" => {
+                let side = value()?
+                    .parse()
+                    .map_err(|_| "gpu board side must be an unsigned integer".to_string())?;
+                if !(gpu::MIN_SIDE..=gpu::MAX_SIDE).contains(&side) {
+                    return Err(format!(
+                        "gpu board side must be between {} and {}",
+                        gpu::MIN_SIDE,
+                        gpu::MAX_SIDE
+                    ));
+                }
+                args.gpu = Some(side);
+            }
+            "--gpu-stats" => {
+                let every: u64 = value()?
+                    .parse()
+                    .map_err(|_| "gpu stats interval must be an unsigned integer".to_string())?;
+                if every == 0 {
+                    return Err("gpu stats interval must be at least 1 generation".to_string());
+                }
+                args.gpu_stats = Some(every);
+            }
+            "--backend" => args.backend = backend(&value()?)?,
+            "--worker" => args.worker = true,
+            "--validate" => {
+                let pair = value()?;
+                let (a, b) = pair.split_once(',').ok_or(format!(
+                    "--validate takes two backends, as in naive,hashlife\n{}",
+                    USAGE
+                ))?;
+                match (a, b) {
+                    ("gpu", other) | (other, "gpu") => args.gpu_validate = Some(backend(other)?),
+                    _ => args.validate = Some((backend(a)?, backend(b)?)),
+                }
+            }
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ => return Err(format!("unknown argument {}\n{}", arg, USAGE)),
+        }
+    }
+
+    args.scene = match scene.as_str() {
+        "empty" => StartupScene::Empty,
+        "soup" => StartupScene::Soup { density },
+        "last" => {
+            args.resume = true;
+            StartupScene::Empty
+        }
+        name => match (Stress::parse(name), library::find(name)) {
+            (Some(stress), _) => StartupScene::Stress(stress),
+            (None, Some(pattern)) => StartupScene::Pattern(pattern),
+            (None, None) => return Err(format!("unknown scene {}\n{}", scene, USAGE)),
+        },
+    };
+    // the saved run needs the window, the headless commands start from a scene
+    let headless = args.trace.is_some() || args.atlas.is_some() || args.gpu.is_some();
+    if args.resume && headless {
+        return Err("--scene last only opens the window".to_string());
+    }
+    // the gpu board runs without the app, it is compared with one cpu backend
+    match (
+        args.gpu.is_some(),
+        args.validate.is_some(),
+        args.gpu_validate.is_some(),
+    ) {
+        (true, true, _) => {
+            return Err("the gpu board validates with --validate gpu,<backend>".to_string())
+        }
+        (false, _, true) => return Err("--validate gpu,<backend> needs --gpu <side>".to_string()),
+        _ => {}
+    }
+    // a density map replaces whatever scene was asked for
+    if let Some(path) = density_map {
+        args.scene = StartupScene::Density(DensityMap::load(&path)?);
+    }
+    if let Some(TraceCommand::Record { generations: g, .. }) = args.trace.as_mut() {
+        *g = generations;
+    }
+    if let Some(convert) = args.convert.as_mut() {
+        convert.rule = rule_given.then_some(args.rule);
+    }
+    if let Some(atlas) = args.atlas.as_mut() {
+        atlas.out = out;
+        atlas.generations = generations;
+    }
+    Ok(args)
+}
+
+fn backend(name: &str) -> Result<TraceBackend, String> {
+    TraceBackend::parse(name).ok_or(format!("unknown backend {}\n{}", name, USAGE))
+}
+
+// "dx,dy,every", enables drift from the start
+fn parse_drift(value: &str) -> Result<Drift, String> {
+    let parts: Vec<&str> = value.split(',').collect();
+    let bad = || format!("drift must look like <dx>,<dy>,<every>, got {}", value);
+    let [dx, dy, every] = parts.as_slice() else {
+        return Err(bad());
+    };
+    let every: u64 = every.parse().map_err(|_| bad())?;
+    if every == 0 {
+        return Err("drift interval must be at least 1".to_string());
+    }
+    Ok(Drift {
+        enabled: true,
+        vector: IVec2::new(
+            dx.parse().map_err(|_| bad())?,
+            dy.parse().map_err(|_| bad())?,
+        ),
+        every,
+    })
+}
+
+// "<width>x<height>", the height includes the rows reserved for the text
+fn parse_size(value: &str) -> Result<GridConfig, String> {
+    let bad = || format!("size must look like <width>x<height>, got {}", value);
+    let (width, height) = value.split_once('x').ok_or_else(bad)?;
+    let grid = GridConfig {
+        width: width.parse().map_err(|_| bad())?,
+        height: height.parse().map_err(|_| bad())?,
+    };
+    let range = MIN_GRID..=MAX_GRID;
+    if !range.contains(&grid.width) || !range.contains(&grid.height) {
+        return Err(format!(
+            "size must be between {} and {} on each side",
+            MIN_GRID, MAX_GRID
+        ));
+    }
+    Ok(grid)
+}
@@ -0,0 +1,274 @@
+use bevy::ecs::system::SystemParam;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+use crate::menu::Screen;
+use crate::rule::Ruleset;
+use crate::settings::Settings;
+use crate::sim::{self, ClearBoard, KeyBindings, RandomizeBoard, SimRng, SingleStep};
+use crate::toast::Toast;
+
+// settings.txt lines "macro.<key> <command>, <command>..", e.g.
+// "macro.7 clear, fill 0.3 seed 7, rule Day&Night, play"
+const MACRO_PREFIX: &str = "macro.";
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
+    Clear,
+    Fill { density: f64, seed: Option<u64> }, // a soup, drawn from `seed` when there is one
+    Rule(Ruleset),
+    Play,
+    Pause,
+    Step, // one generation
+}
+
+impl Command {
+    // "fill 0.3 seed 7", any case, "set rule .." works as well as "rule .."
+    pub fn parse(text: &str) -> Result<Command, String> {
+        let text = text.trim();
+        let text = text.strip_prefix("set ").unwrap_or(text).trim();
+        let (name, rest) = text.split_once(' ').unwrap_or((text, ""));
+        let rest = rest.trim();
+        let exact = |command| match rest.is_empty() {
+            true => Ok(command),
+            false => Err(format!("{} takes nothing after it", name)),
+        };
+        match name.to_lowercase().as_str() {
+            "clear" => exact(Command::Clear),
+            "play" => exact(Command::Play),
+            "pause" => exact(Command::Pause),
+            "step" => exact(Command::Step),
+            "rule" => Ruleset::named(rest)
+                .map_or_else(|| Ruleset::parse(rest), Ok)
+                .map(Command::Rule),
+            "fill" => {
+                let mut words = rest.split_whitespace();
+                let density = words
+                    .next()
+                    .and_then(|d| d.parse::<f64>().ok())
+                    .filter(|d| (0.0..=1.0).contains(d))
+                    .ok_or(format!(
+                        "fill needs a density between 0 and 1, got {}",
+                        rest
+                    ))?;
+                let seed = match (words.next(), words.next(), words.next()) {
+                    (None, _, _) => None,
+                    (Some("seed"), Some(seed), None) => {
+                        Some(seed.parse().map_err(|_| format!("bad seed {}", seed))?)
+                    }
+                    _ => return Err(format!("fill takes a density and seed <n>, got {}", rest)),
+                };
+                Ok(Command::Fill { density, seed })
+            }
+            _ => Err(format!("unknown command {}", name)),
+        }
+    }
+}
+
+// commands separated by commas, run in order
+pub fn parse_macro(text: &str) -> Result<Vec<Command>, String> {
+    text.split(',')
+        .filter(|command| !command.trim().is_empty())
+        .map(Command::parse)
+        .collect()
+}
+
+// a macro of settings.txt and the keys that run it, part of KeyBindings
+#[derive(Clone)]
+pub struct KeyMacro {
+    pub keys: Vec<KeyCode>,
+    pub commands: Vec<Command>,
+}
+
+// "F12", "M", "7", the keys free enough to bind a macro to
+fn key_code(name: &str) -> Option<KeyCode> {
+    const LETTERS: [KeyCode; 26] = [
+        KeyCode::A,
+        KeyCode::B,
+        KeyCode::C,
+        KeyCode::D,
+        KeyCode::E,
+        KeyCode::F,
+        KeyCode::G,
+        KeyCode::H,
+        KeyCode::I,
+        KeyCode::J,
+        KeyCode::K,
+        KeyCode::L,
+        KeyCode::M,
+        KeyCode::N,
+        KeyCode::O,
+        KeyCode::P,
+        KeyCode::Q,
+        KeyCode::R,
+        KeyCode::S,
+        KeyCode::T,
+        KeyCode::U,
+        KeyCode::V,
+        KeyCode::W,
+        KeyCode::X,
+        KeyCode::Y,
+        KeyCode::Z,
+    ];
+    const DIGITS: [KeyCode; 10] = [
+        KeyCode::Key0,
+        KeyCode::Key1,
+        KeyCode::Key2,
+        KeyCode::Key3,
+        KeyCode::Key4,
+        KeyCode::Key5,
+        KeyCode::Key6,
+        KeyCode::Key7,
+        KeyCode::Key8,
+        KeyCode::Key9,
+    ];
+    const FUNCTION: [KeyCode; 12] = [
+        KeyCode::F1,
+        KeyCode::F2,
+        KeyCode::F3,
+        KeyCode::F4,
+        KeyCode::F5,
+        KeyCode::F6,
+        KeyCode::F7,
+        KeyCode::F8,
+        KeyCode::F9,
+        KeyCode::F10,
+        KeyCode::F11,
+        KeyCode::F12,
+    ];
+    let name = name.to_uppercase();
+    let mut chars = name.chars();
+    match (chars.next()?, chars.as_str()) {
+        (c @ 'A'..='Z', "") => Some(LETTERS[c as usize - 'A' as usize]),
+        (c @ '0'..='9', "") => Some(DIGITS[c as usize - '0' as usize]),
+        ('F', n) => FUNCTION
+            .get(n.parse::<usize>().ok()?.checked_sub(1)?)
+            .copied(),
+        _ => None,
+    }
+}
+
+// commands of the macros pressed, one a frame so each one's events are handled before the next
+#[derive(Resource, Default)]
+struct Queue(VecDeque<Command>);
+
+pub struct CommandPlugin;
+
+impl Plugin for CommandPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Queue>()
+            .add_startup_system(load_macros)
+            // ahead of every system a command goes through, so each one sees it the same frame
+            .add_system_to_stage(CoreStage::PreUpdate, run_macros.after(InputSystem));
+    }
+}
+
+// a macro that doesn't parse is left out, with a toast saying why
+fn load_macros(mut bindings: ResMut<KeyBindings>, mut toasts: EventWriter<Toast>) {
+    let settings = Settings::load();
+    for (key, value) in settings.with_prefix(MACRO_PREFIX) {
+        let Some(code) = key_code(key) else {
+            toasts.send(Toast(format!("macro on {}: unknown key", key)));
+            continue;
+        };
+        match parse_macro(value) {
+            Ok(commands) => bindings.macros.push(KeyMacro {
+                keys: vec![code],
+                commands,
+            }),
+            Err(e) => toasts.send(Toast(format!("macro on {}: {}", key, e))),
+        }
+    }
+}
+
+// on the board only, the menus have their own use for the keys
+fn run_macros(
+    key: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    screen: Res<State<Screen>>,
+    mut queue: ResMut<Queue>,
+    mut run: Run,
+) {
+    if *screen.current() != Screen::Board {
+        return;
+    }
+    for binding in bindings.macros.iter() {
+        if key.any_just_pressed(binding.keys.iter().copied()) {
+            queue.0.extend(binding.commands.iter().cloned());
+        }
+    }
+    let Some(command) = queue.0.pop_front() else {
+        return;
+    };
+    run.command(command);
+}
+
+// what the commands act on
+#[derive(SystemParam)]
+struct Run<'w, 's> {
+    clears: EventWriter<'w, 's, ClearBoard>,
+    randomizes: EventWriter<'w, 's, RandomizeBoard>,
+    rng: ResMut<'w, SimRng>,
+    rule: ResMut<'w, Ruleset>,
+    playing: ResMut<'w, sim::State>,
+    single: ResMut<'w, SingleStep>,
+}
+
+impl Run<'_, '_> {
+    fn command(&mut self, command: Command) {
+        match command {
+            Command::Clear => self.clears.send(ClearBoard),
+            Command::Fill { density, seed } => {
+                if let Some(seed) = seed {
+                    *self.rng = SimRng::new(Some(seed));
+                }
+                self.randomizes.send(RandomizeBoard { density });
+            }
+            Command::Rule(rule) => *self.rule = rule,
+            Command::Play => self.playing.0 = true,
+            Command::Pause => self.playing.0 = false,
+            Command::Step => self.single.0 = true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_macro() {
+        let commands = parse_macro("clear, fill 0.3 seed 7, set rule Day&Night, play").unwrap();
+        assert_eq!(
+            commands,
+            vec![
+                Command::Clear,
+                Command::Fill {
+                    density: 0.3,
+                    seed: Some(7)
+                },
+                Command::Rule(Ruleset::parse("B3678/S34678").unwrap()),
+                Command::Play,
+            ]
+        );
+        assert_eq!(
+            Command::parse("rule B36/S23"),
+            Ok(Command::Rule(Ruleset::parse("B36/S23").unwrap()))
+        );
+        assert!(Command::parse("fill 2").is_err());
+        assert!(Command::parse("fill 0.5 seed").is_err());
+        assert!(Command::parse("play now").is_err());
+        assert!(Command::parse("jump").is_err());
+    }
+
+    #[test]
+    fn names_keys() {
+        assert_eq!(key_code("F12"), Some(KeyCode::F12));
+        assert_eq!(key_code("m"), Some(KeyCode::M));
+        assert_eq!(key_code("7"), Some(KeyCode::Key7));
+        assert_eq!(key_code("F13"), None);
+        assert_eq!(key_code("F0"), None);
+        assert_eq!(key_code("Space"), None);
+    }
+}
@@ -0,0 +1,204 @@
+use bevy::prelude::*;
+
+use crate::board::Board;
+use crate::hud::HudElement;
+use crate::panel::BesidePanel;
+use crate::sim::{BoardView, Keys, SimStats, SIZE};
+
+// which neighbours count as touching when grouping live cells
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    Four,
+    #[default]
+    Eight,
+}
+
+// per-cell component label (0 = empty), the overlay is only drawn when `show` is set
+#[derive(Resource, Default)]
+struct Components {
+    labels: Vec<Vec<u32>>,
+    show: bool,
+}
+
+#[derive(Component)]
+struct ComponentOverlay;
+
+#[derive(Component)]
+struct ComponentText;
+
+pub struct ComponentsPlugin;
+
+impl Plugin for ComponentsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Connectivity>()
+            .init_resource::<Components>()
+            .add_startup_system(spawn_text)
+            .add_system(handle_keyboard)
+            .add_system(update_text)
+            .add_system_to_stage(CoreStage::PostUpdate, label_components)
+            .add_system_to_stage(CoreStage::PostUpdate, draw_overlay.after(label_components));
+    }
+}
+
+fn spawn_text(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("Kid Marker.ttf"),
+                font_size: 20.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(5.0),
+                right: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+        ComponentText,
+        HudElement,
+        BesidePanel,
+    ));
+}
+
+fn update_text(
+    components: Res<Components>,
+    connectivity: Res<Connectivity>,
+    stats: Res<SimStats>,
+    mut query: Query<&mut Text, With<ComponentText>>,
+) {
+    let Ok(mut text) = query.get_single_mut() else {
+        return;
+    };
+    text.sections[0].value = if components.show {
+        let n = match *connectivity {
+            Connectivity::Four => 4,
+            Connectivity::Eight => 8,
+        };
+        format!(
+            "{} components ({}-connected), largest {}",
+            stats.components, n, stats.largest_component
+        )
+    } else {
+        String::new()
+    };
+}
+
+// C         -> show/hide component colors
+// Shift + C -> switch between 4 and 8 connectivity
+fn handle_keyboard(
+    key: Keys,
+    mut connectivity: ResMut<Connectivity>,
+    mut components: ResMut<Components>,
+) {
+    // Ctrl + C copies the selection
+    let ctrl = key.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    if key.just_pressed(|k| &k.components) && !ctrl {
+        if key.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+            *connectivity = match *connectivity {
+                Connectivity::Four => Connectivity::Eight,
+                Connectivity::Eight => Connectivity::Four,
+            };
+        } else {
+            components.show = !components.show;
+        }
+    }
+}
+
+// flood fill, returns the labels plus the number of components and the size of the largest
+fn label(map: &Board, connectivity: Connectivity) -> (Vec<Vec<u32>>, usize, usize) {
+    let size = map.size();
+    let mut labels = vec![vec![0; size.y as usize]; size.x as usize];
+    let (mut count, mut largest) = (0, 0);
+    let mut stack = Vec::new();
+
+    for x in 0..size.x {
+        for y in 0..size.y {
+            if !map.alive(x, y) || labels[x as usize][y as usize] != 0 {
+                continue;
+            }
+            count += 1;
+            let mut cells = 0;
+            labels[x as usize][y as usize] = count as u32;
+            stack.push((x, y));
+            while let Some((cx, cy)) = stack.pop() {
+                cells += 1;
+                for (nx, ny) in map.neighbourhood(cx, cy) {
+                    let diagonal = nx != cx && ny != cy;
+                    if (diagonal && connectivity == Connectivity::Four)
+                        || !map.alive(nx, ny)
+                        || labels[nx as usize][ny as usize] != 0
+                    {
+                        continue;
+                    }
+                    labels[nx as usize][ny as usize] = count as u32;
+                    stack.push((nx, ny));
+                }
+            }
+            largest = largest.max(cells);
+        }
+    }
+    (labels, count, largest)
+}
+
+fn label_components(
+    map: Res<Board>,
+    connectivity: Res<Connectivity>,
+    mut components: ResMut<Components>,
+    mut stats: ResMut<SimStats>,
+) {
+    if !map.is_changed() && !connectivity.is_changed() {
+        return;
+    }
+    let (labels, count, largest) = label(&map, *connectivity);
+    components.labels = labels;
+    stats.components = count;
+    stats.largest_component = largest;
+}
+
+// golden-angle hue steps keep neighbouring labels visually apart
+fn label_color(label: u32) -> Color {
+    Color::hsl((label as f32 * 137.5) % 360.0, 0.75, 0.55)
+}
+
+fn draw_overlay(
+    mut commands: Commands,
+    view: BoardView,
+    components: Res<Components>,
+    overlays: Query<Entity, With<ComponentOverlay>>,
+) {
+    if !components.is_changed() {
+        return;
+    }
+    for entity in overlays.iter() {
+        commands.entity(entity).despawn();
+    }
+    if !components.show {
+        return;
+    }
+    let Some(screen) = view.screen() else {
+        return;
+    };
+    for (x, col) in components.labels.iter().enumerate() {
+        for (y, &label) in col.iter().enumerate().filter(|(_, &l)| l != 0) {
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: label_color(label),
+                        custom_size: Some(screen.cell_size() * SIZE),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(
+                        screen.cell_to_world(x as f32, y as f32).extend(0.5),
+                    ),
+                    ..default()
+                },
+                ComponentOverlay,
+            ));
+        }
+    }
+}
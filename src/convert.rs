@@ -0,0 +1,270 @@
+use bevy::prelude::UVec2;
+use bevy::utils::HashMap;
+use image::{GrayImage, Luma};
+use std::fs;
+use std::path::Path;
+
+use crate::rle::{self, Pattern};
+use crate::rule::Ruleset;
+
+// the smallest node of a macrocell file, written out cell by cell
+const LEAF_SIDE: u32 = 8;
+
+// `convert <in> <out>`, runs instead of opening the window
+pub struct Convert {
+    pub input: String,
+    pub output: String,
+    pub rule: Option<Ruleset>, // --rule, replaces the one in the input file
+}
+
+// told apart by the file extension
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Rle,
+    Cells,     // plaintext, as on the LifeWiki
+    Png,       // one pixel per cell, bright is alive
+    Macrocell, // Golly's quadtree format, written only
+    Npy,       // a numpy array of 0 and 1, rows top first, written only
+}
+
+impl Format {
+    fn of(path: &str) -> Result<Format, String> {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase);
+        match extension.as_deref() {
+            Some("rle") => Ok(Format::Rle),
+            Some("cells") => Ok(Format::Cells),
+            Some("png") => Ok(Format::Png),
+            Some("mc") => Ok(Format::Macrocell),
+            Some("npy") => Ok(Format::Npy),
+            _ => Err(format!(
+                "{}: unknown format, use .rle, .cells, .png, .mc or .npy",
+                path
+            )),
+        }
+    }
+}
+
+// every cell, the top row first
+fn rows(pattern: &Pattern) -> Vec<Vec<bool>> {
+    let mut rows = vec![vec![false; pattern.size.x as usize]; pattern.size.y as usize];
+    for cell in pattern.alive.iter() {
+        rows[(pattern.size.y - 1 - cell.y) as usize][cell.x as usize] = true;
+    }
+    rows
+}
+
+// top row first, like rows() makes them
+fn from_rows(rows: &[Vec<bool>]) -> Pattern {
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0) as u32;
+    let height = rows.len() as u32;
+    let mut alive = Vec::new();
+    for (row, cells) in rows.iter().enumerate() {
+        for (x, &cell) in cells.iter().enumerate() {
+            if cell {
+                alive.push(UVec2::new(x as u32, height - 1 - row as u32));
+            }
+        }
+    }
+    Pattern {
+        size: UVec2::new(width, height),
+        alive,
+    }
+}
+
+// '!' starts a comment line, 'O' (or '*') is alive, anything else dead
+pub fn parse_cells(text: &str) -> Pattern {
+    let rows: Vec<Vec<bool>> = text
+        .lines()
+        .filter(|line| !line.starts_with('!'))
+        .map(|line| {
+            line.trim_end()
+                .chars()
+                .map(|c| c == 'O' || c == '*')
+                .collect()
+        })
+        .collect();
+    from_rows(&rows)
+}
+
+fn to_cells(pattern: &Pattern, name: &str) -> String {
+    let mut text = format!("!Name: {}\n", name);
+    for row in rows(pattern) {
+        let end = row.iter().rposition(|&alive| alive).map_or(0, |x| x + 1);
+        text.extend(
+            row[..end]
+                .iter()
+                .map(|&alive| if alive { 'O' } else { '.' }),
+        );
+        text.push('\n');
+    }
+    text
+}
+
+fn load_png(path: &str) -> Result<Pattern, String> {
+    let image = image::open(path)
+        .map_err(|e| format!("could not read {}: {}", path, e))?
+        .to_luma8();
+    let rows: Vec<Vec<bool>> = image
+        .rows()
+        .map(|row| row.map(|pixel| pixel.0[0] >= 128).collect())
+        .collect();
+    Ok(from_rows(&rows))
+}
+
+fn to_png(pattern: &Pattern) -> GrayImage {
+    let rows = rows(pattern);
+    GrayImage::from_fn(pattern.size.x, pattern.size.y, |x, y| {
+        match rows[y as usize][x as usize] {
+            true => Luma([255]),
+            false => Luma([0]),
+        }
+    })
+}
+
+// version 1.0 of the format, a header padded to 64 bytes and then the raw bytes
+fn to_npy(pattern: &Pattern) -> Vec<u8> {
+    let mut header = format!(
+        "{{'descr': '|u1', 'fortran_order': False, 'shape': ({}, {}), }}",
+        pattern.size.y, pattern.size.x
+    );
+    // magic, version and length take 10 bytes, the header ends with a newline
+    let padding = 63 - (10 + header.len()) % 64;
+    header.extend(std::iter::repeat_n(' ', padding));
+    header.push('\n');
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend((header.len() as u16).to_le_bytes());
+    bytes.extend(header.into_bytes());
+    bytes.extend(rows(pattern).into_iter().flatten().map(u8::from));
+    bytes
+}
+
+// node lines in the order they are first needed, each one written once
+struct Macrocell<'a> {
+    rows: &'a [Vec<bool>],
+    lines: Vec<String>,
+    ids: HashMap<String, usize>, // 1-based, 0 is an empty node
+}
+
+impl Macrocell<'_> {
+    fn alive(&self, x: u32, y: u32) -> bool {
+        let row = self.rows.get(y as usize);
+        row.and_then(|row| row.get(x as usize))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    // the square of `side` cells with its top left corner at (x, y), y down
+    fn node(&mut self, x: u32, y: u32, side: u32) -> usize {
+        let line = if side == LEAF_SIDE {
+            // '.' dead, '*' alive, '$' ends a row, trailing dead cells and rows left out
+            let mut leaf = String::new();
+            for dy in 0..side {
+                let row: String = (0..side)
+                    .map(|dx| if self.alive(x + dx, y + dy) { '*' } else { '.' })
+                    .collect();
+                leaf.push_str(row.trim_end_matches('.'));
+                leaf.push('$');
+            }
+            if leaf.len() == side as usize {
+                return 0;
+            }
+            format!("{}$", leaf.trim_end_matches('$'))
+        } else {
+            let half = side / 2;
+            let children = [
+                self.node(x, y, half),
+                self.node(x + half, y, half),
+                self.node(x, y + half, half),
+                self.node(x + half, y + half, half),
+            ];
+            if children == [0; 4] {
+                return 0;
+            }
+            let [nw, ne, sw, se] = children;
+            format!("{} {} {} {} {}", side.trailing_zeros(), nw, ne, sw, se)
+        };
+        if let Some(&id) = self.ids.get(&line) {
+            return id;
+        }
+        self.lines.push(line.clone());
+        self.ids.insert(line, self.lines.len());
+        self.lines.len()
+    }
+}
+
+fn to_macrocell(pattern: &Pattern, rule: &str) -> String {
+    let rows = rows(pattern);
+    let side = pattern
+        .size
+        .max_element()
+        .next_power_of_two()
+        .max(LEAF_SIDE);
+    let mut macrocell = Macrocell {
+        rows: &rows,
+        lines: Vec::new(),
+        ids: HashMap::default(),
+    };
+    // an empty pattern still needs a root, an empty leaf
+    if macrocell.node(0, 0, side) == 0 {
+        macrocell.lines.push("$".to_string());
+    }
+    let mut text = format!("[M2] (bevy_conway)\n#R {}\n", rule);
+    for line in macrocell.lines {
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text
+}
+
+pub fn run(convert: &Convert) -> Result<String, String> {
+    let (from, to) = (Format::of(&convert.input)?, Format::of(&convert.output)?);
+    let text = || {
+        fs::read_to_string(&convert.input)
+            .map_err(|e| format!("could not read {}: {}", convert.input, e))
+    };
+    // only .rle files carry a rule, the others are written as B3/S23 unless --rule says otherwise
+    let (pattern, header_rule) = match from {
+        Format::Rle => {
+            rle::parse_with_rule(&text()?).map_err(|e| format!("{}: {}", convert.input, e))?
+        }
+        Format::Cells => (parse_cells(&text()?), None),
+        Format::Png => (load_png(&convert.input)?, None),
+        Format::Macrocell | Format::Npy => {
+            return Err(format!(
+                "{}: .mc and .npy can only be written",
+                convert.input
+            ))
+        }
+    };
+    let rule = match (convert.rule, header_rule) {
+        (Some(rule), _) => rule.to_string(),
+        (None, Some(rule)) => rule,
+        (None, None) => Ruleset::default().to_string(),
+    };
+    let name = Path::new(&convert.input)
+        .file_stem()
+        .map_or("pattern".into(), |stem| stem.to_string_lossy());
+    let write = |bytes: &[u8]| {
+        fs::write(&convert.output, bytes)
+            .map_err(|e| format!("could not write {}: {}", convert.output, e))
+    };
+    match to {
+        Format::Rle => write(rle::to_text(&pattern, &rule).as_bytes())?,
+        Format::Cells => write(to_cells(&pattern, &name).as_bytes())?,
+        Format::Png => to_png(&pattern)
+            .save(&convert.output)
+            .map_err(|e| format!("could not write {}: {}", convert.output, e))?,
+        Format::Macrocell => write(to_macrocell(&pattern, &rule).as_bytes())?,
+        Format::Npy => write(&to_npy(&pattern))?,
+    }
+    Ok(format!(
+        "{}x{} pattern with {} live cells written to {}",
+        pattern.size.x,
+        pattern.size.y,
+        pattern.alive.len(),
+        convert.output
+    ))
+}
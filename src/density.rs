@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use image::{GrayImage, Luma};
+use rand::Rng;
+
+use crate::board::Board;
+use crate::sim::{Keys, SetCell, SimRng};
+use crate::toast::Toast;
+
+const DENSITY_FILE: &str = "density.png";
+// side in cells of the block each exported pixel stands for
+const BLOCK: u32 = 4;
+
+// the live fraction of each block of the board, a coarse picture of a state without its cells
+#[derive(Clone, Debug)]
+pub struct DensityMap {
+    size: UVec2,      // in pixels
+    values: Vec<f32>, // 0..=1, row by row from the bottom one like the board
+}
+
+impl DensityMap {
+    // blocks cut by the right or top edge count only the cells they have
+    fn from_board(map: &Board, block: u32) -> Self {
+        let board = map.size();
+        let size = (board + UVec2::splat(block - 1)) / block;
+        let mut values = Vec::with_capacity((size.x * size.y) as usize);
+        for py in 0..size.y {
+            for px in 0..size.x {
+                let (min, max) = (UVec2::new(px, py) * block, (UVec2::new(px, py) + 1) * block);
+                let max = max.min(board);
+                let cells = (max.x - min.x) * (max.y - min.y);
+                let alive = (min.x..max.x)
+                    .flat_map(|x| (min.y..max.y).map(move |y| (x, y)))
+                    .filter(|&(x, y)| map.alive(x, y))
+                    .count();
+                values.push(alive as f32 / cells as f32);
+            }
+        }
+        DensityMap { size, values }
+    }
+
+    // white is a fully alive block, black an empty one
+    fn to_image(&self) -> GrayImage {
+        GrayImage::from_fn(self.size.x, self.size.y, |x, y| {
+            let value = self.values[((self.size.y - 1 - y) * self.size.x + x) as usize];
+            Luma([(value * 255.0).round() as u8])
+        })
+    }
+
+    pub fn load(path: &str) -> Result<DensityMap, String> {
+        let image = image::open(path)
+            .map_err(|e| format!("could not read {}: {}", path, e))?
+            .into_luma8();
+        let size = UVec2::new(image.width(), image.height());
+        if size.min_element() == 0 {
+            return Err(format!("{} is empty", path));
+        }
+        let mut values = Vec::with_capacity((size.x * size.y) as usize);
+        for y in (0..size.y).rev() {
+            for x in 0..size.x {
+                values.push(image.get_pixel(x, y).0[0] as f32 / 255.0);
+            }
+        }
+        Ok(DensityMap { size, values })
+    }
+
+    // the image is stretched over the board, whatever block size it was exported with
+    fn at(&self, x: u32, y: u32, board: UVec2) -> f32 {
+        let p = UVec2::new(x, y) * self.size / board;
+        self.values[(p.y * self.size.x + p.x) as usize]
+    }
+
+    // a random board whose blocks have about the densities of the map
+    pub fn microstate(&self, board: UVec2, rng: &mut SimRng) -> Vec<(u32, u32, bool)> {
+        (0..board.x)
+            .flat_map(|x| (0..board.y).map(move |y| (x, y)))
+            .map(|(x, y)| (x, y, rng.0.gen_bool(self.at(x, y, board).into())))
+            .collect()
+    }
+}
+
+pub struct DensityPlugin;
+
+impl Plugin for DensityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(handle_keyboard);
+    }
+}
+
+// D         -> export the board as a density map, one pixel per BLOCK x BLOCK cells
+// Shift + D -> replace the board with a random state drawn from density.png
+fn handle_keyboard(
+    key: Keys,
+    map: Res<Board>,
+    mut rng: ResMut<SimRng>,
+    mut sets: EventWriter<SetCell>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if !key.just_pressed(|k| &k.density) {
+        return;
+    }
+    if !key.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+        match DensityMap::from_board(&map, BLOCK)
+            .to_image()
+            .save(DENSITY_FILE)
+        {
+            Ok(()) => info!("density map written to {}", DENSITY_FILE),
+            Err(e) => toasts.send(Toast(format!("could not write {}: {}", DENSITY_FILE, e))),
+        }
+        return;
+    }
+    match DensityMap::load(DENSITY_FILE) {
+        // regular edits, so the new state ends up in the recipe like a pasted pattern
+        Ok(density) => sets.send_batch(
+            density
+                .microstate(map.size(), &mut rng)
+                .into_iter()
+                .map(|(x, y, alive)| SetCell { x, y, alive }),
+        ),
+        Err(e) => toasts.send(Toast(e)),
+    }
+}
@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+
+use crate::sim::{BoardTransform, Keys, SimStats, TransformBoard};
+
+// shifts the whole board by `vector` (with wrap) every `every` generations
+#[derive(Resource, Clone, Copy)]
+pub struct Drift {
+    pub enabled: bool,
+    pub vector: IVec2,
+    pub every: u64,
+}
+
+impl Default for Drift {
+    fn default() -> Self {
+        Drift {
+            enabled: false,
+            vector: IVec2::new(1, 0),
+            every: 10,
+        }
+    }
+}
+
+pub struct DriftPlugin;
+
+impl Plugin for DriftPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Drift>()
+            .add_system(handle_keyboard)
+            .add_system(apply_drift);
+    }
+}
+
+// W -> enable/disable drift
+fn handle_keyboard(key: Keys, mut drift: ResMut<Drift>) {
+    if key.just_pressed(|k| &k.drift) {
+        drift.enabled = !drift.enabled;
+    }
+}
+
+fn apply_drift(
+    drift: Res<Drift>,
+    stats: Res<SimStats>,
+    mut last: Local<u64>,
+    mut transforms: EventWriter<TransformBoard>,
+) {
+    // generation 0 is the untouched starting board
+    if !drift.enabled || stats.generation == *last || stats.generation == 0 {
+        return;
+    }
+    *last = stats.generation;
+    if stats.generation.is_multiple_of(drift.every) {
+        transforms.send(TransformBoard(BoardTransform::Shift {
+            by: drift.vector,
+            wrap: true,
+        }));
+    }
+}
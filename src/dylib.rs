@@ -0,0 +1,94 @@
+use bevy::prelude::*;
+use libloading::{Library, Symbol};
+use std::fs;
+use std::path::Path;
+
+use crate::rule::{PluginRule, PluginRules, Ruleset};
+use crate::toast::Toast;
+
+// next to the app, `--new-rule-template` crates build the libraries that go in here
+const PLUGINS_DIR: &str = "plugins";
+// the layout of RuleInfo and the two functions, as in templates/rule/lib.rs.in
+const ABI_VERSION: u32 = 1;
+
+#[repr(C)]
+struct RuleInfo {
+    abi_version: u32,
+    name: *const u8,
+    name_len: usize,
+    alive: [u8; 3],
+    empty: [u8; 3],
+}
+
+type InfoFn = extern "C" fn() -> RuleInfo;
+type NextFn = extern "C" fn(u16) -> bool;
+
+fn color([r, g, b]: [u8; 3]) -> Color {
+    Color::rgb_u8(r, g, b)
+}
+
+// the rule is asked about all 512 blocks here once, the library is closed again afterwards
+fn load(path: &Path) -> Result<PluginRule, String> {
+    // SAFETY: a library in plugins/ is trusted to implement the interface of the template,
+    // none of its code or data is used once this returns
+    unsafe {
+        let library = Library::new(path).map_err(|e| e.to_string())?;
+        let info: Symbol<InfoFn> = library
+            .get(b"conway_rule_info")
+            .map_err(|e| e.to_string())?;
+        let next: Symbol<NextFn> = library
+            .get(b"conway_rule_next")
+            .map_err(|e| e.to_string())?;
+        let info = info();
+        if info.abi_version != ABI_VERSION {
+            return Err(format!(
+                "built for interface version {}, this app loads version {}",
+                info.abi_version, ABI_VERSION
+            ));
+        }
+        let name = std::slice::from_raw_parts(info.name, info.name_len);
+        let name = String::from_utf8_lossy(name).into_owned();
+        let rule = Ruleset::from_table(|cells| next(cells));
+        if rule.next_block(0) {
+            return Err("rules with B0 are not supported".to_string());
+        }
+        Ok(PluginRule {
+            name,
+            rule,
+            alive: color(info.alive),
+            empty: color(info.empty),
+        })
+    }
+}
+
+pub struct DylibPlugin;
+
+impl Plugin for DylibPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(load_plugins);
+    }
+}
+
+// every .so, .dylib or .dll in plugins/, sorted by file name, a missing directory is no plugins
+fn load_plugins(mut plugins: ResMut<PluginRules>, mut toasts: EventWriter<Toast>) {
+    let Ok(entries) = fs::read_dir(PLUGINS_DIR) else {
+        return;
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            let extension = path.extension().and_then(|e| e.to_str());
+            matches!(extension, Some("so" | "dylib" | "dll"))
+        })
+        .collect();
+    paths.sort();
+    for path in paths {
+        match load(&path) {
+            Ok(plugin) => {
+                info!("loaded rule {} from {}", plugin.name, path.display());
+                plugins.0.push(plugin);
+            }
+            Err(e) => toasts.send(Toast(format!("could not load {}: {}", path.display(), e))),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+
+use crate::board::{Board, Boundary};
+use crate::sim::{GridConfig, Keys, SimStats, State, MAX_GRID};
+use crate::toast::Toast;
+
+// what happens when live cells first reach the edge of a finite board
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+enum EdgeAction {
+    #[default]
+    Off,
+    Alert, // toast and HUD tag only
+    Pause,
+    Grow, // resize the board around the pattern, pauses once it cannot grow anymore
+}
+
+#[derive(Resource, Default)]
+pub struct EdgeAlarm {
+    action: EdgeAction,
+    touching: bool, // live cells are on the outermost ring right now
+}
+
+impl EdgeAlarm {
+    pub fn label(&self) -> &'static str {
+        match (self.action, self.touching) {
+            (EdgeAction::Off, _) => "",
+            (_, true) => "  [at edge]",
+            (EdgeAction::Alert, false) => "  [edge alert]",
+            (EdgeAction::Pause, false) => "  [edge pause]",
+            (EdgeAction::Grow, false) => "  [edge grow]",
+        }
+    }
+}
+
+pub struct EdgePlugin;
+
+impl Plugin for EdgePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EdgeAlarm>()
+            .add_system(handle_keyboard)
+            // after the generation has been stepped
+            .add_system_to_stage(CoreStage::PostUpdate, check_edges);
+    }
+}
+
+// A -> cycle off / alert / pause / grow
+fn handle_keyboard(key: Keys, mut alarm: ResMut<EdgeAlarm>) {
+    if key.just_pressed(|k| &k.edge_alarm) {
+        alarm.action = match alarm.action {
+            EdgeAction::Off => EdgeAction::Alert,
+            EdgeAction::Alert => EdgeAction::Pause,
+            EdgeAction::Pause => EdgeAction::Grow,
+            EdgeAction::Grow => EdgeAction::Off,
+        };
+    }
+}
+
+fn check_edges(
+    map: Res<Board>,
+    stats: Res<SimStats>,
+    boundary: Res<Boundary>,
+    mut alarm: ResMut<EdgeAlarm>,
+    mut state: ResMut<State>,
+    mut grid: ResMut<GridConfig>,
+    mut toasts: EventWriter<Toast>,
+) {
+    // only a running board is watched, so an edge touched while drawing alarms on play
+    if !state.0 || (!map.is_changed() && !alarm.is_changed() && !state.is_changed()) {
+        return;
+    }
+    // a toroidal board has no edge to run into
+    let size = map.size();
+    let touching = *boundary == Boundary::Finite
+        && stats.bounding_box.is_some_and(|(min, max)| {
+            min.x == 0 || min.y == 0 || max.x + 1 >= size.x || max.y + 1 >= size.y
+        });
+    let first_touch = touching && !alarm.touching;
+    if alarm.touching != touching {
+        alarm.touching = touching;
+    }
+    if !first_touch {
+        return;
+    }
+    match alarm.action {
+        EdgeAction::Off => {}
+        EdgeAction::Alert => toasts.send(Toast(format!(
+            "live cells reached the edge at generation {}",
+            stats.generation
+        ))),
+        EdgeAction::Grow if grid.width < MAX_GRID && grid.height < MAX_GRID => {
+            *grid = grid.resized(5);
+        }
+        EdgeAction::Pause | EdgeAction::Grow => {
+            state.0 = false;
+            toasts.send(Toast(format!(
+                "paused, live cells reached the edge at generation {}",
+                stats.generation
+            )));
+        }
+    }
+}
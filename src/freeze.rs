@@ -0,0 +1,240 @@
+use bevy::prelude::*;
+
+use crate::board::{FrozenRegions, Region};
+use crate::sim::{BoardView, Keys, Screen, Tool};
+
+const FROZEN_COLOR: Color = Color::rgba(0.3, 0.5, 1.0, 0.25);
+const ACTIVE_COLOR: Color = Color::rgba(0.2, 0.8, 0.3, 0.6);
+const HANDLE_COLOR: Color = Color::rgba(0.1, 0.6, 0.2, 0.9);
+
+impl Resource for FrozenRegions {}
+
+// cell where the current freeze drag started
+#[derive(Resource, Default)]
+struct DragStart(Option<UVec2>);
+
+// fixed corner while the active region is being dragged, the cursor is the other one
+#[derive(Resource, Default)]
+struct ActiveDrag(Option<UVec2>);
+
+#[derive(Component)]
+struct FrozenOverlay;
+
+pub struct FreezePlugin;
+
+impl Plugin for FreezePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FrozenRegions>()
+            .init_resource::<DragStart>()
+            .init_resource::<ActiveDrag>()
+            .add_system(handle_keyboard)
+            .add_system(handle_mouse)
+            .add_system(handle_active_mouse)
+            .add_system(draw_overlays.after(handle_mouse).after(handle_active_mouse));
+    }
+}
+
+// F         -> switch between the draw and freeze tools
+// Shift + F -> unfreeze everything
+// V         -> switch between the draw and active region tools
+// Shift + V -> step the whole board again
+fn handle_keyboard(key: Keys, mut tool: ResMut<Tool>, mut frozen: ResMut<FrozenRegions>) {
+    let shift = key.pressed(KeyCode::LShift) || key.pressed(KeyCode::RShift);
+    if key.just_pressed(|k| &k.freeze) {
+        if shift {
+            frozen.regions.clear();
+        } else {
+            *tool = match *tool {
+                Tool::Freeze => Tool::Draw,
+                _ => Tool::Freeze,
+            };
+        }
+    }
+    // Ctrl + V pastes
+    let ctrl = key.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    if key.just_pressed(|k| &k.active_region) && !ctrl {
+        if shift {
+            frozen.active = None;
+        } else {
+            *tool = match *tool {
+                Tool::Active => Tool::Draw,
+                _ => Tool::Active,
+            };
+        }
+    }
+}
+
+// with the freeze tool: left drag marks a region, right click unfreezes the regions under the cursor
+fn handle_mouse(
+    mouse_input: Res<Input<MouseButton>>,
+    view: BoardView,
+    tool: Res<Tool>,
+    mut drag: ResMut<DragStart>,
+    mut frozen: ResMut<FrozenRegions>,
+) {
+    if *tool != Tool::Freeze {
+        if drag.0.is_some() {
+            drag.0 = None;
+        }
+        return;
+    }
+    let Some(screen) = view.screen() else {
+        return;
+    };
+    let cell = screen.cursor_to_cell();
+
+    if mouse_input.just_pressed(MouseButton::Left) {
+        drag.0 = cell;
+    }
+    if mouse_input.just_released(MouseButton::Left) {
+        if let (Some(start), Some(end)) = (drag.0.take(), cell) {
+            frozen.regions.push(Region::new(start, end));
+        }
+    }
+    if mouse_input.just_pressed(MouseButton::Right) {
+        if let Some(cell) = cell {
+            frozen.regions.retain(|r| !r.contains(cell.x, cell.y));
+        }
+    }
+}
+
+// with the active region tool: left drag draws the region, or resizes it when started on
+// one of its corner handles
+fn handle_active_mouse(
+    mouse_input: Res<Input<MouseButton>>,
+    view: BoardView,
+    tool: Res<Tool>,
+    mut drag: ResMut<ActiveDrag>,
+    mut frozen: ResMut<FrozenRegions>,
+) {
+    if *tool != Tool::Active {
+        if drag.0.is_some() {
+            drag.0 = None;
+        }
+        return;
+    }
+    let Some(screen) = view.screen() else {
+        return;
+    };
+    let cell = screen.cursor_to_cell();
+
+    if mouse_input.just_pressed(MouseButton::Left) {
+        let handle = frozen.active.zip(cell).and_then(|(region, cell)| {
+            let corners = region.corners();
+            // the fixed corner is the opposite one
+            (0..4)
+                .find(|&i| corners[i] == cell)
+                .map(|i| corners[(i + 2) % 4])
+        });
+        drag.0 = handle.or(cell);
+    }
+    if mouse_input.just_released(MouseButton::Left) {
+        if let (Some(anchor), Some(end)) = (drag.0.take(), cell) {
+            frozen.active = Some(Region::new(anchor, end));
+        }
+    }
+}
+
+fn spawn_rect(commands: &mut Commands, color: Color, center: Vec2, size: Vec2) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(size),
+                ..default()
+            },
+            transform: Transform::from_translation(center.extend(1.0)),
+            ..default()
+        },
+        FrozenOverlay,
+    ));
+}
+
+fn spawn_overlay(commands: &mut Commands, region: Region, screen: &Screen) {
+    let min = screen.cell_to_world(region.min.x as f32, region.min.y as f32);
+    let max = screen.cell_to_world(region.max.x as f32, region.max.y as f32);
+    let cells = (region.max - region.min + UVec2::ONE).as_vec2();
+    spawn_rect(
+        commands,
+        FROZEN_COLOR,
+        (min + max) / 2.0,
+        cells * screen.cell_size(),
+    );
+}
+
+// outline of the active region plus a handle on each corner
+fn spawn_active_overlay(commands: &mut Commands, region: Region, screen: &Screen) {
+    let cell = screen.cell_size();
+    let half = cell / 2.0;
+    let min = screen.cell_to_world(region.min.x as f32, region.min.y as f32) - half;
+    let max = screen.cell_to_world(region.max.x as f32, region.max.y as f32) + half;
+    let line = 3.0;
+    let (width, height) = (max.x - min.x, max.y - min.y);
+    let mid = (min + max) / 2.0;
+    spawn_rect(
+        commands,
+        ACTIVE_COLOR,
+        Vec2::new(mid.x, min.y),
+        Vec2::new(width, line),
+    );
+    spawn_rect(
+        commands,
+        ACTIVE_COLOR,
+        Vec2::new(mid.x, max.y),
+        Vec2::new(width, line),
+    );
+    spawn_rect(
+        commands,
+        ACTIVE_COLOR,
+        Vec2::new(min.x, mid.y),
+        Vec2::new(line, height),
+    );
+    spawn_rect(
+        commands,
+        ACTIVE_COLOR,
+        Vec2::new(max.x, mid.y),
+        Vec2::new(line, height),
+    );
+    for corner in region.corners() {
+        let center = screen.cell_to_world(corner.x as f32, corner.y as f32);
+        spawn_rect(commands, HANDLE_COLOR, center, cell * 0.5);
+    }
+}
+
+// overlays are rebuilt only when the regions change or while a region is being dragged
+fn draw_overlays(
+    mut commands: Commands,
+    view: BoardView,
+    frozen: Res<FrozenRegions>,
+    drag: Res<DragStart>,
+    active_drag: Res<ActiveDrag>,
+    overlays: Query<Entity, With<FrozenOverlay>>,
+) {
+    let dragging = drag.0.is_some() || active_drag.0.is_some();
+    if !frozen.is_changed() && !drag.is_changed() && !active_drag.is_changed() && !dragging {
+        return;
+    }
+    for entity in overlays.iter() {
+        commands.entity(entity).despawn();
+    }
+    let Some(screen) = view.screen() else {
+        return;
+    };
+    for region in frozen.regions.iter() {
+        spawn_overlay(&mut commands, *region, &screen);
+    }
+    if let (Some(start), Some(end)) = (drag.0, screen.cursor_to_cell()) {
+        spawn_overlay(&mut commands, Region::new(start, end), &screen);
+    }
+    // while it is being dragged the preview replaces the current active region
+    match (active_drag.0, screen.cursor_to_cell()) {
+        (Some(anchor), Some(end)) => {
+            spawn_active_overlay(&mut commands, Region::new(anchor, end), &screen)
+        }
+        _ => {
+            if let Some(region) = frozen.active {
+                spawn_active_overlay(&mut commands, region, &screen);
+            }
+        }
+    }
+}
@@ -0,0 +1,673 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::render::extract_resource::{ExtractResource, ExtractResourcePlugin};
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_graph::{self, RenderGraph};
+use bevy::render::render_resource::*;
+use bevy::render::renderer::{RenderContext, RenderDevice};
+use bevy::render::{RenderApp, RenderStage};
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::board::{Board, Boundary, FrozenRegions, SimBackend};
+use crate::rule::Ruleset;
+use crate::sim::{SimRng, StartupScene};
+use crate::trace::TraceBackend;
+use crate::validate::{self, DIFF_COLOR, MAX_HIGHLIGHTS};
+
+pub const MIN_SIDE: u32 = 16;
+pub const MAX_SIDE: u32 = 8192;
+const WORKGROUP_SIZE: u32 = 8;
+pub const DEFAULT_STATS_EVERY: u64 = 30;
+const WINDOW_SIDE: f32 = 1000.0;
+const SHADER: &str = include_str!("../assets/shaders/life.wgsl");
+
+// what the gpu board is made from, the same options the regular app starts with
+#[derive(Resource)]
+pub struct GpuSetup {
+    pub side: u32,
+    pub scene: StartupScene,
+    pub seed: Option<u64>,
+    pub boundary: Boundary,
+    pub rule: Ruleset,
+    pub stats_every: u64, // generations between two copies of the board back to the cpu
+    pub validate: Option<TraceBackend>, // what every board read back is diffed with
+}
+
+// a square board stepped by a compute shader once per frame and drawn as one sprite,
+// for boards far too big for a sprite per cell, SPACE pauses
+pub fn run(setup: GpuSetup) -> Result<(), String> {
+    if setup.rule.decay_states() > 0 {
+        return Err("the gpu board only runs 2-state rules".to_string());
+    }
+    if !setup.rule.is_totalistic() {
+        return Err("the gpu board only runs B/S rules".to_string());
+    }
+    if let Some(backend) = setup.validate {
+        let mut probe = Board::new(UVec2::splat(setup.side));
+        probe.set_boundary(setup.boundary);
+        probe.set_rule(setup.rule);
+        if !backend.create().supports(&probe, &FrozenRegions::default()) {
+            return Err(format!("{} can't run this board", backend.name()));
+        }
+    }
+    let (birth, survival) = setup.rule.masks();
+    let source = SHADER
+        .replace("{BIRTH}", &birth.to_string())
+        .replace("{SURVIVAL}", &survival.to_string());
+    App::new()
+        .insert_resource(ClearColor(Color::rgb(0.05, 0.05, 0.05)))
+        .add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    window: WindowDescriptor {
+                        width: WINDOW_SIDE,
+                        height: WINDOW_SIDE,
+                        title: format!("Bevy_app {0}x{0} (gpu)", setup.side),
+                        ..default()
+                    },
+                    ..default()
+                })
+                // one texel per cell, a filtered board would blur
+                .set(ImagePlugin::default_nearest()),
+        )
+        .insert_resource(Playing(true))
+        .insert_resource(setup)
+        .add_plugin(GpuLifePlugin { source })
+        .add_startup_system(spawn_board)
+        .add_system(handle_keyboard)
+        .add_system(swap_images.after(handle_keyboard))
+        .add_system(show_stats)
+        .add_system(draw_diff.after(show_stats))
+        .add_system_to_stage(CoreStage::Last, report_on_exit)
+        .run();
+    Ok(())
+}
+
+#[derive(Resource)]
+struct Playing(bool);
+
+// the board is kept twice, every generation reads one image and writes the other
+#[derive(Resource, Clone, ExtractResource)]
+struct GpuBoard {
+    images: [Handle<Image>; 2],
+    current: usize,  // the image that is shown, the newest one written
+    step: bool,      // write the next generation into the other image this frame
+    generation: u64, // of the current image
+    readback: bool,  // copy the image written this frame back
+}
+
+// set by the life node when it did step the board, a frame in which the shader was still
+// compiling or the images were not on the gpu yet leaves the board where it was
+#[derive(Resource, Clone, Default)]
+struct Dispatched(Arc<AtomicBool>);
+
+#[derive(Component)]
+struct BoardSprite;
+
+#[derive(Component)]
+struct StatsText;
+
+#[derive(Component)]
+struct DiffHighlight;
+
+// a board copied back from the gpu, handed from the render world to the main one
+struct Readback {
+    generation: u64,
+    board: Board,
+}
+
+// holds the newest readback until show_stats takes it, an older one that was never shown is dropped
+#[derive(Resource, Clone, Default)]
+struct Readbacks(Arc<Mutex<Option<Readback>>>);
+
+// --validate gpu,<backend>: the same board stepped on the cpu, diffed with every readback
+#[derive(Resource)]
+struct GpuCheck {
+    name: &'static str,
+    backend: Box<dyn SimBackend + Send + Sync>,
+    board: Board,
+    generation: u64, // of `board`
+    checked: u64,
+    first_diff: Option<(u64, usize)>, // generation and number of cells of the first disagreement
+    diff: Vec<UVec2>,                 // cells the two disagree on in the newest readback
+}
+
+impl GpuCheck {
+    // the cpu board is stepped up to the readback's generation, after a disagreement it carries
+    // on from the gpu's board so that a later one is a disagreement of its own
+    fn compare(&mut self, readback: &Readback) {
+        let Some(ahead) = readback.generation.checked_sub(self.generation) else {
+            return;
+        };
+        self.board = self
+            .backend
+            .advance(&self.board, &FrozenRegions::default(), ahead);
+        self.generation = readback.generation;
+        self.checked += 1;
+        self.diff = validate::disagreeing(&readback.board, &self.board);
+        if self.diff.is_empty() {
+            return;
+        }
+        if self.first_diff.is_none() {
+            self.first_diff = Some((self.generation, self.diff.len()));
+            warn!(
+                "gpu and {} disagree on {} cells at generation {}",
+                self.name,
+                self.diff.len(),
+                self.generation
+            );
+        }
+        self.board = readback.board.clone();
+    }
+}
+
+fn spawn_board(
+    mut commands: Commands,
+    setup: Res<GpuSetup>,
+    mut images: ResMut<Assets<Image>>,
+    asset_server: Res<AssetServer>,
+) {
+    let side = setup.side;
+    let map = setup
+        .scene
+        .build_board(UVec2::splat(side), &mut SimRng::new(setup.seed));
+    // texture rows go top to bottom, the board's y goes up
+    let mut data = Vec::with_capacity((side * side * 4) as usize);
+    for y in (0..side).rev() {
+        for x in 0..side {
+            let v = if map.alive(x, y) { 26 } else { 255 };
+            data.extend_from_slice(&[v, v, v, 255]);
+        }
+    }
+    let mut image = Image::new(
+        Extent3d {
+            width: side,
+            height: side,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8Unorm,
+    );
+    image.texture_descriptor.usage = TextureUsages::COPY_DST
+        | TextureUsages::COPY_SRC
+        | TextureUsages::STORAGE_BINDING
+        | TextureUsages::TEXTURE_BINDING;
+    let board = GpuBoard {
+        images: [images.add(image.clone()), images.add(image)],
+        current: 0,
+        step: false,
+        generation: 0,
+        readback: false,
+    };
+    commands.spawn(Camera2dBundle::default());
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::splat(WINDOW_SIDE)),
+                ..default()
+            },
+            texture: board.images[0].clone(),
+            ..default()
+        },
+        BoardSprite,
+    ));
+    commands.spawn((
+        TextBundle::from_section(
+            format!("generation 0  population {}", map.population()),
+            TextStyle {
+                font: asset_server.load("Kid Marker.ttf"),
+                font_size: 20.0,
+                color: Color::rgb(0.9, 0.3, 0.3),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(5.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+        StatsText,
+    ));
+    if let Some(backend) = setup.validate {
+        let mut map = map;
+        map.set_boundary(setup.boundary);
+        map.set_rule(setup.rule);
+        commands.insert_resource(GpuCheck {
+            name: backend.name(),
+            backend: backend.create(),
+            board: map,
+            generation: 0,
+            checked: 0,
+            first_diff: None,
+            diff: Vec::new(),
+        });
+    }
+    commands.insert_resource(board);
+}
+
+// SPACE || S -> play/pause
+fn handle_keyboard(key: Res<Input<KeyCode>>, mut playing: ResMut<Playing>) {
+    if key.just_pressed(KeyCode::Space) | key.just_pressed(KeyCode::S) {
+        playing.0 = !playing.0;
+    }
+}
+
+// the generation the last frame wrote to the other image is shown once the node says it ran
+fn swap_images(
+    playing: Res<Playing>,
+    setup: Res<GpuSetup>,
+    dispatched: Res<Dispatched>,
+    board: Option<ResMut<GpuBoard>>,
+    mut sprites: Query<&mut Handle<Image>, With<BoardSprite>>,
+) {
+    let Some(mut board) = board else {
+        return;
+    };
+    if dispatched.0.swap(false, Ordering::AcqRel) {
+        board.current = 1 - board.current;
+        board.generation += 1;
+    }
+    board.step = playing.0;
+    board.readback = board.step && (board.generation + 1) % setup.stats_every == 0;
+    for mut texture in sprites.iter_mut() {
+        *texture = board.images[board.current].clone();
+    }
+}
+
+// the stats of the newest board that came back, they lag a frame or two behind the picture
+fn show_stats(
+    readbacks: Res<Readbacks>,
+    check: Option<ResMut<GpuCheck>>,
+    mut text: Query<&mut Text, With<StatsText>>,
+) {
+    let Some(readback) = readbacks.0.lock().unwrap().take() else {
+        return;
+    };
+    let mut stats = format!(
+        "generation {}  population {}  hash {:016x}",
+        readback.generation,
+        readback.board.population(),
+        readback.board.state_hash()
+    );
+    if let Some(mut check) = check {
+        check.compare(&readback);
+        stats.push_str(&match check.diff.len() {
+            0 => format!("  same as {}", check.name),
+            cells => format!("  {} cells off {}", cells, check.name),
+        });
+    }
+    for mut text in text.iter_mut() {
+        text.sections[0].value = stats.clone();
+    }
+}
+
+// the cells the newest readback disagrees on in magenta, over the board sprite
+fn draw_diff(
+    mut commands: Commands,
+    setup: Res<GpuSetup>,
+    check: Option<Res<GpuCheck>>,
+    highlights: Query<Entity, With<DiffHighlight>>,
+) {
+    let Some(check) = check.filter(|c| c.is_changed()) else {
+        return;
+    };
+    for entity in highlights.iter() {
+        commands.entity(entity).despawn();
+    }
+    let cell = WINDOW_SIDE / setup.side as f32;
+    for at in check.diff.iter().take(MAX_HIGHLIGHTS) {
+        let center = (at.as_vec2() + 0.5) * cell - WINDOW_SIDE / 2.0;
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: DIFF_COLOR,
+                    custom_size: Some(Vec2::splat(cell.max(1.0))),
+                    ..default()
+                },
+                transform: Transform::from_translation(center.extend(1.0)),
+                ..default()
+            },
+            DiffHighlight,
+        ));
+    }
+}
+
+// the summary goes to stdout when the window closes
+fn report_on_exit(exits: EventReader<AppExit>, check: Option<Res<GpuCheck>>) {
+    let (false, Some(check)) = (exits.is_empty(), check) else {
+        return;
+    };
+    println!(
+        "{}",
+        validate::summary(["gpu", check.name], check.checked, check.first_diff)
+    );
+}
+
+struct GpuLifePlugin {
+    source: String,
+}
+
+impl Plugin for GpuLifePlugin {
+    fn build(&self, app: &mut App) {
+        let shader = app
+            .world
+            .resource_mut::<Assets<Shader>>()
+            .add(Shader::from_wgsl(self.source.clone()));
+        let setup = app.world.resource::<GpuSetup>();
+        let toroidal = setup.boundary == Boundary::Toroidal;
+        let (boundary, rule) = (setup.boundary, setup.rule);
+        let (readbacks, dispatched) = (Readbacks::default(), Dispatched::default());
+        app.add_plugin(ExtractResourcePlugin::<GpuBoard>::default())
+            .insert_resource(readbacks.clone())
+            .insert_resource(dispatched.clone());
+        let render_app = app.sub_app_mut(RenderApp);
+        render_app
+            .insert_resource(LifeShader { shader, toroidal })
+            .insert_resource(ReadbackTarget { boundary, rule })
+            .insert_resource(readbacks)
+            .insert_resource(dispatched)
+            .init_resource::<LifePipeline>()
+            .add_system_to_stage(RenderStage::Queue, queue_bind_group)
+            .add_system_to_stage(RenderStage::Queue, queue_readback)
+            .add_system_to_stage(RenderStage::Cleanup, finish_readback);
+        let mut render_graph = render_app.world.resource_mut::<RenderGraph>();
+        render_graph.add_node("life", LifeNode::default());
+        render_graph
+            .add_node_edge("life", bevy::render::main_graph::node::CAMERA_DRIVER)
+            .unwrap();
+    }
+}
+
+#[derive(Resource)]
+struct LifeShader {
+    shader: Handle<Shader>,
+    toroidal: bool,
+}
+
+#[derive(Resource)]
+struct LifePipeline {
+    layout: BindGroupLayout,
+    pipeline: CachedComputePipelineId,
+}
+
+impl FromWorld for LifePipeline {
+    fn from_world(world: &mut World) -> Self {
+        let layout =
+            world
+                .resource::<RenderDevice>()
+                .create_bind_group_layout(&BindGroupLayoutDescriptor {
+                    label: Some("life_bind_group_layout"),
+                    entries: &[
+                        BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: ShaderStages::COMPUTE,
+                            ty: BindingType::Texture {
+                                sample_type: TextureSampleType::Float { filterable: false },
+                                view_dimension: TextureViewDimension::D2,
+                                multisampled: false,
+                            },
+                            count: None,
+                        },
+                        BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: ShaderStages::COMPUTE,
+                            ty: BindingType::StorageTexture {
+                                access: StorageTextureAccess::WriteOnly,
+                                format: TextureFormat::Rgba8Unorm,
+                                view_dimension: TextureViewDimension::D2,
+                            },
+                            count: None,
+                        },
+                    ],
+                });
+        let shader = world.resource::<LifeShader>();
+        let shader_defs = match shader.toroidal {
+            true => vec!["TOROIDAL".to_string()],
+            false => vec![],
+        };
+        let descriptor = ComputePipelineDescriptor {
+            label: Some(Cow::from("life_pipeline")),
+            layout: Some(vec![layout.clone()]),
+            shader: shader.shader.clone(),
+            shader_defs,
+            entry_point: Cow::from("update"),
+        };
+        let pipeline = world
+            .resource_mut::<PipelineCache>()
+            .queue_compute_pipeline(descriptor);
+        LifePipeline { layout, pipeline }
+    }
+}
+
+// reads the current image, writes the other one
+#[derive(Resource)]
+struct LifeBindGroup(BindGroup);
+
+fn queue_bind_group(
+    mut commands: Commands,
+    pipeline: Res<LifePipeline>,
+    gpu_images: Res<RenderAssets<Image>>,
+    board: Option<Res<GpuBoard>>,
+    render_device: Res<RenderDevice>,
+) {
+    commands.remove_resource::<LifeBindGroup>();
+    let Some(board) = board.filter(|b| b.step) else {
+        return;
+    };
+    let (Some(input), Some(output)) = (
+        gpu_images.get(&board.images[board.current]),
+        gpu_images.get(&board.images[1 - board.current]),
+    ) else {
+        return;
+    };
+    let bind_group = render_device.create_bind_group(&BindGroupDescriptor {
+        label: Some("life_bind_group"),
+        layout: &pipeline.layout,
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(&input.texture_view),
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: BindingResource::TextureView(&output.texture_view),
+            },
+        ],
+    });
+    commands.insert_resource(LifeBindGroup(bind_group));
+}
+
+// what a read back board is decoded into, so its hash can be compared with a cpu run's
+#[derive(Resource)]
+struct ReadbackTarget {
+    boundary: Boundary,
+    rule: Ruleset,
+}
+
+// a copy of one generation on its way back, at most one at a time so the gpu never waits on it:
+// the life node copies the image into `buffer`, after the frame is submitted the buffer is mapped,
+// and once wgpu says it is (it checks on every submit) the cells are decoded
+#[derive(Resource)]
+struct PendingReadback {
+    buffer: Buffer,
+    generation: u64,
+    side: u32,
+    row_bytes: u32, // texture rows are padded to a multiple of 256 bytes in the buffer
+    copied: Arc<AtomicBool>, // set by the life node, which skips the copy with the step
+    mapping: bool,  // map_async was called
+    mapped: Arc<Mutex<Option<Result<(), String>>>>, // what wgpu answered, None while it hasn't
+}
+
+fn queue_readback(
+    mut commands: Commands,
+    board: Option<Res<GpuBoard>>,
+    pending: Option<Res<PendingReadback>>,
+    gpu_images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+) {
+    // a readback still in flight makes this one skipped, the next one is due in `stats_every`
+    let Some(board) = board.filter(|b| b.step && b.readback && pending.is_none()) else {
+        return;
+    };
+    let Some(image) = gpu_images.get(&board.images[1 - board.current]) else {
+        return;
+    };
+    let side = image.size.x as u32;
+    let row_bytes = RenderDevice::align_copy_bytes_per_row(side as usize * 4) as u32;
+    let buffer = render_device.create_buffer(&BufferDescriptor {
+        label: Some("life_readback"),
+        size: (row_bytes * side) as u64,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    commands.insert_resource(PendingReadback {
+        buffer,
+        generation: board.generation + 1,
+        side,
+        row_bytes,
+        copied: Arc::default(),
+        mapping: false,
+        mapped: Arc::default(),
+    });
+}
+
+fn finish_readback(
+    mut commands: Commands,
+    pending: Option<ResMut<PendingReadback>>,
+    target: Res<ReadbackTarget>,
+    readbacks: Res<Readbacks>,
+) {
+    let Some(mut pending) = pending else {
+        return;
+    };
+    if !pending.mapping {
+        // the board was not stepped this frame, there is nothing to read
+        if !pending.copied.load(Ordering::Acquire) {
+            commands.remove_resource::<PendingReadback>();
+            return;
+        }
+        pending.mapping = true;
+        let mapped = pending.mapped.clone();
+        pending
+            .buffer
+            .slice(..)
+            .map_async(MapMode::Read, move |result| {
+                *mapped.lock().unwrap() = Some(result.map_err(|e| e.to_string()));
+            });
+        return;
+    }
+    let answer = pending.mapped.lock().unwrap().take();
+    match answer {
+        None => return,
+        // a buffer that can't be mapped never will be, the next readback gets a new one
+        Some(Err(e)) => {
+            error!(
+                "could not read generation {} back: {}",
+                pending.generation, e
+            );
+            commands.remove_resource::<PendingReadback>();
+            return;
+        }
+        Some(Ok(())) => {}
+    }
+    let side = pending.side;
+    let mut board = Board::new(UVec2::splat(side));
+    board.set_boundary(target.boundary);
+    board.set_rule(target.rule);
+    {
+        let bytes = pending.buffer.slice(..).get_mapped_range();
+        // texture rows go top to bottom, the board's y goes up, alive texels are dark
+        for (row, texels) in bytes.chunks(pending.row_bytes as usize).enumerate() {
+            for x in 0..side {
+                if texels[x as usize * 4 + 1] < 128 {
+                    board.set(x, side - 1 - row as u32, true);
+                }
+            }
+        }
+    }
+    *readbacks.0.lock().unwrap() = Some(Readback {
+        generation: pending.generation,
+        board,
+    });
+    commands.remove_resource::<PendingReadback>();
+}
+
+#[derive(Default)]
+struct LifeNode {
+    side: u32, // 0 until the board is extracted
+}
+
+impl render_graph::Node for LifeNode {
+    fn update(&mut self, world: &mut World) {
+        if let Some(board) = world.get_resource::<GpuBoard>() {
+            let gpu_images = world.resource::<RenderAssets<Image>>();
+            if let Some(image) = gpu_images.get(&board.images[0]) {
+                self.side = image.size.x as u32;
+            }
+        }
+    }
+
+    fn run(
+        &self,
+        _graph: &mut render_graph::RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), render_graph::NodeRunError> {
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let pipeline = world.resource::<LifePipeline>();
+        // nothing to step this frame, or the shader is still compiling
+        let (Some(bind_group), Some(compute)) = (
+            world.get_resource::<LifeBindGroup>(),
+            pipeline_cache.get_compute_pipeline(pipeline.pipeline),
+        ) else {
+            return Ok(());
+        };
+        {
+            let mut pass = render_context
+                .command_encoder
+                .begin_compute_pass(&ComputePassDescriptor::default());
+            pass.set_bind_group(0, &bind_group.0, &[]);
+            pass.set_pipeline(compute);
+            let groups = self.side.div_ceil(WORKGROUP_SIZE);
+            pass.dispatch_workgroups(groups, groups, 1);
+        }
+        world
+            .resource::<Dispatched>()
+            .0
+            .store(true, Ordering::Release);
+        // the generation just written, copied after the pass in the same encoder
+        let readback = world
+            .get_resource::<PendingReadback>()
+            .filter(|p| !p.mapping && !p.copied.load(Ordering::Acquire));
+        let board = world.resource::<GpuBoard>();
+        let image = world
+            .resource::<RenderAssets<Image>>()
+            .get(&board.images[1 - board.current]);
+        if let (Some(readback), Some(image)) = (readback, image) {
+            render_context.command_encoder.copy_texture_to_buffer(
+                image.texture.as_image_copy(),
+                ImageCopyBuffer {
+                    buffer: &readback.buffer,
+                    layout: ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: std::num::NonZeroU32::new(readback.row_bytes),
+                        rows_per_image: None,
+                    },
+                },
+                Extent3d {
+                    width: readback.side,
+                    height: readback.side,
+                    depth_or_array_layers: 1,
+                },
+            );
+            readback.copied.store(true, Ordering::Release);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,345 @@
+use bevy::prelude::UVec2;
+use bevy::utils::HashMap;
+
+use crate::board::{Board, Boundary, FrozenRegions, SimBackend};
+use crate::rule::Ruleset;
+
+// past this many nodes the tree is thrown away and rebuilt from the current board
+const MAX_NODES: usize = 1 << 20;
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+// a square of 2^level cells, level 0 nodes are the two single cells
+// children are ordered (low x, low y), (high x, low y), (low x, high y), (high x, high y)
+#[derive(Clone, Copy)]
+struct Node {
+    children: [NodeId; 4],
+    level: u8,
+    population: u64,
+}
+
+// HashLife: equal squares of the board are the same node of a quadtree, so the future of every
+// distinct square is computed once and looked up from then on
+// a node of level L can be run 2^(L-2) generations at once, so long jumps take hops that double
+// in length instead of one step per generation
+// only 2-state rules without frozen cells or a data channel, see `supports`
+#[derive(Default)]
+pub struct HashLife {
+    nodes: Vec<Node>,
+    ids: HashMap<[NodeId; 4], NodeId>,
+    next: HashMap<(NodeId, u8), NodeId>, // the center of a node 2^step generations later
+    empty: Vec<NodeId>,                  // the all dead node of each level
+    rule: Option<Ruleset>,               // what `next` was computed with
+    last: Option<(u64, UVec2, NodeId)>,  // state hash, size and root of the last returned board
+}
+
+impl HashLife {
+    fn reset(&mut self, rule: Ruleset) {
+        *self = HashLife::default();
+        self.rule = Some(rule);
+        for population in [0, 1] {
+            self.nodes.push(Node {
+                children: [DEAD; 4],
+                level: 0,
+                population,
+            });
+        }
+        self.empty.push(DEAD);
+    }
+
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(&id) = self.ids.get(&children) {
+            return id;
+        }
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            children,
+            level: self.nodes[children[0] as usize].level + 1,
+            population: children
+                .iter()
+                .map(|&c| self.nodes[c as usize].population)
+                .sum(),
+        });
+        self.ids.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let e = *self.empty.last().unwrap();
+            let up = self.join([e; 4]);
+            self.empty.push(up);
+        }
+        self.empty[level as usize]
+    }
+
+    fn child(&self, id: NodeId, i: usize) -> NodeId {
+        self.nodes[id as usize].children[i]
+    }
+
+    fn population(&self, id: NodeId) -> u64 {
+        self.nodes[id as usize].population
+    }
+
+    // the 2^level square of `map` with its low corner at (x, y), cells past the board are dead
+    fn build(&mut self, map: &Board, level: u8, x: u32, y: u32) -> NodeId {
+        let size = map.size();
+        if x >= size.x || y >= size.y {
+            return self.empty(level);
+        }
+        if level == 0 {
+            return if map.alive(x, y) { ALIVE } else { DEAD };
+        }
+        let half = 1 << (level - 1);
+        let children = [
+            self.build(map, level - 1, x, y),
+            self.build(map, level - 1, x + half, y),
+            self.build(map, level - 1, x, y + half),
+            self.build(map, level - 1, x + half, y + half),
+        ];
+        self.join(children)
+    }
+
+    // writes the live cells of `id` into `map`, with its low corner at (x, y)
+    fn flatten(&self, id: NodeId, x: u32, y: u32, map: &mut Board) {
+        if self.population(id) == 0 {
+            return;
+        }
+        let level = self.nodes[id as usize].level;
+        if level == 0 {
+            map.set(x, y, true);
+            return;
+        }
+        let half = 1 << (level - 1);
+        for (i, &c) in self.nodes[id as usize].children.iter().enumerate() {
+            self.flatten(c, x + half * (i as u32 % 2), y + half * (i as u32 / 2), map);
+        }
+    }
+
+    // `id` with every cell at or past `size` killed, births can't go beyond a finite board
+    fn clip(&mut self, id: NodeId, x: u32, y: u32, size: UVec2) -> NodeId {
+        let level = self.nodes[id as usize].level;
+        let side = 1 << level;
+        if self.population(id) == 0 || (x + side <= size.x && y + side <= size.y) {
+            return id;
+        }
+        if x >= size.x || y >= size.y {
+            return self.empty(level);
+        }
+        let half = side / 2;
+        let c = self.nodes[id as usize].children;
+        let children = [
+            self.clip(c[0], x, y, size),
+            self.clip(c[1], x + half, y, size),
+            self.clip(c[2], x, y + half, size),
+            self.clip(c[3], x + half, y + half, size),
+        ];
+        self.join(children)
+    }
+
+    // the middle quarter of a node of level >= 2, one level down
+    fn center(&mut self, id: NodeId) -> NodeId {
+        let c = self.nodes[id as usize].children;
+        let children = [
+            self.child(c[0], 3),
+            self.child(c[1], 2),
+            self.child(c[2], 1),
+            self.child(c[3], 0),
+        ];
+        self.join(children)
+    }
+
+    // the middle quarter of a node of level >= 2, 2^step generations later, step <= level - 2
+    fn next(&mut self, id: NodeId, step: u8) -> NodeId {
+        if let Some(&next) = self.next.get(&(id, step)) {
+            return next;
+        }
+        let level = self.nodes[id as usize].level;
+        // at the longest step both halves of the recursion run half the generations,
+        // below it the first half only takes the middles and the second runs them all
+        let both = step == level - 2;
+        let result = if level == 2 {
+            self.next_4x4(id)
+        } else {
+            // the 4x4 grandchildren, g[x][y]
+            let mut g = [[DEAD; 4]; 4];
+            for (x, col) in g.iter_mut().enumerate() {
+                for (y, node) in col.iter_mut().enumerate() {
+                    let c = self.child(id, x / 2 + 2 * (y / 2));
+                    *node = self.child(c, x % 2 + 2 * (y % 2));
+                }
+            }
+            // 3x3 overlapping half-size squares, each reduced to its middle
+            let mut m = [[DEAD; 3]; 3];
+            for x in 0..3 {
+                for y in 0..3 {
+                    let sub = self.join([g[x][y], g[x + 1][y], g[x][y + 1], g[x + 1][y + 1]]);
+                    m[x][y] = match both {
+                        true => self.next(sub, step - 1),
+                        false => self.center(sub),
+                    };
+                }
+            }
+            // 2x2 half-size squares centered on each quadrant of the result
+            let mut quadrants = [DEAD; 4];
+            for (i, q) in quadrants.iter_mut().enumerate() {
+                let (x, y) = (i % 2, i / 2);
+                let around = self.join([m[x][y], m[x + 1][y], m[x][y + 1], m[x + 1][y + 1]]);
+                *q = self.next(around, if both { step - 1 } else { step });
+            }
+            self.join(quadrants)
+        };
+        self.next.insert((id, step), result);
+        result
+    }
+
+    // base case, the rule applied to the middle 2x2 cells of a 4x4 square
+    fn next_4x4(&mut self, id: NodeId) -> NodeId {
+        let rule = self.rule.unwrap_or_default();
+        let alive = |x: usize, y: usize| {
+            let c = self.child(id, x / 2 + 2 * (y / 2));
+            self.child(c, x % 2 + 2 * (y % 2)) == ALIVE
+        };
+        let mut cells = [DEAD; 4];
+        for (i, cell) in cells.iter_mut().enumerate() {
+            let (x, y) = (1 + i % 2, 1 + i / 2);
+            let neighbours = (x - 1..=x + 1)
+                .flat_map(|i| (y - 1..=y + 1).map(move |j| (i, j)))
+                .filter(|&(i, j)| (i, j) != (x, y) && alive(i, j))
+                .count() as u8;
+            if rule.next_state(alive(x, y), neighbours) {
+                *cell = ALIVE;
+            }
+        }
+        self.join(cells)
+    }
+
+    // the live cells of `id` within min..=max, with its low corner at (x, y)
+    // squares already inside the box can't grow it and are skipped
+    fn bounds(&self, id: NodeId, x: u32, y: u32, found: &mut Option<(UVec2, UVec2)>) {
+        let level = self.nodes[id as usize].level;
+        let (low, high) = (
+            UVec2::new(x, y),
+            UVec2::new(x, y) + UVec2::splat((1 << level) - 1),
+        );
+        let inside = found.is_some_and(|(min, max)| low.cmpge(min).all() && high.cmple(max).all());
+        if self.population(id) == 0 || inside {
+            return;
+        }
+        if level == 0 {
+            *found = Some(match *found {
+                Some((min, max)) => (min.min(low), max.max(low)),
+                None => (low, low),
+            });
+            return;
+        }
+        let half = 1 << (level - 1);
+        for (i, &c) in self.nodes[id as usize].children.iter().enumerate() {
+            self.bounds(
+                c,
+                x + half * (i as u32 % 2),
+                y + half * (i as u32 / 2),
+                found,
+            );
+        }
+    }
+
+    // the longest hop at most `left` generations long the board held by `root` can take
+    // on a finite board nothing may be born past the edge during a hop, so it is no longer than
+    // the live cells are far from the edges, a single generation is cut back to the board instead
+    fn hop_step(&self, root: NodeId, size: UVec2, boundary: Boundary, left: u64) -> u8 {
+        let level = self.nodes[root as usize].level;
+        let reach = match boundary {
+            Boundary::Toroidal => left,
+            Boundary::Finite => {
+                let mut found = None;
+                self.bounds(root, 0, 0, &mut found);
+                let Some((min, max)) = found else {
+                    return 0;
+                };
+                let margin = min
+                    .min_element()
+                    .min((size - UVec2::ONE - max).min_element());
+                left.min(margin.max(1) as u64)
+            }
+        };
+        (reach.ilog2() as u8).min(level - 1)
+    }
+
+    // 2^step generations of the board held by `root`, step < its level
+    fn step(&mut self, root: NodeId, size: UVec2, boundary: Boundary, step: u8) -> NodeId {
+        let [a, b, c, d] = self.nodes[root as usize].children;
+        match boundary {
+            // the board in the middle of a dead square twice its size
+            Boundary::Finite => {
+                let e = self.empty(self.nodes[a as usize].level);
+                let padded = [
+                    self.join([e, e, e, a]),
+                    self.join([e, e, b, e]),
+                    self.join([e, c, e, e]),
+                    self.join([d, e, e, e]),
+                ];
+                let padded = self.join(padded);
+                let next = self.next(padded, step);
+                self.clip(next, 0, 0, size)
+            }
+            // four copies of the board, the middle comes out shifted by half the board
+            Boundary::Toroidal => {
+                let tiled = self.join([root; 4]);
+                let next = self.next(tiled, step);
+                let [a, b, c, d] = self.nodes[next as usize].children;
+                self.join([d, c, b, a])
+            }
+        }
+    }
+}
+
+impl SimBackend for HashLife {
+    // a torus has to be the level's exact square so the copies line up
+    fn supports(&self, map: &Board, frozen: &FrozenRegions) -> bool {
+        let size = map.size();
+        let shape = match map.boundary() {
+            Boundary::Finite => true,
+            Boundary::Toroidal => size.x == size.y && size.x.is_power_of_two(),
+        };
+        shape
+            && map.rule().decay_states() == 0
+            && map.rule().is_totalistic()
+            && !map.has_data()
+            && frozen.regions.is_empty()
+            && frozen.active.is_none()
+    }
+
+    fn advance(&mut self, map: &Board, _frozen: &FrozenRegions, generations: u64) -> Board {
+        let size = map.size();
+        if self.rule != Some(map.rule()) || self.nodes.len() > MAX_NODES {
+            self.reset(map.rule());
+        }
+        let level = (size.max_element().next_power_of_two().trailing_zeros() as u8).max(2);
+        // stepping one generation at a time (traces) goes on from the tree instead of rebuilding it
+        let mut root = match self.last {
+            Some((hash, last_size, root)) if hash == map.state_hash() && last_size == size => root,
+            _ => self.build(map, level, 0, 0),
+        };
+        let mut left = generations;
+        while left > 0 && self.population(root) > 0 {
+            let step = self.hop_step(root, size, map.boundary(), left);
+            root = self.step(root, size, map.boundary(), step);
+            left -= 1 << step;
+            if self.nodes.len() > MAX_NODES {
+                let mut cells = map.cleared();
+                self.flatten(root, 0, 0, &mut cells);
+                self.reset(map.rule());
+                root = self.build(&cells, level, 0, 0);
+            }
+        }
+        let mut next = map.cleared();
+        self.flatten(root, 0, 0, &mut next);
+        next.carry_ages(map, generations);
+        self.last = Some((next.state_hash(), size, root));
+        next
+    }
+}
@@ -0,0 +1,206 @@
+use bevy::diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+
+use crate::settings::Settings;
+use crate::sim::{Keys, SimStats, Stabilization};
+
+// how much text is drawn over the board
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HudLayout {
+    Minimal, // nothing but the board
+    #[default]
+    Standard, // state and generation
+    Research, // plus full stats and diagnostics
+}
+
+impl HudLayout {
+    fn name(&self) -> &'static str {
+        match self {
+            HudLayout::Minimal => "minimal",
+            HudLayout::Standard => "standard",
+            HudLayout::Research => "research",
+        }
+    }
+
+    fn parse(name: &str) -> Option<HudLayout> {
+        match name {
+            "minimal" => Some(HudLayout::Minimal),
+            "standard" => Some(HudLayout::Standard),
+            "research" => Some(HudLayout::Research),
+            _ => None,
+        }
+    }
+}
+
+// text nodes hidden by the minimal layout
+#[derive(Component)]
+pub struct HudElement;
+
+#[derive(Component)]
+struct ResearchPanel;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(FrameTimeDiagnosticsPlugin)
+            .init_resource::<HudLayout>()
+            .add_startup_system(spawn_panel)
+            .add_startup_system(load_layout)
+            .add_system(handle_keyboard)
+            .add_system(save_layout.after(handle_keyboard))
+            .add_system(apply_layout)
+            .add_system(update_panel)
+            .add_system(update_title);
+    }
+}
+
+fn spawn_panel(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("Kid Marker.ttf"),
+                font_size: 18.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(35.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+        ResearchPanel,
+        HudElement,
+    ));
+}
+
+// the layout picked last time, an unknown one is the default
+fn load_layout(mut layout: ResMut<HudLayout>) {
+    let settings = Settings::load();
+    if let Some(picked) = settings.get("hud_layout").and_then(HudLayout::parse) {
+        *layout = picked;
+    }
+}
+
+// F4 changes it, the one loaded at startup is already there
+fn save_layout(layout: Res<HudLayout>) {
+    if !layout.is_changed() || layout.is_added() {
+        return;
+    }
+    let mut settings = Settings::load();
+    if settings.get("hud_layout") == Some(layout.name()) {
+        return;
+    }
+    settings.set("hud_layout", layout.name().to_string());
+    if let Err(e) = settings.save() {
+        warn!("{}", e);
+    }
+}
+
+// F4 -> cycle minimal / standard / research
+fn handle_keyboard(key: Keys, mut layout: ResMut<HudLayout>) {
+    if key.just_pressed(|k| &k.hud_layout) {
+        *layout = match *layout {
+            HudLayout::Minimal => HudLayout::Standard,
+            HudLayout::Standard => HudLayout::Research,
+            HudLayout::Research => HudLayout::Minimal,
+        };
+    }
+}
+
+fn apply_layout(
+    layout: Res<HudLayout>,
+    mut elements: Query<&mut Visibility, (With<HudElement>, Without<ResearchPanel>)>,
+    mut panel: Query<&mut Visibility, With<ResearchPanel>>,
+) {
+    if !layout.is_changed() {
+        return;
+    }
+    for mut visibility in elements.iter_mut() {
+        visibility.is_visible = *layout != HudLayout::Minimal;
+    }
+    for mut visibility in panel.iter_mut() {
+        visibility.is_visible = *layout == HudLayout::Research;
+    }
+}
+
+fn update_panel(
+    layout: Res<HudLayout>,
+    stats: Res<SimStats>,
+    diagnostics: Res<Diagnostics>,
+    mut query: Query<&mut Text, With<ResearchPanel>>,
+) {
+    if *layout != HudLayout::Research {
+        return;
+    }
+    let Ok(mut text) = query.get_single_mut() else {
+        return;
+    };
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or(0.0);
+    let bounds = match stats.bounding_box {
+        Some((min, max)) => format!("({}, {}) - ({}, {})", min.x, min.y, max.x, max.y),
+        None => "-".to_string(),
+    };
+    let stable = match stats.stabilization {
+        Stabilization::Evolving => "evolving".to_string(),
+        Stabilization::Extinct => "extinct".to_string(),
+        other => other.label().trim().to_string(),
+    };
+    text.sections[0].value = format!(
+        "population {}\nbirths {}  deaths {}\nbounds {}\n{}\ncomponents {} (largest {})\nhash {:016x}\n{:.0} fps",
+        stats.population,
+        stats.births,
+        stats.deaths,
+        bounds,
+        stable,
+        stats.components,
+        stats.largest_component,
+        stats.hash,
+        fps
+    );
+}
+
+// 12340 -> "12,340"
+fn grouped(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+// "Bevy_app — gen 12,340 — pop 5,112", readable from the taskbar too
+fn update_title(
+    stats: Res<SimStats>,
+    mut windows: ResMut<Windows>,
+    mut base: Local<Option<String>>,
+) {
+    let Some(window) = windows.get_primary_mut() else {
+        return;
+    };
+    if !stats.is_changed() {
+        return;
+    }
+    let base = base.get_or_insert_with(|| window.title().to_string());
+    let title = format!(
+        "{} — gen {} — pop {}",
+        base,
+        grouped(stats.generation),
+        grouped(stats.population as u64)
+    );
+    if window.title() != title {
+        window.set_title(title);
+    }
+}
@@ -0,0 +1,100 @@
+use bevy::ecs::system::SystemParam;
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::mouse::{MouseButtonInput, MouseWheel};
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use bevy::winit::{UpdateMode, WinitSettings};
+
+use crate::sim::State;
+
+// frames while idle are only drawn on input or after this long, so timers and toasts still tick
+const IDLE_WAIT: Duration = Duration::from_secs(1);
+
+// --idle <seconds>, how long a paused board goes without input before the app idles
+// None never idles
+#[derive(Resource, Clone, Copy)]
+pub struct IdleTimeout(pub Option<f32>);
+
+impl Default for IdleTimeout {
+    fn default() -> Self {
+        IdleTimeout(Some(30.0))
+    }
+}
+
+#[derive(Resource, Default)]
+struct Idle {
+    since_input: f32, // seconds
+    asleep: bool,
+}
+
+// anything the user does that should wake the app
+#[derive(SystemParam)]
+struct InputActivity<'w, 's> {
+    keys: EventReader<'w, 's, KeyboardInput>,
+    buttons: EventReader<'w, 's, MouseButtonInput>,
+    cursor: EventReader<'w, 's, CursorMoved>,
+    wheel: EventReader<'w, 's, MouseWheel>,
+}
+
+impl InputActivity<'_, '_> {
+    fn any(&mut self) -> bool {
+        // every reader is drained, so old events don't count next frame
+        let counts = [
+            self.keys.iter().count(),
+            self.buttons.iter().count(),
+            self.cursor.iter().count(),
+            self.wheel.iter().count(),
+        ];
+        counts.iter().any(|&n| n > 0)
+    }
+}
+
+pub struct IdlePlugin;
+
+impl Plugin for IdlePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<IdleTimeout>()
+            .init_resource::<Idle>()
+            .add_system(update_mode);
+    }
+}
+
+// a paused board left alone stops redrawing continuously, the first input or play resumes it
+fn update_mode(
+    time: Res<Time>,
+    timeout: Res<IdleTimeout>,
+    state: Res<State>,
+    mut input: InputActivity,
+    mut idle: ResMut<Idle>,
+    settings: Option<ResMut<WinitSettings>>,
+) {
+    let Some(mut settings) = settings else {
+        return;
+    };
+    if input.any() || state.0 {
+        idle.since_input = 0.0;
+    } else {
+        idle.since_input += time.delta_seconds();
+    }
+    let idle_now = timeout.0.is_some_and(|seconds| idle.since_input >= seconds);
+    if idle_now == idle.asleep {
+        return;
+    }
+    idle.asleep = idle_now;
+    let mode = || match idle_now {
+        true => UpdateMode::Reactive {
+            max_wait: IDLE_WAIT,
+        },
+        false => UpdateMode::Continuous,
+    };
+    settings.focused_mode = mode();
+    settings.unfocused_mode = mode();
+    info!(
+        "{}",
+        if idle_now {
+            "idle, redrawing on input only"
+        } else {
+            "awake"
+        }
+    );
+}
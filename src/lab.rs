@@ -0,0 +1,668 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::ui::{FocusPolicy, UiSystem};
+use bevy::utils::{Duration, Instant};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::board::{Board, Boundary, FrozenRegions};
+use crate::menu::Screen;
+use crate::rule::{PluginRules, Ruleset};
+use crate::sim::{Keys, RandomizeBoard, SimRng, StartupScene};
+use crate::thumbnail::Thumbnailer;
+use crate::toast::Toast;
+
+const BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+const HOVERED: Color = Color::rgb(0.3, 0.45, 0.25);
+const SELECTED: Color = Color::rgb(0.2, 0.3, 0.5);
+const FONT_SIZE: f32 = 14.0;
+// boards shown at once, a 3x3 grid
+const SLOTS: usize = 9;
+// every candidate runs the main board, or the same soup, on a small torus for a while, then stops
+const SIDE: u32 = 48;
+const DENSITY: f64 = 0.3;
+const GENERATIONS: u64 = 200;
+const THUMBNAIL: f32 = 96.0;
+// rules offered to compare: the one in use, the presets, then plugin rules up to this many
+const CHOICES: usize = 16;
+// surprise me: random rules on random soups, tried for a few milliseconds a frame, only the
+// ones whose population neither dies out, fills the board nor freezes over GENERATIONS are kept
+const SEARCH_BUDGET: Duration = Duration::from_millis(8);
+const MAX_TRIES: u32 = 400;
+const CHECK_EVERY: u64 = 20;
+const MIN_ALIVE: f64 = 0.02;
+const MAX_ALIVE: f64 = 0.6;
+
+#[derive(Clone, Copy, Default)]
+struct Soup {
+    seed: u64,
+    density: f64,
+}
+
+// a rule run on its own small board
+struct Candidate {
+    rule: Ruleset,
+    label: String,
+    soup: Soup,
+    board: Board,
+    generation: u64,
+}
+
+// the live cells of the main board centred on the lab's torus, the ones past its sides cut off,
+// None for an empty board
+fn cropped(map: &Board) -> Option<Board> {
+    let (min, max) = map.bounding_box()?;
+    let center = ((min + max) / 2).as_ivec2();
+    Some(map.resized(UVec2::splat(SIDE), IVec2::splat(SIDE as i32 / 2) - center))
+}
+
+impl Candidate {
+    // `from` is the board to start on, without one the soup is drawn
+    fn new(rule: Ruleset, label: String, soup: Soup, from: Option<&Board>) -> Self {
+        let mut board = match from {
+            Some(from) => from.clone(),
+            None => {
+                let scene = StartupScene::Soup {
+                    density: soup.density,
+                };
+                scene.build_board(UVec2::splat(SIDE), &mut SimRng::new(Some(soup.seed)))
+            }
+        };
+        board.set_rule(rule);
+        board.set_boundary(Boundary::Toroidal);
+        Candidate {
+            rule,
+            label,
+            soup,
+            board,
+            generation: 0,
+        }
+    }
+
+    // run headless to the end, checked every CHECK_EVERY generations: the same board twice in a
+    // row is a still life or an oscillator of a period dividing CHECK_EVERY
+    fn is_lively(&self) -> bool {
+        let frozen = FrozenRegions::default();
+        let area = (SIDE * SIDE) as f64;
+        let mut board = self.board.clone();
+        let mut last = board.state_hash();
+        for generation in 1..=GENERATIONS {
+            board = board.step(&frozen);
+            if generation % CHECK_EVERY == 0 {
+                let alive = board.population() as f64 / area;
+                let hash = board.state_hash();
+                if !(MIN_ALIVE..=MAX_ALIVE).contains(&alive) || hash == last {
+                    return false;
+                }
+                last = hash;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+enum View {
+    #[default]
+    Mutations,
+    Surprise,
+    Compare,
+}
+
+// the rule explorer: the rule in use and mutations of it side by side, a click adopts one and
+// the next mutations are drawn around it, or random rules that passed `is_lively`, or rules
+// picked to compare
+#[derive(Resource, Default)]
+struct Lab {
+    show: bool,
+    view: View,
+    from: Option<Board>, // the main board as cropped, every mutation starts from it
+    seed: u64,           // of the soup they start from instead after New soup
+    search: Option<u32>, // tries so far while looking for surprises
+    choices: Vec<(Ruleset, String)>, // with their names, to pick the compared rules from
+    selected: Vec<Ruleset>,
+    candidates: Vec<Candidate>,
+    images: Vec<Handle<Image>>, // one per slot, redrawn as the boards run
+    rng: SimRng,
+}
+
+impl Lab {
+    fn start(&mut self, rules: Vec<(Ruleset, String)>) {
+        let soup = Soup {
+            seed: self.seed,
+            density: DENSITY,
+        };
+        self.candidates = rules
+            .into_iter()
+            .take(SLOTS)
+            .map(|(rule, label)| Candidate::new(rule, label, soup, self.from.as_ref()))
+            .collect();
+    }
+
+    // what the main board looks like now, an empty one leaves the candidates on the soup
+    fn take_board(&mut self, map: &Board) {
+        self.from = cropped(map);
+    }
+
+    // the rule itself first, then single births or survivals added or taken out, in random order
+    fn mutate(&mut self, rule: Ruleset) {
+        let mut mutations: Vec<(Ruleset, String)> = (0..=8)
+            .flat_map(|n| [(true, n), (false, n)])
+            .filter_map(|(birth, n)| {
+                let mutated = rule.toggled(birth, n)?;
+                let (births, survivals) = mutated.masks();
+                let added = if birth { births } else { survivals } & 1 << n != 0;
+                let change = format!(
+                    "{}{}{}",
+                    if added { '+' } else { '-' },
+                    if birth { 'B' } else { 'S' },
+                    n
+                );
+                Some((mutated, format!("{}  {}", mutated, change)))
+            })
+            .collect();
+        mutations.shuffle(&mut self.rng.0);
+        let current = (rule, format!("{}  now", rule));
+        self.view = View::Mutations;
+        self.search = None;
+        self.start(std::iter::once(current).chain(mutations).collect());
+    }
+
+    // the rule in use and the presets side by side at first, more can be swapped in
+    fn compare(&mut self, rule: Ruleset, plugins: &PluginRules) {
+        self.choices.clear();
+        let offered = std::iter::once(rule)
+            .chain(Ruleset::presets())
+            .chain(plugins.0.iter().map(|plugin| plugin.rule));
+        for rule in offered {
+            if self.choices.len() < CHOICES && !self.choices.iter().any(|(r, _)| *r == rule) {
+                self.choices.push((rule, plugins.name(&rule)));
+            }
+        }
+        self.selected = self.choices.iter().take(SLOTS).map(|(r, _)| *r).collect();
+        self.view = View::Compare;
+        self.search = None;
+        self.matrix();
+    }
+
+    // the selected rules in the order they are offered, labelled with their rulestrings,
+    // MAP rules are too long for that and go by their names
+    fn matrix(&mut self) {
+        let rules = self
+            .choices
+            .iter()
+            .filter(|(rule, _)| self.selected.contains(rule))
+            .map(|(rule, name)| match rule.is_totalistic() {
+                true => (*rule, rule.to_string()),
+                false => (*rule, name.clone()),
+            })
+            .collect();
+        self.start(rules);
+    }
+
+    fn surprise(&mut self) {
+        self.view = View::Surprise;
+        self.search = Some(0);
+        self.candidates.clear();
+    }
+
+    // a random two state rule without B0 on a random soup, kept if it is lively
+    fn try_random(&mut self) {
+        let rng = &mut self.rng.0;
+        let birth = rng.gen_range(1..256u16) << 1;
+        let Some(rule) = Ruleset::life_like(birth, rng.gen_range(0..512)) else {
+            return;
+        };
+        let soup = Soup {
+            seed: rng.gen(),
+            density: rng.gen_range(0.1..0.5),
+        };
+        let label = format!("{}  {:.0}%", rule, soup.density * 100.0);
+        let candidate = Candidate::new(rule, label, soup, None);
+        if candidate.is_lively() {
+            self.candidates.push(candidate);
+        }
+    }
+}
+
+#[derive(Component)]
+struct LabPanel;
+
+#[derive(Component)]
+struct LabSlot(usize);
+
+// the row of rules to compare, only laid out in that view
+#[derive(Component)]
+struct LabChoices;
+
+#[derive(Component)]
+struct LabChoice(usize);
+
+#[derive(Component)]
+struct ChoiceLabel(usize);
+
+// the rule of the candidate in a slot
+#[derive(Component)]
+struct LabLabel(usize);
+
+#[derive(Component, Clone, Copy)]
+enum LabButton {
+    Pick(usize),
+    Soup,
+    Mutations,
+    Surprise,
+    Compare,
+    Choice(usize),
+}
+
+// what the buttons act on
+#[derive(SystemParam)]
+struct LabActions<'w, 's> {
+    rule: ResMut<'w, Ruleset>,
+    plugins: Res<'w, PluginRules>,
+    map: Res<'w, Board>,
+    randomizes: EventWriter<'w, 's, RandomizeBoard>,
+    toasts: EventWriter<'w, 's, Toast>,
+}
+
+impl LabActions<'_, '_> {
+    fn run(&mut self, button: LabButton, lab: &mut Lab) {
+        match button {
+            // the new rule is mutated in turn, see follow_rule, a surprise comes with a soup
+            // as dense as the one it was tried on
+            LabButton::Pick(i) => match lab.candidates.get(i) {
+                Some(candidate) if lab.view == View::Surprise => {
+                    *self.rule = candidate.rule;
+                    self.randomizes.send(RandomizeBoard {
+                        density: candidate.soup.density,
+                    });
+                }
+                Some(candidate) if candidate.rule != *self.rule => *self.rule = candidate.rule,
+                _ => {}
+            },
+            // off the main board until Mutations or Compare is pressed again
+            LabButton::Soup => {
+                lab.seed = lab.rng.0.gen();
+                lab.from = None;
+                let seed = lab.seed;
+                for candidate in lab.candidates.iter_mut() {
+                    let soup = Soup {
+                        seed,
+                        ..candidate.soup
+                    };
+                    let label = candidate.label.clone();
+                    *candidate = Candidate::new(candidate.rule, label, soup, None);
+                }
+            }
+            LabButton::Mutations => self.mutate(lab),
+            LabButton::Surprise => lab.surprise(),
+            LabButton::Compare => {
+                lab.take_board(&self.map);
+                lab.compare(*self.rule, &self.plugins);
+            }
+            LabButton::Choice(i) => {
+                let Some(&(rule, _)) = lab.choices.get(i) else {
+                    return;
+                };
+                if let Some(at) = lab.selected.iter().position(|r| *r == rule) {
+                    lab.selected.remove(at);
+                } else if lab.selected.len() < SLOTS {
+                    lab.selected.push(rule);
+                } else {
+                    self.toasts
+                        .send(Toast(format!("at most {} rules side by side", SLOTS)));
+                    return;
+                }
+                lab.matrix();
+            }
+        }
+    }
+
+    fn mutate(&mut self, lab: &mut Lab) {
+        if !self.rule.is_totalistic() {
+            self.toasts
+                .send(Toast("only B/S rules can be mutated".to_string()));
+            return;
+        }
+        lab.take_board(&self.map);
+        lab.mutate(*self.rule);
+    }
+}
+
+pub struct LabPlugin;
+
+impl Plugin for LabPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Lab>()
+            .add_startup_system(spawn_panel)
+            .add_system(handle_keyboard)
+            .add_system(follow_rule.after(handle_keyboard))
+            // clicks on the panel are taken before the board's systems get to see them
+            .add_system_to_stage(CoreStage::PreUpdate, handle_input.after(UiSystem::Focus))
+            .add_system(search_rules.after(follow_rule))
+            .add_system(run_candidates.after(search_rules))
+            .add_system(update_panel.after(run_candidates))
+            .add_system(update_choices.after(run_candidates));
+    }
+}
+
+fn spawn_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut lab: ResMut<Lab>,
+    mut thumbnails: Thumbnailer,
+) {
+    lab.images = (0..SLOTS).map(|_| thumbnails.live()).collect();
+    let font = asset_server.load("Kid Marker.ttf");
+    let text = |value: &str| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font.clone(),
+                font_size: FONT_SIZE,
+                color: Color::WHITE,
+            },
+        )
+    };
+    let button = |parent: &mut ChildBuilder, button: LabButton, label: &str| {
+        parent
+            .spawn((
+                ButtonBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(4.0)),
+                        margin: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    background_color: BUTTON.into(),
+                    ..default()
+                },
+                button,
+            ))
+            .with_children(|inner| {
+                inner.spawn(text(label));
+            });
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(200.0),
+                        top: Val::Px(80.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(6.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.75).into(),
+                visibility: Visibility { is_visible: false },
+                ..default()
+            },
+            Interaction::default(),
+            FocusPolicy::Block,
+            LabPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn(NodeBundle::default()).with_children(|row| {
+                button(row, LabButton::Mutations, "New mutations");
+                button(row, LabButton::Soup, "New soup");
+                button(row, LabButton::Surprise, "Surprise me");
+                button(row, LabButton::Compare, "Compare");
+            });
+            panel
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            display: Display::None,
+                            flex_wrap: FlexWrap::Wrap,
+                            size: Size::new(Val::Px(3.0 * (THUMBNAIL + 12.0)), Val::Undefined),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    LabChoices,
+                ))
+                .with_children(|row| {
+                    for i in 0..CHOICES {
+                        row.spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::all(Val::Px(4.0)),
+                                    margin: UiRect::all(Val::Px(2.0)),
+                                    ..default()
+                                },
+                                background_color: BUTTON.into(),
+                                ..default()
+                            },
+                            LabButton::Choice(i),
+                            LabChoice(i),
+                        ))
+                        .with_children(|choice| {
+                            choice.spawn((text(""), ChoiceLabel(i)));
+                        });
+                    }
+                });
+            panel
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_wrap: FlexWrap::Wrap,
+                        size: Size::new(Val::Px(3.0 * (THUMBNAIL + 12.0)), Val::Undefined),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|grid| {
+                    for (i, image) in lab.images.iter().enumerate() {
+                        grid.spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Column,
+                                    align_items: AlignItems::Center,
+                                    padding: UiRect::all(Val::Px(2.0)),
+                                    margin: UiRect::all(Val::Px(2.0)),
+                                    size: Size::new(Val::Px(THUMBNAIL + 8.0), Val::Undefined),
+                                    ..default()
+                                },
+                                background_color: BUTTON.into(),
+                                ..default()
+                            },
+                            LabButton::Pick(i),
+                            LabSlot(i),
+                        ))
+                        .with_children(|slot| {
+                            slot.spawn(ImageBundle {
+                                image: image.clone().into(),
+                                style: Style {
+                                    size: Size::new(Val::Px(THUMBNAIL), Val::Px(THUMBNAIL)),
+                                    ..default()
+                                },
+                                ..default()
+                            });
+                            slot.spawn((text(""), LabLabel(i)));
+                        });
+                    }
+                });
+        });
+}
+
+// F10 -> show/hide the rule explorer, opened on mutations of the rule in use
+fn handle_keyboard(key: Keys, mut lab: ResMut<Lab>, mut actions: LabActions) {
+    if !key.just_pressed(|k| &k.lab) {
+        return;
+    }
+    lab.show = !lab.show;
+    if lab.show {
+        lab.seed = lab.rng.0.gen();
+        actions.mutate(&mut lab);
+    }
+}
+
+// a rule adopted from the explorer, or picked any other way, gets mutated in turn, on the main
+// board as it is by then unless a new soup was asked for
+// surprises stay up to be picked one after the other
+fn follow_rule(rule: Res<Ruleset>, map: Res<Board>, mut lab: ResMut<Lab>) {
+    if lab.show && lab.view == View::Mutations && rule.is_changed() && !rule.is_added() {
+        if lab.from.is_some() {
+            lab.take_board(&map);
+        }
+        lab.mutate(*rule);
+    }
+}
+
+// random rules tried until the slots are full, SEARCH_BUDGET a frame, at least one try each
+fn search_rules(mut lab: ResMut<Lab>, mut toasts: EventWriter<Toast>) {
+    let Some(mut tries) = lab.search.filter(|_| lab.show) else {
+        return;
+    };
+    let started = Instant::now();
+    while lab.candidates.len() < SLOTS && tries < MAX_TRIES {
+        lab.try_random();
+        tries += 1;
+        if started.elapsed() >= SEARCH_BUDGET {
+            break;
+        }
+    }
+    lab.search = Some(tries);
+    if lab.candidates.len() == SLOTS || tries == MAX_TRIES {
+        lab.search = None;
+        if lab.candidates.len() < SLOTS {
+            toasts.send(Toast(format!(
+                "only {} lively rules found in {} tries",
+                lab.candidates.len(),
+                tries
+            )));
+        }
+    }
+}
+
+fn handle_input(
+    screen: Res<State<Screen>>,
+    mut mouse: ResMut<Input<MouseButton>>,
+    mut lab: ResMut<Lab>,
+    buttons: Query<(&Interaction, &LabButton), Changed<Interaction>>,
+    panels: Query<&Interaction, With<LabPanel>>,
+    mut actions: LabActions,
+) {
+    if !lab.show || *screen.current() != Screen::Board {
+        return;
+    }
+    for (interaction, &button) in buttons.iter() {
+        if *interaction == Interaction::Clicked {
+            actions.run(button, &mut lab);
+        }
+    }
+    if panels
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+    {
+        mouse.clear();
+    }
+}
+
+// a generation of every candidate per frame, until they have run long enough
+fn run_candidates(mut lab: ResMut<Lab>, mut thumbnails: Thumbnailer) {
+    if !lab.show {
+        return;
+    }
+    let frozen = FrozenRegions::default();
+    let lab = &mut *lab;
+    for (candidate, image) in lab.candidates.iter_mut().zip(lab.images.iter()) {
+        let fresh = candidate.generation == 0;
+        if candidate.generation < GENERATIONS {
+            candidate.board = candidate.board.step(&frozen);
+            candidate.generation += 1;
+        }
+        if fresh || candidate.generation < GENERATIONS {
+            thumbnails.redraw(image, &candidate.board);
+        }
+    }
+}
+
+type LabButtons<'a> = (&'a Interaction, &'a mut BackgroundColor);
+// the choices are colored by update_choices
+type PlainButtons = (Changed<Interaction>, With<LabButton>, Without<LabChoice>);
+
+// hidden on the menus, the slots without a candidate too
+fn update_panel(
+    lab: Res<Lab>,
+    screen: Res<State<Screen>>,
+    mut panels: Query<&mut Visibility, (With<LabPanel>, Without<LabSlot>)>,
+    mut slots: Query<(&LabSlot, &mut Visibility), Without<LabPanel>>,
+    mut labels: Query<(&LabLabel, &mut Text)>,
+    mut buttons: Query<LabButtons, PlainButtons>,
+) {
+    let show = lab.show && *screen.current() == Screen::Board;
+    for mut visibility in panels.iter_mut() {
+        if visibility.is_visible != show {
+            visibility.is_visible = show;
+        }
+    }
+    for (interaction, mut color) in buttons.iter_mut() {
+        *color = match interaction {
+            Interaction::None => BUTTON.into(),
+            _ => HOVERED.into(),
+        };
+    }
+    for (slot, mut visibility) in slots.iter_mut() {
+        let filled = slot.0 < lab.candidates.len();
+        if visibility.is_visible != filled {
+            visibility.is_visible = filled;
+        }
+    }
+    for (label, mut text) in labels.iter_mut() {
+        let value = lab.candidates.get(label.0).map_or("", |c| c.label.as_str());
+        if text.sections[0].value != value {
+            text.sections[0].value = value.to_string();
+        }
+    }
+}
+
+type ChoiceButtons<'a> = (
+    &'a LabChoice,
+    &'a Interaction,
+    &'a mut BackgroundColor,
+    &'a mut Style,
+);
+
+// the rules on offer to compare, the selected ones highlighted
+fn update_choices(
+    lab: Res<Lab>,
+    mut rows: Query<&mut Style, (With<LabChoices>, Without<LabChoice>)>,
+    mut buttons: Query<ChoiceButtons>,
+    mut labels: Query<(&ChoiceLabel, &mut Text)>,
+) {
+    let display = |shown: bool| match shown {
+        true => Display::Flex,
+        false => Display::None,
+    };
+    for mut style in rows.iter_mut() {
+        if style.display != display(lab.view == View::Compare) {
+            style.display = display(lab.view == View::Compare);
+        }
+    }
+    for (choice, interaction, mut color, mut style) in buttons.iter_mut() {
+        let offered = lab.choices.get(choice.0);
+        if style.display != display(offered.is_some()) {
+            style.display = display(offered.is_some());
+        }
+        let next = match offered {
+            _ if *interaction != Interaction::None => HOVERED,
+            Some((rule, _)) if lab.selected.contains(rule) => SELECTED,
+            _ => BUTTON,
+        };
+        if color.0 != next {
+            *color = next.into();
+        }
+    }
+    for (label, mut text) in labels.iter_mut() {
+        let value = lab
+            .choices
+            .get(label.0)
+            .map_or("", |(_, name)| name.as_str());
+        if text.sections[0].value != value {
+            text.sections[0].value = value.to_string();
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use std::marker::PhantomData;
+
+use crate::menu::Screen;
+use crate::panel::BesidePanel;
+use crate::rule::{PluginRules, Ruleset};
+use crate::sim::CellColors;
+
+const FONT_SIZE: f32 = 14.0;
+const SWATCH: f32 = 12.0;
+// a rule with more states shows the first, the last and evenly spaced ones in between
+const MAX_ROWS: usize = 10;
+
+#[derive(Component)]
+struct LegendPanel;
+
+#[derive(Component)]
+struct LegendTitle;
+
+#[derive(Component)]
+struct LegendRow(usize);
+
+#[derive(Component)]
+struct LegendSwatch(usize);
+
+#[derive(Component)]
+struct LegendText(usize);
+
+pub struct LegendPlugin;
+
+impl Plugin for LegendPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(spawn_panel)
+            .add_system(update_legend);
+    }
+}
+
+fn spawn_panel(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let style = TextStyle {
+        font: asset_server.load("Kid Marker.ttf"),
+        font_size: FONT_SIZE,
+        color: Color::WHITE,
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        bottom: Val::Px(60.0),
+                        right: Val::Px(10.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(6.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.75).into(),
+                visibility: Visibility { is_visible: false },
+                ..default()
+            },
+            LegendPanel,
+            BesidePanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((TextBundle::from_section("", style.clone()), LegendTitle));
+            for i in 0..MAX_ROWS {
+                panel
+                    .spawn((
+                        NodeBundle {
+                            style: Style {
+                                align_items: AlignItems::Center,
+                                margin: UiRect::top(Val::Px(2.0)),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        LegendRow(i),
+                    ))
+                    .with_children(|row| {
+                        row.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    size: Size::new(Val::Px(SWATCH), Val::Px(SWATCH)),
+                                    margin: UiRect::right(Val::Px(6.0)),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            LegendSwatch(i),
+                        ));
+                        row.spawn((TextBundle::from_section("", style.clone()), LegendText(i)));
+                    });
+            }
+        });
+}
+
+// what the legend is drawn from
+#[derive(SystemParam)]
+struct LegendSource<'w, 's> {
+    rule: Res<'w, Ruleset>,
+    plugins: Res<'w, PluginRules>,
+    colors: Res<'w, CellColors>,
+    screen: Res<'w, State<Screen>>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+// the rows of the panel, a state each
+fn rows(source: &LegendSource) -> Vec<(String, Color)> {
+    let states = source.colors.states(source.rule.decay_states());
+    if states.len() <= MAX_ROWS {
+        return states;
+    }
+    (0..MAX_ROWS)
+        .map(|i| states[i * (states.len() - 1) / (MAX_ROWS - 1)].clone())
+        .collect()
+}
+
+// shown on the board while a Generations rule is in use, the states follow the rule and the
+// theme's colors
+fn update_legend(
+    source: LegendSource,
+    mut panels: Query<&mut Visibility, With<LegendPanel>>,
+    mut titles: Query<&mut Text, (With<LegendTitle>, Without<LegendText>)>,
+    mut row_nodes: Query<(&LegendRow, &mut Style)>,
+    mut swatches: Query<(&LegendSwatch, &mut BackgroundColor)>,
+    mut texts: Query<(&LegendText, &mut Text), Without<LegendTitle>>,
+) {
+    if !source.rule.is_changed() && !source.colors.is_changed() && !source.screen.is_changed() {
+        return;
+    }
+    let show = source.rule.decay_states() > 0 && *source.screen.current() == Screen::Board;
+    for mut visibility in panels.iter_mut() {
+        visibility.is_visible = show;
+    }
+    if !show {
+        return;
+    }
+    let rows = rows(&source);
+    for mut text in titles.iter_mut() {
+        text.sections[0].value = source.plugins.name(&source.rule);
+    }
+    for (row, mut style) in row_nodes.iter_mut() {
+        style.display = match row.0 < rows.len() {
+            true => Display::Flex,
+            false => Display::None,
+        };
+    }
+    for (swatch, mut color) in swatches.iter_mut() {
+        if let Some((_, state)) = rows.get(swatch.0) {
+            *color = (*state).into();
+        }
+    }
+    for (label, mut text) in texts.iter_mut() {
+        if let Some((name, _)) = rows.get(label.0) {
+            text.sections[0].value = name.clone();
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use bevy::prelude::*;
+
+pub mod about;
+pub mod analysis;
+pub mod atlas;
+pub mod audio;
+pub mod board;
+pub mod brush;
+pub mod chaos;
+pub mod checkpoint;
+pub mod cli;
+pub mod command;
+pub mod components;
+pub mod convert;
+pub mod density;
+pub mod drift;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dylib;
+pub mod edge;
+pub mod freeze;
+pub mod gpu;
+pub mod hashlife;
+pub mod hud;
+pub mod idle;
+pub mod lab;
+pub mod legend;
+pub mod library;
+pub mod menu;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod mirror;
+pub mod panel;
+pub mod pattern;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod placement;
+pub mod probe;
+pub mod recipe;
+pub mod rle;
+pub mod rule;
+pub mod rulebook;
+pub mod select;
+pub mod settings;
+pub mod shapes;
+pub mod sim;
+pub mod snippet;
+pub mod sparkline;
+pub mod sparse;
+pub mod speed;
+pub mod spray;
+pub mod stress;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sync;
+pub mod template;
+pub mod theme;
+pub mod thumbnail;
+pub mod timeline;
+pub mod toast;
+pub mod trace;
+pub mod trail;
+pub mod validate;
+pub mod worker;
+
+// what a host app picks up front, everything else is changed at runtime with the keys
+#[derive(Clone)]
+pub struct ConwayConfig {
+    pub grid: sim::GridConfig,
+    pub colors: sim::CellColors,
+    pub rate: Option<f32>, // generations per second, None = every frame, 0 or less is refused
+    pub keys: sim::KeyBindings,
+    pub menu: bool, // open on the main menu instead of the board
+}
+
+impl Default for ConwayConfig {
+    fn default() -> Self {
+        ConwayConfig {
+            grid: default(),
+            colors: default(),
+            rate: speed::Speed::default().rate,
+            keys: default(),
+            menu: false,
+        }
+    }
+}
+
+// the whole game of life, board, HUD and tools, for an app that already has
+// DefaultPlugins and a 2d camera
+pub struct ConwayPlugin {
+    config: ConwayConfig,
+}
+
+impl ConwayPlugin {
+    pub fn new(config: ConwayConfig) -> Self {
+        ConwayPlugin { config }
+    }
+}
+
+impl Plugin for ConwayPlugin {
+    fn build(&self, app: &mut App) {
+        let config = self.config.clone();
+        // the host's background is the one of the default theme
+        let background = app.world.get_resource::<ClearColor>().map(|clear| clear.0);
+        let theme = theme::Theme::new(config.colors.clone(), background.unwrap_or(Color::BLACK));
+        app.insert_resource(config.grid)
+            .insert_resource(config.colors)
+            .insert_resource(theme)
+            .insert_resource(speed::Speed::new(config.rate))
+            .insert_resource(config.keys)
+            .add_plugin(toast::ToastPlugin)
+            .add_plugin(thumbnail::ThumbnailPlugin)
+            .add_plugin(speed::SpeedPlugin)
+            .add_plugin(rule::RulePlugin)
+            .add_plugin(theme::ThemePlugin)
+            .add_plugin(audio::SoundPlugin)
+            .add_plugin(sim::SimPlugin)
+            .add_plugin(hud::HudPlugin)
+            .add_plugin(recipe::RecipePlugin)
+            .add_plugin(freeze::FreezePlugin)
+            .add_plugin(about::AboutPlugin)
+            .add_plugin(spray::SprayPlugin)
+            .add_plugin(brush::BrushPlugin)
+            .add_plugin(shapes::ShapesPlugin)
+            .add_plugin(select::SelectPlugin)
+            .add_plugin(probe::ProbePlugin)
+            .add_plugin(components::ComponentsPlugin)
+            .add_plugin(drift::DriftPlugin)
+            .add_plugin(chaos::ChaosPlugin)
+            .add_plugin(edge::EdgePlugin)
+            .add_plugin(pattern::PatternPlugin)
+            .add_plugin(library::LibraryPlugin)
+            .add_plugin(density::DensityPlugin)
+            .add_plugin(analysis::AnalysisPlugin)
+            .add_plugin(sparkline::SparklinePlugin)
+            .add_plugin(idle::IdlePlugin)
+            .add_plugin(trail::TrailPlugin)
+            .add_plugin(menu::MenuPlugin { open: config.menu })
+            .add_plugin(panel::PanelPlugin)
+            .add_plugin(timeline::TimelinePlugin)
+            .add_plugin(rulebook::RulebookPlugin)
+            .add_plugin(lab::LabPlugin)
+            .add_plugin(legend::LegendPlugin)
+            .add_plugin(checkpoint::CheckpointPlugin)
+            .add_plugin(command::CommandPlugin)
+            .add_plugin(validate::ValidatePlugin);
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_plugin(dylib::DylibPlugin)
+            .add_plugin(sync::SyncPlugin);
+    }
+}
@@ -0,0 +1,496 @@
+use bevy::prelude::*;
+use bevy::window::FileDragAndDrop;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+use zip::ZipArchive;
+
+use crate::convert;
+use crate::pattern::{self, Aim, Placer, Stamped};
+use crate::rle::{self, Pattern};
+use crate::select::TileFill;
+use crate::settings::Settings;
+use crate::sim::{BoardClick, CellColors, Keys, Tool, SIZE};
+use crate::toast::Toast;
+
+// the classics, as RLE like the LifeWiki has them
+pub const PATTERNS: [(&str, &str); 6] = [
+    ("glider", "x = 3, y = 3\nbo$2bo$3o!"),
+    ("LWSS", "x = 5, y = 4\nbo2bo$o4b$o3bo$4o!"),
+    (
+        "Gosper glider gun",
+        "x = 36, y = 9\n24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$\
+         2o8bo3bob2o4bobo$10bo5bo7bo$11bo3bo$12b2o!",
+    ),
+    (
+        "pulsar",
+        "x = 13, y = 13\n2b3o3b3o2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2$2b3o3b3o$\
+         o4bobo4bo$o4bobo4bo$o4bobo4bo2$2b3o3b3o!",
+    ),
+    ("R-pentomino", "x = 3, y = 3\nb2o$2o$bo!"),
+    ("acorn", "x = 7, y = 3\nbo$3bo$2o2b3o!"),
+];
+
+// a pattern of PATTERNS by name, any case, with - or _ for the spaces ("gosper-glider-gun")
+pub fn find(name: &str) -> Option<Pattern> {
+    let spaced = |name: &str| name.replace(['-', '_'], " ");
+    let (_, text) = PATTERNS
+        .iter()
+        .find(|(n, _)| spaced(n).eq_ignore_ascii_case(&spaced(name)))?;
+    rle::parse(text).ok()
+}
+
+// the ghost is the live color, faded
+const GHOST_ALPHA: f32 = 0.4;
+// stamped patterns remembered for the top of the Library page
+const MAX_RECENT: usize = 3;
+// files of an archive bigger than this are not patterns anyone stamps
+const MAX_ARCHIVED_FILE: u64 = 1 << 20;
+// names in settings.txt, | doesn't turn up in file names the way commas do
+const SEPARATOR: char = '|';
+// the pattern index last synced from the about panel, next to settings.txt
+pub const INDEX_FILE: &str = "library_index.json";
+
+pub struct LibraryEntry {
+    pub name: String,
+    pub pattern: Pattern,
+    pub source: Option<String>, // the archive or INDEX_FILE, None for the classics
+}
+
+// asks for the pattern index to be downloaded again, see sync.rs
+pub struct SyncIndex;
+
+// the classics, then the patterns of every archive opened so far and of the synced index
+// the pattern stamped while the tool is Tool::Stamp
+#[derive(Resource, Default)]
+pub struct Library {
+    pub entries: Vec<LibraryEntry>,
+    sources: Vec<String>, // the archives' paths, remembered in settings.txt
+    current: usize,
+}
+
+impl Library {
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.name == name)
+    }
+
+    // the patterns of a new index take the place of the last one's
+    pub fn replace_index(&mut self, entries: Vec<LibraryEntry>) {
+        let index = Some(INDEX_FILE.to_string());
+        self.entries.retain(|entry| entry.source != index);
+        self.entries.extend(entries);
+        self.current = self.current.min(self.entries.len().saturating_sub(1));
+    }
+}
+
+// picks entries[i] for stamping, from the menu
+pub struct PickPattern(pub usize);
+
+// names of library patterns, the last stamped (newest first) and the ones marked on the Library
+// page, kept in settings.txt
+#[derive(Resource, Default)]
+pub struct PatternPicks {
+    pub recent: Vec<String>,
+    pub favorites: Vec<String>,
+}
+
+impl PatternPicks {
+    pub fn is_favorite(&self, name: &str) -> bool {
+        self.favorites.iter().any(|f| f == name)
+    }
+
+    pub fn toggle_favorite(&mut self, name: &str) {
+        match self.is_favorite(name) {
+            true => self.favorites.retain(|f| f != name),
+            false => self.favorites.push(name.to_string()),
+        }
+    }
+
+    fn stamped(&mut self, name: &str) {
+        self.recent.retain(|r| r != name);
+        self.recent.insert(0, name.to_string());
+        self.recent.truncate(MAX_RECENT);
+    }
+}
+
+fn split(text: &str) -> Vec<String> {
+    text.split(SEPARATOR)
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// the .rle and .cells files anywhere in a zip archive, named after the file, other files and
+// ones that don't parse are skipped
+fn read_archive(path: &str) -> Result<Vec<LibraryEntry>, String> {
+    let file = File::open(path).map_err(|e| format!("could not read {}: {}", path, e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("{}: {}", path, e))?;
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let Ok(mut file) = archive.by_index(i) else {
+            continue;
+        };
+        let name = Path::new(file.name()).to_path_buf();
+        let extension = name
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase);
+        let Some(stem) = name.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+            continue;
+        };
+        if !file.is_file() || file.size() > MAX_ARCHIVED_FILE {
+            continue;
+        }
+        let mut text = String::new();
+        if file.read_to_string(&mut text).is_err() {
+            continue;
+        }
+        let pattern = match extension.as_deref() {
+            Some("rle") => rle::parse(&text).ok(),
+            Some("cells") => Some(convert::parse_cells(&text)),
+            _ => None,
+        };
+        if let Some(pattern) = pattern.filter(|p| !p.alive.is_empty()) {
+            entries.push(LibraryEntry {
+                name: stem,
+                pattern,
+                source: Some(path.to_string()),
+            });
+        }
+    }
+    Ok(entries)
+}
+
+// {"patterns": [{"name": "glider", "rle": "x = 3, y = 3\nbo$2bo$3o!"}, ..]}, the entries that
+// lack either or don't parse are skipped
+pub fn read_index(text: &str) -> Result<Vec<LibraryEntry>, String> {
+    let index: serde_json::Value =
+        serde_json::from_str(text).map_err(|e| format!("bad pattern index: {}", e))?;
+    let patterns = index["patterns"]
+        .as_array()
+        .ok_or("the pattern index has no patterns list")?;
+    let entries = patterns
+        .iter()
+        .filter_map(|entry| {
+            let name = entry["name"].as_str()?;
+            let pattern = rle::parse(entry["rle"].as_str()?).ok()?;
+            Some(LibraryEntry {
+                name: name.to_string(),
+                pattern,
+                source: Some(INDEX_FILE.to_string()),
+            })
+        })
+        .collect();
+    Ok(entries)
+}
+
+// one translucent sprite per live cell of the current pattern
+#[derive(Component)]
+struct GhostCell;
+
+#[derive(Resource, Default)]
+struct Ghost {
+    cells: Vec<Entity>,
+    pattern: Option<usize>, // what `cells` were spawned for
+}
+
+pub struct LibraryPlugin;
+
+impl Plugin for LibraryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Library>()
+            .init_resource::<Ghost>()
+            .init_resource::<PatternPicks>()
+            .add_event::<PickPattern>()
+            .add_event::<SyncIndex>()
+            .add_startup_system(parse_patterns)
+            .add_startup_system(load_picks)
+            .add_system_to_stage(CoreStage::Last, save_picks)
+            .add_system(open_dropped)
+            .add_system(handle_keyboard)
+            .add_system(stamp.after(handle_keyboard))
+            .add_system(tile_pattern.after(handle_keyboard))
+            .add_system_to_stage(CoreStage::PostUpdate, draw_ghost);
+    }
+}
+
+// the classics, the archives remembered from earlier sessions and the last synced index
+fn parse_patterns(mut library: ResMut<Library>, mut toasts: EventWriter<Toast>) {
+    for (name, text) in PATTERNS {
+        match rle::parse(text) {
+            Ok(pattern) => library.entries.push(LibraryEntry {
+                name: name.to_string(),
+                pattern,
+                source: None,
+            }),
+            Err(e) => warn!("built-in pattern {}: {}", name, e),
+        }
+    }
+    let sources = split(Settings::load().get("library_sources").unwrap_or(""));
+    for path in sources {
+        match read_archive(&path) {
+            Ok(entries) => library.entries.extend(entries),
+            Err(e) => toasts.send(Toast(e)),
+        }
+        library.sources.push(path);
+    }
+    if let Ok(text) = fs::read_to_string(INDEX_FILE) {
+        match read_index(&text) {
+            Ok(entries) => library.entries.extend(entries),
+            Err(e) => toasts.send(Toast(format!("{}: {}", INDEX_FILE, e))),
+        }
+    }
+}
+
+fn load_picks(mut picks: ResMut<PatternPicks>) {
+    let settings = Settings::load();
+    let list = |key| split(settings.get(key).unwrap_or(""));
+    picks.recent = list("recent_patterns");
+    picks.favorites = list("favorite_patterns");
+}
+
+// a zip archive dropped on the window adds its patterns to the library, and is opened again
+// at the next start
+fn open_dropped(
+    mut drops: EventReader<FileDragAndDrop>,
+    mut library: ResMut<Library>,
+    mut toasts: EventWriter<Toast>,
+) {
+    for drop in drops.iter() {
+        let FileDragAndDrop::DroppedFile { path_buf, .. } = drop else {
+            continue;
+        };
+        let is_zip = path_buf
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase);
+        if is_zip.as_deref() != Some("zip") {
+            continue;
+        }
+        let path = path_buf.to_string_lossy().to_string();
+        if library.sources.contains(&path) {
+            toasts.send(Toast(format!("{} is already in the library", path)));
+            continue;
+        }
+        let entries = match read_archive(&path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                toasts.send(Toast(e));
+                continue;
+            }
+        };
+        toasts.send(Toast(format!(
+            "{} patterns from {} added to the library",
+            entries.len(),
+            path
+        )));
+        library.entries.extend(entries);
+        library.sources.push(path);
+        let mut settings = Settings::load();
+        settings.set(
+            "library_sources",
+            library.sources.join(&SEPARATOR.to_string()),
+        );
+        if let Err(e) = settings.save() {
+            warn!("{}", e);
+        }
+    }
+}
+
+// a stamp or a favorite changes them, the ones loaded at startup are already there
+fn save_picks(picks: Res<PatternPicks>) {
+    if !picks.is_changed() || picks.is_added() {
+        return;
+    }
+    let mut settings = Settings::load();
+    let separator = SEPARATOR.to_string();
+    settings.set("recent_patterns", picks.recent.join(&separator));
+    settings.set("favorite_patterns", picks.favorites.join(&separator));
+    if let Err(e) = settings.save() {
+        warn!("{}", e);
+    }
+}
+
+// U -> stamp the next of the classics, after the last one back to drawing
+// while stamping . and , aim the pattern, see pattern::orient
+fn handle_keyboard(
+    key: Keys,
+    mut picks: EventReader<PickPattern>,
+    mut library: ResMut<Library>,
+    mut tool: ResMut<Tool>,
+) {
+    let count = library.entries.len();
+    if let Some(pick) = picks.iter().last() {
+        library.current = pick.0.min(count.saturating_sub(1));
+        *tool = Tool::Stamp;
+    }
+    if *tool == Tool::Stamp {
+        let current = library.current;
+        if let Some(entry) = library.entries.get_mut(current) {
+            pattern::orient(&key, &mut entry.pattern);
+        }
+    }
+    // the archives can hold thousands, those are picked from the Library page
+    let classics = count.min(PATTERNS.len());
+    if !key.just_pressed(|k| &k.library) || classics == 0 {
+        return;
+    }
+    if *tool != Tool::Stamp {
+        library.current = 0;
+        *tool = Tool::Stamp;
+    } else if library.current + 1 < classics {
+        library.current += 1;
+    } else {
+        *tool = Tool::Draw;
+    }
+    if *tool == Tool::Stamp {
+        info!("stamping {}", library.entries[library.current].name);
+    }
+}
+
+// a click stamps the pattern centered on the cell under the cursor, snapped
+fn stamp(
+    mut click: BoardClick,
+    aim: Aim,
+    tool: Res<Tool>,
+    library: Res<Library>,
+    mut placer: Placer,
+    mut stamps: EventWriter<Stamped>,
+    mut picks: ResMut<PatternPicks>,
+) {
+    if *tool != Tool::Stamp {
+        return;
+    }
+    let Some(entry) = library.entries.get(library.current) else {
+        return;
+    };
+    let Some(cell) = aim.center(&entry.pattern) else {
+        return;
+    };
+    if click.edits() && placer.place(&entry.pattern, cell) {
+        stamps.send(Stamped(entry.name.clone()));
+        if picks.recent.first() != Some(&entry.name) {
+            picks.stamped(&entry.name);
+        }
+    }
+}
+
+// End while stamping -> tile the pattern, see select::fill_tiles
+fn tile_pattern(
+    key: Keys,
+    tool: Res<Tool>,
+    library: Res<Library>,
+    mut fills: EventWriter<TileFill>,
+) {
+    if !key.just_pressed(|k| &k.tile) || *tool != Tool::Stamp {
+        return;
+    }
+    if let Some(entry) = library.entries.get(library.current) {
+        fills.send(TileFill(entry.pattern.clone()));
+    }
+}
+
+// follows the cursor while stamping, laid out the same way Placer::place stamps it
+fn draw_ghost(
+    mut commands: Commands,
+    aim: Aim,
+    tool: Res<Tool>,
+    library: Res<Library>,
+    colors: Res<CellColors>,
+    mut ghost: ResMut<Ghost>,
+    mut sprites: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<GhostCell>>,
+) {
+    let current = (*tool == Tool::Stamp).then_some(library.current);
+    if ghost.pattern != current {
+        for entity in ghost.cells.drain(..) {
+            commands.entity(entity).despawn();
+        }
+        ghost.pattern = current;
+        let pattern = current
+            .and_then(|i| library.entries.get(i))
+            .map(|e| &e.pattern);
+        for _ in pattern.map_or(&[][..], |p| &p.alive[..]) {
+            let hidden = SpriteBundle {
+                visibility: Visibility { is_visible: false },
+                ..default()
+            };
+            let cell = commands.spawn((hidden, GhostCell)).id();
+            ghost.cells.push(cell);
+        }
+        // the new sprites are placed next frame, once they exist
+        return;
+    }
+    let pattern = current
+        .and_then(|i| library.entries.get(i))
+        .map(|e| &e.pattern);
+    let Some((screen, pattern)) = aim.screen().zip(pattern) else {
+        return;
+    };
+    let mut color = colors.alive;
+    color.set_a(GHOST_ALPHA);
+    let origin = aim
+        .center(pattern)
+        .map(|c| c.as_ivec2() - (pattern.size / 2).as_ivec2());
+    let board = screen.board_size().as_ivec2();
+    for (&entity, cell) in ghost.cells.iter().zip(pattern.alive.iter()) {
+        let Ok((mut sprite, mut transform, mut visibility)) = sprites.get_mut(entity) else {
+            continue;
+        };
+        let pos = origin.map(|o| o + cell.as_ivec2());
+        // cells past the edges are dropped when stamped, so they aren't shown either
+        let shown = pos.filter(|p| p.cmpge(IVec2::ZERO).all() && p.cmplt(board).all());
+        visibility.is_visible = shown.is_some();
+        let Some(pos) = shown else {
+            continue;
+        };
+        sprite.color = color;
+        sprite.custom_size = Some(screen.cell_size() * SIZE);
+        transform.translation = screen.cell_to_world(pos.x as f32, pos.y as f32).extend(0.6);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::{FileOptions, ZipWriter};
+
+    #[test]
+    fn reads_the_patterns_of_an_archive() {
+        let path = std::env::temp_dir().join("bevy_conway_library_test.zip");
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        let files = [
+            ("pack/glider.rle", PATTERNS[0].1),
+            ("pack/blinker.cells", "!Name: blinker\nOOO\n"),
+            ("pack/README.txt", "not a pattern"),
+            ("pack/broken.rle", "x = 3\n!"),
+        ];
+        for (name, text) in files {
+            zip.start_file(name, FileOptions::default()).unwrap();
+            zip.write_all(text.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let entries = read_archive(&path.to_string_lossy()).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["glider", "blinker"]);
+        assert_eq!(entries[1].pattern.alive.len(), 3);
+    }
+
+    #[test]
+    fn reads_a_pattern_index() {
+        let text = r#"{"patterns": [
+            {"name": "glider", "rle": "x = 3, y = 3\nbo$2bo$3o!"},
+            {"name": "no rle"},
+            {"name": "broken", "rle": "x = 3\n!"}
+        ]}"#;
+        let entries = read_index(text).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "glider");
+        assert_eq!(entries[0].pattern.alive.len(), 5);
+        assert!(read_index("[]").is_err());
+        assert!(read_index("not json").is_err());
+    }
+}
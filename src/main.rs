@@ -1,6 +1,9 @@
 use bevy::{prelude::*, window::PresentMode};
 
-mod sim;
+use bevy_app::{
+    atlas, checkpoint, cli, convert, gpu, sim, template, trace, validate, worker, ConwayConfig,
+    ConwayPlugin,
+};
 
 #[cfg(target_arch = "wasm32")]
 const HEIGHT: f32 = 500.0;
@@ -13,7 +16,104 @@ const HEIGHT: f32 = 1000.0;
 const WIDTH: f32 = 1000.0;
 
 fn main() {
-    App::new()
+    let args = match cli::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    if let Some(name) = &args.new_rule {
+        match template::run(name) {
+            Ok(summary) => println!("{}", summary),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(command) = &args.convert {
+        match convert::run(command) {
+            Ok(summary) => println!("{}", summary),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(side) = args.gpu {
+        let setup = gpu::GpuSetup {
+            side,
+            scene: args.scene,
+            seed: args.seed,
+            boundary: args.boundary,
+            rule: args.rule,
+            stats_every: args.gpu_stats.unwrap_or(gpu::DEFAULT_STATS_EVERY),
+            validate: args.gpu_validate,
+        };
+        if let Err(e) = gpu::run(setup) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(atlas) = &args.atlas {
+        let setup = atlas::AtlasSetup {
+            scene: args.scene.clone(),
+            seed: args.seed,
+            grid: args.grid,
+            boundary: args.boundary,
+        };
+        match atlas::run(atlas, &setup) {
+            Ok(summary) => println!("{}", summary),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(command) = &args.trace {
+        let setup = trace::TraceSetup {
+            scene: args.scene.clone(),
+            seed: args.seed,
+            grid: args.grid,
+            boundary: args.boundary,
+            rule: args.rule,
+            backend: args.backend,
+        };
+        match trace::run(command, &setup) {
+            Ok(summary) => println!("{}", summary),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // a scene asked for on the command line goes straight to the board
+    let menu = matches!(args.scene, sim::StartupScene::Empty) && !args.resume;
+    let mut app = App::new();
+    app.insert_resource(args.scene)
+        .insert_resource(sim::SimRng::new(args.seed))
+        .insert_resource(args.drift)
+        .insert_resource(args.chaos)
+        .insert_resource(args.boundary)
+        .insert_resource(args.rule)
+        .insert_resource(checkpoint::CheckpointInterval(args.checkpoint))
+        .insert_resource(checkpoint::ResumeSave(args.resume))
+        .insert_resource(validate::ValidateBackends(args.validate))
+        .insert_resource(sim::LiveBackend::new(args.backend))
+        .insert_resource(worker::StepWorker::new(args.worker, args.backend))
+        .insert_resource(args.idle)
         .insert_resource(ClearColor(Color::rgb(0.05, 0.05, 0.05)))
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             window: WindowDescriptor {
@@ -28,10 +128,17 @@ fn main() {
             ..default()
         }))
         .add_startup_system(setup_camera)
-        .add_plugin(sim::SimPlugin)
-        .run();
+        .add_plugin(ConwayPlugin::new(ConwayConfig {
+            grid: args.grid,
+            menu,
+            ..default()
+        }));
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_plugin(bevy_app::placement::PlacementPlugin)
+        .add_plugin(bevy_app::mirror::MirrorPlugin);
+    app.run();
 }
 
 fn setup_camera(mut commands: Commands) {
     commands.spawn(Camera2dBundle::default());
-}
\ No newline at end of file
+}
@@ -0,0 +1,544 @@
+use bevy::app::AppExit;
+use bevy::ecs::schedule::ShouldRun;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::ui::UiSystem;
+use std::marker::PhantomData;
+
+use crate::audio::SoundThemes;
+use crate::board::Boundary;
+use crate::checkpoint::{LoadBoard, SaveBoard, SavedBoard};
+use crate::library::{Library, PatternPicks, PickPattern};
+use crate::pattern::PastePattern;
+use crate::recipe::LoadRecipe;
+use crate::rule::{PluginRules, Ruleset};
+use crate::sim::{self, KeyBindings, Keys, RestartBoard};
+use crate::speed::Speed;
+use crate::theme::Theme;
+use crate::thumbnail::Thumbnailer;
+
+const BACKGROUND: Color = Color::rgb(0.05, 0.05, 0.05);
+// the pause menu dims the board instead of hiding it
+const DIMMED: Color = Color::rgba(0.0, 0.0, 0.0, 0.7);
+const BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+const SELECTED: Color = Color::rgb(0.3, 0.45, 0.25);
+// the square the thumbnails of the patterns and the save are fit into, within a button
+const THUMBNAIL: f32 = 34.0;
+// items drawn at once, a longer page (a library with archives) scrolls with the selection
+const MAX_ROWS: usize = 14;
+
+const TUTORIAL: &str = "\
+Click cells to bring them to life or kill them, then press Space to let them evolve.
+Drag to paint, right click to erase, Ctrl+scroll sizes the brush and Z makes it round.
+F7 draws lines, rectangles and ellipses, pressed again it picks the next shape.
+F9 selects a rectangle, Ctrl+C or Ctrl+X copies it and Ctrl+V pastes it where you click.
+While stamping or pasting . turns the pattern, , and Shift+, flip it.
+N steps one generation while paused, J jumps 1000 ahead.
+R clears the board, G picks the next rule, B wraps the edges around.
+U stamps the classic patterns one after the other, O pastes assets/pattern.rle.
+E and L save and load the editing recipe.
+H plots the population, F4 cycles how much text is shown, F1 shows the about panel.
+F6 lists what happened on the board, click an entry to go back to it.
+F8 has notes and examples for every rule, F10 tries out mutations of the one in use.
+Q shows the control panel, Escape pauses and opens the quick settings.";
+
+// the main menu comes first, the board is live once it is closed
+// Escape on the board pauses it behind an overlay
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Screen {
+    Menu,
+    Board,
+    Paused,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+enum Page {
+    #[default]
+    Main,
+    Settings,
+    Tutorial,
+    Pause,
+    Library,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Item {
+    NewBoard,
+    Load,
+    Patterns,
+    Settings,
+    Tutorial,
+    Quit,
+    Rule,
+    Boundary,
+    Theme,
+    Sound,
+    Back,
+    Resume,
+    Restart,
+    Speed,
+    Save,
+    LoadSave,
+    MainMenu,
+    Stamp(usize), // Library::entries[i]
+    Favorite(usize),
+    Recent(usize),
+    PasteFile,
+}
+
+impl Page {
+    fn items(&self, library: &Library, picks: &PatternPicks) -> Vec<Item> {
+        match self {
+            Page::Main => vec![
+                Item::NewBoard,
+                Item::Load,
+                Item::Patterns,
+                Item::Settings,
+                Item::Tutorial,
+                Item::Quit,
+            ],
+            Page::Settings => {
+                vec![
+                    Item::Rule,
+                    Item::Boundary,
+                    Item::Theme,
+                    Item::Sound,
+                    Item::Back,
+                ]
+            }
+            Page::Tutorial => vec![Item::Back],
+            Page::Pause => vec![
+                Item::Resume,
+                Item::Restart,
+                Item::Speed,
+                Item::Rule,
+                Item::Theme,
+                Item::Patterns,
+                Item::Save,
+                Item::LoadSave,
+                Item::MainMenu,
+            ],
+            // the favorites and the recently stamped ones first, then every pattern
+            Page::Library => {
+                let recent = picks.recent.iter().filter(|name| !picks.is_favorite(name));
+                let favorites = picks.favorites.iter();
+                favorites
+                    .filter_map(|name| library.position(name).map(Item::Favorite))
+                    .chain(recent.filter_map(|name| library.position(name).map(Item::Recent)))
+                    .chain((0..library.entries.len()).map(Item::Stamp))
+                    .chain([Item::PasteFile, Item::Back])
+                    .collect()
+            }
+        }
+    }
+
+    // what Escape picks
+    fn back(&self) -> Option<Item> {
+        match self {
+            Page::Main => None,
+            Page::Settings | Page::Tutorial | Page::Library => Some(Item::Back),
+            Page::Pause => Some(Item::Resume),
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct Menu {
+    page: Page,
+    selected: usize,   // index into page.items()
+    was_playing: bool, // before the pause menu opened
+}
+
+#[derive(Component)]
+struct MenuRoot;
+
+#[derive(Component)]
+struct MenuButton(usize);
+
+// what the menu items act on
+#[derive(SystemParam)]
+struct MenuActions<'w, 's> {
+    screen: ResMut<'w, State<Screen>>,
+    playing: ResMut<'w, sim::State>,
+    speed: ResMut<'w, Speed>,
+    rule: ResMut<'w, Ruleset>,
+    plugins: Res<'w, PluginRules>,
+    boundary: ResMut<'w, Boundary>,
+    theme: ResMut<'w, Theme>,
+    sound: ResMut<'w, SoundThemes>,
+    recipes: EventWriter<'w, 's, LoadRecipe>,
+    patterns: EventWriter<'w, 's, PastePattern>,
+    board: BoardActions<'w, 's>,
+    exits: EventWriter<'w, 's, AppExit>,
+}
+
+// the pause menu's board operations, and the pattern library's
+#[derive(SystemParam)]
+struct BoardActions<'w, 's> {
+    restarts: EventWriter<'w, 's, RestartBoard>,
+    saves: EventWriter<'w, 's, SaveBoard>,
+    loads: EventWriter<'w, 's, LoadBoard>,
+    picks: EventWriter<'w, 's, PickPattern>,
+    patterns: ResMut<'w, PatternPicks>,
+}
+
+// the settings shown in the item labels
+#[derive(SystemParam)]
+struct MenuSettings<'w, 's> {
+    rule: Res<'w, Ruleset>,
+    plugins: Res<'w, PluginRules>,
+    boundary: Res<'w, Boundary>,
+    theme: Res<'w, Theme>,
+    sound: Res<'w, SoundThemes>,
+    speed: Res<'w, Speed>,
+    saved: Res<'w, SavedBoard>,
+    picks: Res<'w, PatternPicks>,
+    library: Res<'w, Library>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl MenuSettings<'_, '_> {
+    fn is_changed(&self) -> bool {
+        self.saved.is_changed()
+            || self.picks.is_changed()
+            || self.library.is_changed()
+            || self.rule.is_changed()
+            || self.boundary.is_changed()
+            || self.theme.is_changed()
+            || self.sound.is_changed()
+            || self.speed.is_changed()
+    }
+}
+
+impl Item {
+    fn label(&self, settings: &MenuSettings) -> String {
+        match self {
+            Item::NewBoard => "New board".to_string(),
+            Item::Load => "Load recipe".to_string(),
+            Item::Patterns => "Patterns".to_string(),
+            Item::Settings => "Settings".to_string(),
+            Item::Tutorial => "Tutorial".to_string(),
+            Item::Quit => "Quit".to_string(),
+            Item::Rule => format!("Rule: {}", settings.plugins.name(&settings.rule)),
+            Item::Boundary => match *settings.boundary {
+                Boundary::Finite => "Edges: finite".to_string(),
+                Boundary::Toroidal => "Edges: wrap around".to_string(),
+            },
+            Item::Theme => format!("Theme: {}", settings.theme.palette().name),
+            Item::Sound => format!("Sound: {}", settings.sound.name()),
+            Item::Back => "Back".to_string(),
+            Item::Resume => "Resume".to_string(),
+            Item::Restart => "Restart".to_string(),
+            Item::Speed => format!("Speed: {}", settings.speed.label()),
+            Item::Save => "Save board".to_string(),
+            Item::LoadSave => match &settings.saved.0 {
+                Some((generation, _)) => format!("Load board (generation {})", generation),
+                None => "Load board".to_string(),
+            },
+            Item::MainMenu => "Quit to menu".to_string(),
+            Item::Stamp(i) | Item::Favorite(i) | Item::Recent(i) => {
+                let name = &settings.library.entries[*i].name;
+                match self {
+                    _ if settings.picks.is_favorite(name) => format!("* {}", name),
+                    Item::Recent(_) => format!("{} (recent)", name),
+                    _ => name.clone(),
+                }
+            }
+            Item::PasteFile => "assets/pattern.rle".to_string(),
+        }
+    }
+
+    // shown left of the label, what a pick would put on the board
+    fn thumbnail(
+        &self,
+        settings: &MenuSettings,
+        thumbnails: &mut Thumbnailer,
+    ) -> Option<(Handle<Image>, Size)> {
+        match self {
+            Item::Stamp(i) | Item::Favorite(i) | Item::Recent(i) => {
+                let entry = settings.library.entries.get(*i)?;
+                Some(thumbnails.of(&entry.pattern, THUMBNAIL))
+            }
+            Item::LoadSave => {
+                let (_, map) = settings.saved.0.as_ref()?;
+                Some(thumbnails.of_board(map, THUMBNAIL))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl MenuActions<'_, '_> {
+    fn run(&mut self, item: Item, menu: &mut Menu) {
+        let page = |menu: &mut Menu, page| {
+            menu.page = page;
+            menu.selected = 0;
+        };
+        match item {
+            Item::NewBoard => {}
+            Item::Load => self.recipes.send(LoadRecipe),
+            Item::Patterns => return page(menu, Page::Library),
+            Item::Stamp(i) | Item::Favorite(i) | Item::Recent(i) => {
+                self.board.picks.send(PickPattern(i))
+            }
+            Item::PasteFile => self.patterns.send(PastePattern),
+            Item::Settings => return page(menu, Page::Settings),
+            Item::Tutorial => return page(menu, Page::Tutorial),
+            Item::Quit => return self.exits.send(AppExit),
+            Item::Rule => {
+                let (_, next) = self.plugins.after(&self.rule);
+                *self.rule = next;
+                return;
+            }
+            Item::Boundary => {
+                *self.boundary = match *self.boundary {
+                    Boundary::Finite => Boundary::Toroidal,
+                    Boundary::Toroidal => Boundary::Finite,
+                };
+                return;
+            }
+            Item::Theme => return self.theme.next(),
+            Item::Sound => return self.sound.next(),
+            // the library is also on the pause menu
+            Item::Back if *self.screen.current() == Screen::Paused => {
+                return page(menu, Page::Pause)
+            }
+            Item::Back => return page(menu, Page::Main),
+            Item::Speed => return self.speed.next_preset(),
+            // the board plays on after these, like before the pause
+            Item::Resume => self.playing.0 = menu.was_playing,
+            Item::Save => {
+                self.board.saves.send(SaveBoard);
+                self.playing.0 = menu.was_playing;
+            }
+            // and stays paused after these
+            Item::Restart => self.board.restarts.send(RestartBoard),
+            Item::LoadSave => self.board.loads.send(LoadBoard),
+            Item::MainMenu => {
+                page(menu, Page::Main);
+                let _ = self.screen.set(Screen::Menu);
+                return;
+            }
+        }
+        // the items that get to the board close the menu
+        let _ = self.screen.set(Screen::Board);
+    }
+}
+
+pub struct MenuPlugin {
+    pub open: bool, // false starts on the board
+}
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        let first = if self.open {
+            Screen::Menu
+        } else {
+            Screen::Board
+        };
+        app.init_resource::<Menu>()
+            // input is read and swallowed before the board's systems get to see it
+            .add_state_to_stage(CoreStage::PreUpdate, first)
+            .add_system_set_to_stage(
+                CoreStage::PreUpdate,
+                SystemSet::new()
+                    .with_run_criteria(menu_open)
+                    .with_system(handle_input.after(UiSystem::Focus))
+                    .with_system(draw_menu.after(handle_input)),
+            )
+            .add_system_set_to_stage(
+                CoreStage::PreUpdate,
+                SystemSet::on_exit(Screen::Menu).with_system(close_menu),
+            )
+            .add_system_set_to_stage(
+                CoreStage::PreUpdate,
+                SystemSet::on_exit(Screen::Paused).with_system(close_menu),
+            )
+            // in Update, so the Escape that opens the pause menu doesn't also close it
+            .add_system(open_pause);
+    }
+}
+
+// the main menu or the pause menu
+fn menu_open(screen: Res<State<Screen>>) -> ShouldRun {
+    match screen.current() {
+        Screen::Board => ShouldRun::No,
+        Screen::Menu | Screen::Paused => ShouldRun::Yes,
+    }
+}
+
+// Escape -> pause the board and open the quick settings
+fn open_pause(
+    key: Keys,
+    mut screen: ResMut<State<Screen>>,
+    mut playing: ResMut<sim::State>,
+    mut menu: ResMut<Menu>,
+) {
+    if *screen.current() != Screen::Board || !key.just_pressed(|k| &k.pause_menu) {
+        return;
+    }
+    menu.page = Page::Pause;
+    menu.selected = 0;
+    menu.was_playing = playing.0;
+    playing.0 = false;
+    let _ = screen.set(Screen::Paused);
+}
+
+// Up / Down / Enter or the mouse pick an item, Escape goes back, F marks a pattern as a favorite
+// while the menu is open no key or click reaches the board
+fn handle_input(
+    mut key: ResMut<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut mouse: ResMut<Input<MouseButton>>,
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut menu: ResMut<Menu>,
+    library: Res<Library>,
+    mut actions: MenuActions,
+) {
+    let items = menu.page.items(&library, &actions.board.patterns);
+    let mut chosen = None;
+    for (interaction, button) in buttons.iter() {
+        match interaction {
+            Interaction::Clicked => chosen = Some(button.0),
+            Interaction::Hovered if menu.selected != button.0 => menu.selected = button.0,
+            _ => {}
+        }
+    }
+    let pressed = |keys: &Vec<KeyCode>| key.any_just_pressed(keys.iter().copied());
+    if pressed(&bindings.menu_up) {
+        menu.selected = (menu.selected + items.len() - 1) % items.len();
+    }
+    if pressed(&bindings.menu_down) {
+        menu.selected = (menu.selected + 1) % items.len();
+    }
+    if pressed(&bindings.menu_pick) {
+        chosen = Some(menu.selected);
+    }
+    if pressed(&bindings.menu_favorite) {
+        if let Some(Item::Stamp(i) | Item::Favorite(i) | Item::Recent(i)) = items.get(menu.selected)
+        {
+            actions
+                .board
+                .patterns
+                .toggle_favorite(&library.entries[*i].name);
+            // the list above the patterns grew or shrank
+            let count = menu.page.items(&library, &actions.board.patterns).len();
+            menu.selected = menu.selected.min(count - 1);
+        }
+    }
+    let mut item = chosen.and_then(|i| items.get(i)).copied();
+    if pressed(&bindings.menu_back) {
+        item = menu.page.back();
+    }
+    if let Some(item) = item {
+        actions.run(item, &mut menu);
+    }
+    key.reset_all();
+    // clicks still have to be released, so the buttons see them end
+    mouse.clear();
+}
+
+// rebuilt whenever the selection or a setting shown in it changes
+fn draw_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    menu: Res<Menu>,
+    settings: MenuSettings,
+    mut thumbnails: Thumbnailer,
+    roots: Query<Entity, With<MenuRoot>>,
+) {
+    let drawn = !roots.is_empty();
+    if drawn && !menu.is_changed() && !settings.is_changed() {
+        return;
+    }
+    for root in roots.iter() {
+        commands.entity(root).despawn_recursive();
+    }
+    let font = asset_server.load("Kid Marker.ttf");
+    let text = |value: &str, size: f32, color: Color| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: font.clone(),
+                font_size: size,
+                color,
+            },
+        )
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: match menu.page {
+                    Page::Pause => DIMMED.into(),
+                    _ => BACKGROUND.into(),
+                },
+                ..default()
+            },
+            MenuRoot,
+        ))
+        .with_children(|root| {
+            let title = match menu.page {
+                Page::Pause => "Paused",
+                _ => "Conway's Game of Life",
+            };
+            root.spawn(text(title, 40.0, Color::WHITE).with_style(Style {
+                margin: UiRect::bottom(Val::Px(30.0)),
+                ..default()
+            }));
+            if menu.page == Page::Tutorial {
+                root.spawn(text(TUTORIAL, 18.0, Color::GRAY).with_style(Style {
+                    margin: UiRect::bottom(Val::Px(20.0)),
+                    max_size: Size::new(Val::Px(700.0), Val::Undefined),
+                    ..default()
+                }));
+            }
+            let items = menu.page.items(&settings.library, &settings.picks);
+            let first = (menu.selected + 1).saturating_sub(MAX_ROWS);
+            for (i, &item) in items.iter().enumerate().skip(first).take(MAX_ROWS) {
+                let color = if i == menu.selected { SELECTED } else { BUTTON };
+                root.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(320.0), Val::Px(44.0)),
+                            margin: UiRect::all(Val::Px(5.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: color.into(),
+                        ..default()
+                    },
+                    MenuButton(i),
+                ))
+                .with_children(|button| {
+                    if let Some((image, size)) = item.thumbnail(&settings, &mut thumbnails) {
+                        button.spawn(ImageBundle {
+                            image: image.into(),
+                            style: Style {
+                                size,
+                                margin: UiRect::right(Val::Px(12.0)),
+                                ..default()
+                            },
+                            ..default()
+                        });
+                    }
+                    button.spawn(text(&item.label(&settings), 22.0, Color::WHITE));
+                });
+            }
+        });
+}
+
+fn close_menu(mut commands: Commands, roots: Query<Entity, With<MenuRoot>>) {
+    for root in roots.iter() {
+        commands.entity(root).despawn_recursive();
+    }
+}
@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+use bevy::render::camera::{RenderTarget, ScalingMode};
+use bevy::window::{
+    CreateWindow, MonitorSelection, WindowClosed, WindowId, WindowMode, WindowResized,
+};
+use bevy::winit::WinitWindows;
+
+use crate::sim::Keys;
+
+// the board in a second window, e.g. on a projector, the HUD and panels stay on the primary one
+#[derive(Resource, Default)]
+struct Mirror(Option<(WindowId, Entity)>); // the window and the camera drawing into it
+
+#[derive(Component)]
+struct MirrorCamera;
+
+pub struct MirrorPlugin;
+
+impl Plugin for MirrorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Mirror>()
+            .add_system(handle_keyboard)
+            .add_system(follow_primary)
+            .add_system(handle_closed);
+    }
+}
+
+// the first monitor the primary window isn't on
+fn other_monitor(winit: &WinitWindows, primary: WindowId) -> Option<usize> {
+    let window = winit.get_window(primary)?;
+    let current = window.current_monitor();
+    window
+        .available_monitors()
+        .position(|m| Some(&m) != current.as_ref())
+}
+
+// the area the primary window shows, so the mirror shows the whole board at any size
+fn scaling(primary: &Window) -> ScalingMode {
+    ScalingMode::Auto {
+        min_width: primary.width(),
+        min_height: primary.height(),
+    }
+}
+
+// F5 -> open/close the mirror window
+fn handle_keyboard(
+    mut commands: Commands,
+    key: Keys,
+    mut windows: ResMut<Windows>,
+    winit: NonSend<WinitWindows>,
+    mut mirror: ResMut<Mirror>,
+    mut creates: EventWriter<CreateWindow>,
+) {
+    if !key.just_pressed(|k| &k.mirror_window) {
+        return;
+    }
+    if let Some((window, camera)) = mirror.0.take() {
+        if let Some(window) = windows.get_mut(window) {
+            window.close();
+        }
+        commands.entity(camera).despawn();
+        return;
+    }
+    let Some(primary) = windows.get_primary() else {
+        return;
+    };
+    let title = "Bevy_app - mirror".to_string();
+    // fullscreen on another monitor, with a single one it is a window that can be moved around
+    let descriptor = match other_monitor(&winit, primary.id()) {
+        Some(monitor) => WindowDescriptor {
+            title,
+            mode: WindowMode::BorderlessFullscreen,
+            monitor: MonitorSelection::Index(monitor),
+            decorations: false,
+            ..default()
+        },
+        None => WindowDescriptor {
+            title,
+            width: primary.width(),
+            height: primary.height(),
+            ..default()
+        },
+    };
+    let id = WindowId::new();
+    creates.send(CreateWindow { id, descriptor });
+    let mut camera = Camera2dBundle::default();
+    camera.camera.target = RenderTarget::Window(id);
+    camera.camera.priority = 1;
+    camera.projection.scaling_mode = scaling(primary);
+    let camera = commands
+        .spawn((camera, UiCameraConfig { show_ui: false }, MirrorCamera))
+        .id();
+    mirror.0 = Some((id, camera));
+}
+
+fn follow_primary(
+    mut resized: EventReader<WindowResized>,
+    windows: Res<Windows>,
+    mut cameras: Query<&mut OrthographicProjection, With<MirrorCamera>>,
+) {
+    if !resized.iter().any(|event| event.id == WindowId::primary()) {
+        return;
+    }
+    let Some(primary) = windows.get_primary() else {
+        return;
+    };
+    for mut projection in cameras.iter_mut() {
+        projection.scaling_mode = scaling(primary);
+    }
+}
+
+// closing the primary window closes the mirror too, so the app exits
+fn handle_closed(
+    mut commands: Commands,
+    mut closed: EventReader<WindowClosed>,
+    mut windows: ResMut<Windows>,
+    mut mirror: ResMut<Mirror>,
+) {
+    for event in closed.iter() {
+        let Some((window, camera)) = mirror.0 else {
+            return;
+        };
+        if event.id != window && event.id != WindowId::primary() {
+            continue;
+        }
+        if let Some(window) = windows.get_mut(window) {
+            window.close();
+        }
+        commands.entity(camera).despawn();
+        mirror.0 = None;
+    }
+}
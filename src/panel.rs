@@ -0,0 +1,211 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext, EguiPlugin, EguiSystem};
+
+use crate::menu::Screen;
+use crate::rule::{PluginRules, Ruleset};
+use crate::sim::{self, ClearBoard, GridConfig, Keys, RandomizeBoard, SingleStep};
+use crate::speed::Speed;
+
+const WIDTH: f32 = 190.0;
+// the gap the overlays on the right keep to the window's edge, or to the panel when it is open
+const MARGIN: f32 = 10.0;
+
+// the speed slider is logarithmic between these, its last few pixels are max
+const MIN_RATE: f32 = 1.0;
+const MAX_RATE: f32 = 120.0;
+const MAX_FROM: f32 = 0.95;
+
+const MIN_DENSITY: f64 = 0.05;
+const MAX_DENSITY: f64 = 0.95;
+
+#[derive(Resource)]
+struct Panel {
+    show: bool,
+    density: f64, // of the soups Randomize draws
+    width: f32,   // taken from the right of the window this frame, 0 while hidden
+}
+
+impl Default for Panel {
+    fn default() -> Self {
+        Panel {
+            show: true,
+            density: 0.3,
+            width: 0.0,
+        }
+    }
+}
+
+// a bevy_ui node anchored to the right, moved left of the panel while it is open
+#[derive(Component)]
+pub struct BesidePanel;
+
+// where on the slider a rate is, 0..1
+fn slider_position(rate: Option<f32>) -> f32 {
+    match rate {
+        Some(rate) => {
+            let t = (rate / MIN_RATE).ln() / (MAX_RATE / MIN_RATE).ln();
+            t.clamp(0.0, 1.0) * MAX_FROM
+        }
+        None => 1.0,
+    }
+}
+
+fn slider_rate(position: f32) -> Option<f32> {
+    (position < MAX_FROM).then(|| MIN_RATE * (MAX_RATE / MIN_RATE).powf(position / MAX_FROM))
+}
+
+// what the controls act on
+#[derive(SystemParam)]
+struct PanelActions<'w, 's> {
+    state: ResMut<'w, sim::State>,
+    speed: ResMut<'w, Speed>,
+    single: ResMut<'w, SingleStep>,
+    grid: ResMut<'w, GridConfig>,
+    rule: ResMut<'w, Ruleset>,
+    plugins: Res<'w, PluginRules>,
+    clears: EventWriter<'w, 's, ClearBoard>,
+    randomizes: EventWriter<'w, 's, RandomizeBoard>,
+}
+
+impl PanelActions<'_, '_> {
+    // the same as the keys, see sim::handle_keyboard and friends
+    fn show(&mut self, ui: &mut egui::Ui, panel: &mut Panel) {
+        ui.horizontal(|ui| {
+            let play = if self.state.0 { "Pause" } else { "Play" };
+            if ui.button(play).clicked() {
+                self.state.0 = !self.state.0;
+            }
+            if ui
+                .add_enabled(!self.state.0, egui::Button::new("Step"))
+                .clicked()
+            {
+                self.single.0 = true;
+            }
+        });
+        ui.separator();
+
+        ui.label(format!("Speed: {}", self.speed.label()));
+        let mut position = slider_position(self.speed.rate);
+        let slider = egui::Slider::new(&mut position, 0.0..=1.0).show_value(false);
+        if ui.add(slider).changed() {
+            let rate = slider_rate(position);
+            if self.speed.rate != rate {
+                self.speed.set(rate);
+            }
+        }
+        ui.separator();
+
+        let by = ui
+            .horizontal(|ui| {
+                let smaller = ui.button("-").clicked();
+                ui.label(format!("{} x {}", self.grid.width, self.grid.height));
+                let bigger = ui.button("+").clicked();
+                5 * (bigger as i32 - smaller as i32)
+            })
+            .inner;
+        let resized = self.grid.resized(by);
+        if resized != *self.grid {
+            *self.grid = resized;
+        }
+        ui.separator();
+
+        let mut rule = *self.rule;
+        egui::ComboBox::from_id_source("rule")
+            .selected_text(format!("Rule: {}", self.plugins.name(&rule)))
+            .width(WIDTH - 20.0)
+            .show_ui(ui, |ui| {
+                for (name, choice) in self.plugins.all() {
+                    ui.selectable_value(&mut rule, choice, name);
+                }
+            });
+        if rule != *self.rule {
+            *self.rule = rule;
+        }
+        ui.separator();
+
+        let density = egui::Slider::new(&mut panel.density, MIN_DENSITY..=MAX_DENSITY)
+            .text("density")
+            .custom_formatter(|density, _| format!("{:.0}%", density * 100.0));
+        ui.add(density);
+        ui.horizontal(|ui| {
+            if ui.button("Random").clicked() {
+                self.randomizes.send(RandomizeBoard {
+                    density: panel.density,
+                });
+            }
+            if ui.button("Clear").clicked() {
+                self.clears.send(ClearBoard);
+            }
+        });
+        ui.separator();
+
+        if ui.button("Hide (Q)").clicked() {
+            panel.show = false;
+        }
+    }
+}
+
+pub struct PanelPlugin;
+
+impl Plugin for PanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(EguiPlugin)
+            .init_resource::<Panel>()
+            .add_system(handle_keyboard)
+            // clicks on the panel are taken before the board's systems get to see them
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                show_panel.after(EguiSystem::BeginFrame),
+            )
+            .add_system(move_beside_panel);
+    }
+}
+
+// Q -> show/hide the control panel
+fn handle_keyboard(key: Keys, mut panel: ResMut<Panel>) {
+    if key.just_pressed(|k| &k.panel) {
+        panel.show = !panel.show;
+    }
+}
+
+// on the board only, the menus take the whole window
+fn show_panel(
+    screen: Res<State<Screen>>,
+    mut egui: ResMut<EguiContext>,
+    mut mouse: ResMut<Input<MouseButton>>,
+    mut panel: ResMut<Panel>,
+    mut actions: PanelActions,
+) {
+    let show = panel.show && *screen.current() == Screen::Board;
+    let mut width = 0.0;
+    if show {
+        let ctx = egui.ctx_mut();
+        let response = egui::SidePanel::right("controls")
+            .resizable(false)
+            .exact_width(WIDTH)
+            .show(ctx, |ui| {
+                ui.heading("Controls");
+                actions.show(ui, &mut panel)
+            });
+        width = response.response.rect.width();
+        if ctx.is_pointer_over_area() || ctx.wants_pointer_input() {
+            mouse.clear();
+        }
+    }
+    if panel.width != width {
+        panel.width = width;
+    }
+}
+
+fn move_beside_panel(panel: Res<Panel>, mut nodes: Query<&mut Style, With<BesidePanel>>) {
+    if !panel.is_changed() {
+        return;
+    }
+    let right = Val::Px(panel.width + MARGIN);
+    for mut style in nodes.iter_mut() {
+        if style.position.right != right {
+            style.position.right = right;
+        }
+    }
+}
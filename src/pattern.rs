@@ -0,0 +1,458 @@
+use bevy::asset::{AssetEvent, AssetLoader, LoadContext, LoadState, LoadedAsset};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+
+use crate::board::Board;
+use crate::rle::{self, Pattern};
+use crate::select::Selection;
+use crate::sim::{BoardView, GridConfig, Keys, Screen, SetCell, SimStats, MAX_GRID};
+use crate::snippet;
+use crate::toast::Toast;
+
+const PATTERN_FILE: &str = "pattern.rle";
+const EXPORT_FILE: &str = "export.rle";
+const SNIPPET_FILE: &str = "export.rs";
+
+// a parsed .rle asset, parse errors are kept so they can be shown instead of just logged
+#[derive(TypeUuid)]
+#[uuid = "6f1b3c52-2f7e-4b0e-9a54-3d0c8f2b7a11"]
+pub struct RlePattern(pub Result<Pattern, String>);
+
+#[derive(Default)]
+struct RleLoader;
+
+impl AssetLoader for RleLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let pattern = std::str::from_utf8(bytes)
+                .map_err(|_| "the file is not utf-8 text".to_string())
+                .and_then(rle::parse);
+            load_context.set_default_asset(LoadedAsset::new(RlePattern(pattern)));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["rle"]
+    }
+}
+
+// the pattern being (re)loaded and the cell it will be centered on
+#[derive(Resource, Default)]
+struct Placing(Option<(Handle<RlePattern>, UVec2)>);
+
+// pastes assets/pattern.rle centered on the board, like O with the cursor off the board
+pub struct PastePattern;
+
+// a whole pattern was put on the board, from the file or the library
+pub struct Stamped(pub String);
+
+// what a pattern that does not fit on the board does, Insert cycles through them
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Overflow {
+    #[default]
+    Grow, // the grid grows on every side until it fits, clipped past MAX_GRID
+    Clip,   // the cells off the board are dropped, and counted in a toast
+    Reject, // nothing is placed
+}
+
+impl Overflow {
+    fn next(self) -> Self {
+        match self {
+            Overflow::Grow => Overflow::Clip,
+            Overflow::Clip => Overflow::Reject,
+            Overflow::Reject => Overflow::Grow,
+        }
+    }
+}
+
+// where a stamp or paste lands around the cell under the cursor, \ cycles through them
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Snap {
+    #[default]
+    Cell,
+    Every(u32), // the pattern's corner on multiples of this many cells
+    Pattern,    // on multiples of the pattern's own size, so copies tile an agar
+}
+
+const SNAP_STEPS: [u32; 4] = [2, 4, 8, 16];
+
+impl Snap {
+    fn next(self) -> Self {
+        match self {
+            Snap::Cell => Snap::Every(SNAP_STEPS[0]),
+            Snap::Every(n) => match SNAP_STEPS.iter().position(|&step| step == n) {
+                Some(i) if i + 1 < SNAP_STEPS.len() => Snap::Every(SNAP_STEPS[i + 1]),
+                _ => Snap::Pattern,
+            },
+            Snap::Pattern => Snap::Cell,
+        }
+    }
+
+    // the center Placer::place is given for a `size` pattern under `cursor` so that its corner
+    // is snapped, never left or below the board's
+    pub fn center(self, cursor: UVec2, size: UVec2) -> UVec2 {
+        let step = match self {
+            Snap::Cell => return cursor,
+            Snap::Every(n) => UVec2::splat(n),
+            Snap::Pattern => size.max(UVec2::ONE),
+        }
+        .as_ivec2();
+        let half = (size / 2).as_ivec2();
+        let at = cursor.as_ivec2() - half;
+        let corner = IVec2::new(at.x.div_euclid(step.x), at.y.div_euclid(step.y)) * step;
+        (corner + half).max(IVec2::ZERO).as_uvec2()
+    }
+}
+
+// the cell a stamp or paste is centered on, snapped
+#[derive(SystemParam)]
+pub struct Aim<'w, 's> {
+    view: BoardView<'w, 's>,
+    snap: Res<'w, Snap>,
+}
+
+impl Aim<'_, '_> {
+    pub fn screen(&self) -> Option<Screen<'_>> {
+        self.view.screen()
+    }
+
+    pub fn center(&self, pattern: &Pattern) -> Option<UVec2> {
+        let cursor = self.view.screen()?.cursor_to_cell()?;
+        Some(self.snap.center(cursor, pattern.size))
+    }
+}
+
+// a grid grown for a pattern, and the cells to set once resize_grid has respawned the board
+// at that size
+#[derive(Resource, Default)]
+pub struct Growing {
+    grid: Option<GridConfig>,
+    cells: Vec<UVec2>,
+}
+
+// the smallest grid grown evenly on each side that has room for cells from `min` to `max`,
+// and how far resize_grid moves the cells already on the board, None past MAX_GRID
+fn grown_to_fit(grid: &GridConfig, min: IVec2, max: IVec2) -> Option<(GridConfig, IVec2)> {
+    let board = grid.board_size().as_ivec2();
+    let by = (-min)
+        .max(max - board + IVec2::ONE)
+        .max(IVec2::ZERO)
+        .as_uvec2();
+    let mut next = GridConfig {
+        width: grid.width + 2 * by.x,
+        height: grid.height + 2 * by.y,
+    };
+    // part of the height goes to the rows kept for the text
+    while next.board_size().y < board.y as u32 + 2 * by.y {
+        next.height += 1;
+    }
+    if next.width > MAX_GRID || next.height > MAX_GRID {
+        return None;
+    }
+    Some((next, (next.board_size().as_ivec2() - board) / 2))
+}
+
+// stamps, pastes and imports, guarded by Overflow
+#[derive(SystemParam)]
+pub struct Placer<'w, 's> {
+    overflow: Res<'w, Overflow>,
+    grid: Res<'w, GridConfig>,
+    growing: ResMut<'w, Growing>,
+    sets: EventWriter<'w, 's, SetCell>,
+    toasts: EventWriter<'w, 's, Toast>,
+}
+
+impl Placer<'_, '_> {
+    // live cells are sent as regular edits, so they show up right away and end up in the recipe
+    // false when nothing was placed
+    pub fn place(&mut self, pattern: &Pattern, center: UVec2) -> bool {
+        // a grid still growing is the one the cells go on
+        let grid = self.growing.grid.unwrap_or(*self.grid);
+        let origin = center.as_ivec2() - (pattern.size / 2).as_ivec2();
+        let board = grid.board_size().as_ivec2();
+        let on_board = |pos: IVec2| pos.cmpge(IVec2::ZERO).all() && pos.cmplt(board).all();
+        let mut cells: Vec<IVec2> = pattern
+            .alive
+            .iter()
+            .map(|c| origin + c.as_ivec2())
+            .collect();
+        let off = cells.iter().filter(|&&pos| !on_board(pos)).count();
+        if off > 0 && *self.overflow == Overflow::Grow {
+            let min = cells
+                .iter()
+                .fold(IVec2::splat(i32::MAX), |min, &pos| min.min(pos));
+            let max = cells
+                .iter()
+                .fold(IVec2::splat(i32::MIN), |max, &pos| max.max(pos));
+            if let Some((next, offset)) = grown_to_fit(&grid, min, max) {
+                let size = next.board_size();
+                self.toasts.send(Toast(format!(
+                    "the board grew to {}x{} to fit the pattern",
+                    size.x, size.y
+                )));
+                for cell in self.growing.cells.iter_mut() {
+                    *cell = (cell.as_ivec2() + offset).as_uvec2();
+                }
+                cells.iter_mut().for_each(|pos| *pos += offset);
+                self.growing.grid = Some(next);
+                self.growing
+                    .cells
+                    .extend(cells.iter().map(|pos| pos.as_uvec2()));
+                return true;
+            }
+        }
+        if off > 0 {
+            let message = match *self.overflow {
+                Overflow::Grow => format!(
+                    "the board cannot grow past {}x{}, {} cells fell off it",
+                    MAX_GRID, MAX_GRID, off
+                ),
+                Overflow::Clip => format!("{} cells fell off the board", off),
+                Overflow::Reject => format!(
+                    "not placed, {} cells would fall off the board (Insert to clip them)",
+                    off
+                ),
+            };
+            self.toasts.send(Toast(message));
+            if *self.overflow == Overflow::Reject {
+                return false;
+            }
+        }
+        let cells = cells.into_iter().filter(|&pos| on_board(pos));
+        if self.growing.grid.is_some() {
+            self.growing.cells.extend(cells.map(|pos| pos.as_uvec2()));
+            return true;
+        }
+        for pos in cells {
+            self.sets.send(SetCell {
+                x: pos.x as u32,
+                y: pos.y as u32,
+                alive: true,
+            });
+        }
+        true
+    }
+}
+
+// the grid is grown here rather than by the Placer, which shares it with BoardView
+fn grow_board(
+    mut grid: ResMut<GridConfig>,
+    map: Res<Board>,
+    mut growing: ResMut<Growing>,
+    mut sets: EventWriter<SetCell>,
+) {
+    let Some(target) = growing.grid else {
+        return;
+    };
+    if map.size() == target.board_size() {
+        for cell in growing.cells.drain(..) {
+            sets.send(SetCell {
+                x: cell.x,
+                y: cell.y,
+                alive: true,
+            });
+        }
+        growing.grid = None;
+    } else if *grid != target {
+        *grid = target;
+    }
+}
+
+pub struct PatternPlugin;
+
+impl Plugin for PatternPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<RlePattern>()
+            .init_asset_loader::<RleLoader>()
+            .init_resource::<Placing>()
+            .init_resource::<Overflow>()
+            .init_resource::<Growing>()
+            .init_resource::<Snap>()
+            .add_event::<PastePattern>()
+            .add_event::<Stamped>()
+            .add_system(handle_keyboard)
+            .add_system(handle_overflow_key)
+            .add_system(handle_snap_key)
+            .add_system(
+                place_pattern
+                    .after(handle_keyboard)
+                    .after(handle_overflow_key),
+            )
+            .add_system(grow_board.after(place_pattern))
+            .add_system(export_pattern);
+    }
+}
+
+// O         -> paste assets/pattern.rle centered on the cell under the cursor (or the board center)
+// Shift + O -> export the selection or the board as RLE, see export_pattern
+// Ctrl + O  -> export them as a Rust const
+fn handle_keyboard(
+    key: Keys,
+    view: BoardView,
+    grid: Res<GridConfig>,
+    asset_server: Res<AssetServer>,
+    mut pastes: EventReader<PastePattern>,
+    mut placing: ResMut<Placing>,
+) {
+    let modifiers = [
+        KeyCode::LShift,
+        KeyCode::RShift,
+        KeyCode::LControl,
+        KeyCode::RControl,
+    ];
+    let pressed = key.just_pressed(|k| &k.paste_file) && !key.any_pressed(modifiers);
+    let center = match (pastes.iter().count() > 0, pressed) {
+        (true, _) => grid.board_size() / 2,
+        (false, true) => view
+            .screen()
+            .and_then(|s| s.cursor_to_cell())
+            .unwrap_or(grid.board_size() / 2),
+        (false, false) => return,
+    };
+    let handle = asset_server.load(PATTERN_FILE);
+    // the file may have changed since it was last loaded
+    asset_server.reload_asset(PATTERN_FILE);
+    placing.0 = Some((handle, center));
+}
+
+// Insert -> grow the board for, clip or reject patterns that do not fit on it
+fn handle_overflow_key(key: Keys, mut overflow: ResMut<Overflow>, mut toasts: EventWriter<Toast>) {
+    if key.just_pressed(|k| &k.overflow) {
+        *overflow = overflow.next();
+        let message = match *overflow {
+            Overflow::Grow => "the board grows for patterns too big for it",
+            Overflow::Clip => "patterns too big for the board are clipped",
+            Overflow::Reject => "patterns too big for the board are rejected",
+        };
+        toasts.send(Toast(message.to_string()));
+    }
+}
+
+// \ -> snap stamps and pastes to every cell, to every 2, 4, 8 or 16 cells, or to the
+// pattern's own size
+fn handle_snap_key(key: Keys, mut snap: ResMut<Snap>, mut toasts: EventWriter<Toast>) {
+    if key.just_pressed(|k| &k.snap) {
+        *snap = snap.next();
+        let message = match *snap {
+            Snap::Cell => "stamps snap to every cell".to_string(),
+            Snap::Every(n) => format!("stamps snap to every {} cells", n),
+            Snap::Pattern => "stamps snap to the pattern's size".to_string(),
+        };
+        toasts.send(Toast(message));
+    }
+}
+
+// while stamping or pasting:
+// .         -> turn the pattern a quarter clockwise
+// ,         -> flip it left to right
+// Shift + , -> flip it top to bottom
+pub fn orient(key: &Keys, pattern: &mut Pattern) {
+    if key.just_pressed(|k| &k.turn_pattern) {
+        *pattern = pattern.rotated();
+    }
+    if key.just_pressed(|k| &k.flip_pattern) {
+        *pattern = match key.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+            true => pattern.flipped_y(),
+            false => pattern.flipped_x(),
+        };
+    }
+}
+
+fn place_pattern(
+    mut events: EventReader<AssetEvent<RlePattern>>,
+    asset_server: Res<AssetServer>,
+    patterns: Res<Assets<RlePattern>>,
+    mut placing: ResMut<Placing>,
+    mut placer: Placer,
+    mut stamps: EventWriter<Stamped>,
+) {
+    let Some((handle, center)) = placing.0.clone() else {
+        events.clear();
+        return;
+    };
+    if asset_server.get_load_state(&handle) == LoadState::Failed {
+        placing.0 = None;
+        placer
+            .toasts
+            .send(Toast(format!("could not load assets/{}", PATTERN_FILE)));
+        return;
+    }
+    let ready = events.iter().any(|e| match e {
+        AssetEvent::Created { handle: h } | AssetEvent::Modified { handle: h } => *h == handle,
+        AssetEvent::Removed { .. } => false,
+    });
+    if !ready {
+        return;
+    }
+    placing.0 = None;
+    match patterns.get(&handle).map(|p| &p.0) {
+        Some(Ok(pattern)) if placer.place(pattern, center) => {
+            stamps.send(Stamped(PATTERN_FILE.to_string()));
+        }
+        Some(Err(e)) => placer.toasts.send(Toast(format!(
+            "could not load assets/{}: {}",
+            PATTERN_FILE, e
+        ))),
+        Some(Ok(_)) | None => {}
+    }
+}
+
+// the live cells inside `bounds`, relative to its min corner
+pub fn board_pattern(map: &Board, (min, max): (UVec2, UVec2)) -> Pattern {
+    let mut alive = Vec::new();
+    for x in min.x..=max.x {
+        for y in min.y..=max.y {
+            if map.alive(x, y) {
+                alive.push(UVec2::new(x, y) - min);
+            }
+        }
+    }
+    Pattern {
+        size: max - min + UVec2::ONE,
+        alive,
+    }
+}
+
+// the selection, or without one the board trimmed to its live cells, goes to export.rle, ready to
+// paste into Golly, or to export.rs, ready to bake into a program using the library
+fn export_pattern(
+    key: Keys,
+    map: Res<Board>,
+    stats: Res<SimStats>,
+    selection: Res<Selection>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if !key.just_pressed(|k| &k.paste_file) {
+        return;
+    }
+    let rust = key.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    if !rust && !key.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+        return;
+    }
+    let bounds = match selection.region(&map) {
+        Some(region) => (region.min, region.max),
+        None => match stats.bounding_box {
+            Some(bounds) => bounds,
+            None => {
+                toasts.send(Toast("nothing to export, the board is empty".to_string()));
+                return;
+            }
+        },
+    };
+    let (pattern, rule) = (board_pattern(&map, bounds), map.rule().to_string());
+    let (text, file) = match rust {
+        true => (snippet::to_rust(&pattern, &rule), SNIPPET_FILE),
+        false => (rle::to_text(&pattern, &rule), EXPORT_FILE),
+    };
+    match std::fs::write(file, &text) {
+        Ok(()) => info!("pattern written to {}", file),
+        Err(e) => toasts.send(Toast(format!("could not write {}: {}", file, e))),
+    }
+}
@@ -0,0 +1,176 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::window::{WindowMode, WindowMoved, WindowResized};
+use bevy::winit::WinitWindows;
+use std::str::FromStr;
+use winit::dpi::PhysicalPosition;
+use winit::monitor::MonitorHandle;
+
+use crate::settings::Settings;
+use crate::sim::Keys;
+
+// where the window was when the app last exited
+#[derive(Resource, Default)]
+struct Placement {
+    size: Option<Vec2>,      // logical, the last one while not fullscreen
+    position: Option<IVec2>, // physical outer corner on the desktop, same
+    monitor: Option<String>,
+    fullscreen: bool,
+}
+
+// "a<sep>b" as two numbers
+fn pair<T: FromStr>(value: &str, sep: char) -> Option<(T, T)> {
+    let (a, b) = value.split_once(sep)?;
+    Some((a.trim().parse().ok()?, b.trim().parse().ok()?))
+}
+
+impl Placement {
+    // anything missing or unreadable is left to the window's defaults
+    fn read(settings: &Settings) -> Self {
+        let size = settings.get("window_size").and_then(|v| pair(v, 'x'));
+        let position = settings.get("window_position").and_then(|v| pair(v, ','));
+        Placement {
+            size: size.map(|(w, h)| Vec2::new(w, h)),
+            position: position.map(|(x, y)| IVec2::new(x, y)),
+            monitor: settings.get("window_monitor").map(str::to_string),
+            fullscreen: settings.get("window_fullscreen") == Some("true"),
+        }
+    }
+
+    fn write(&self, settings: &mut Settings) {
+        if let Some(size) = self.size {
+            settings.set("window_size", format!("{}x{}", size.x, size.y));
+        }
+        if let Some(position) = self.position {
+            settings.set("window_position", format!("{},{}", position.x, position.y));
+        }
+        match &self.monitor {
+            Some(monitor) => settings.set("window_monitor", monitor.clone()),
+            None => settings.remove("window_monitor"),
+        }
+        settings.set("window_fullscreen", self.fullscreen.to_string());
+    }
+}
+
+// the desktop app's window comes back where it was, the library leaves its host's window alone
+pub struct PlacementPlugin;
+
+impl Plugin for PlacementPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Placement>()
+            .add_startup_system(restore)
+            .add_system(handle_keyboard)
+            .add_system(track.after(handle_keyboard))
+            .add_system_to_stage(CoreStage::Last, save_on_exit);
+    }
+}
+
+fn contains(monitor: &MonitorHandle, point: IVec2) -> bool {
+    let (origin, size) = (monitor.position(), monitor.size());
+    let min = IVec2::new(origin.x, origin.y);
+    let max = min + IVec2::new(size.width as i32, size.height as i32);
+    point.cmpge(min).all() && point.cmplt(max).all()
+}
+
+// displays may have changed since, so the window goes on the monitor it was on if it is still
+// connected, else the one under its old position, else the primary one,
+// and it is kept inside that monitor
+fn restore(
+    mut windows: ResMut<Windows>,
+    winit: NonSend<WinitWindows>,
+    mut placement: ResMut<Placement>,
+) {
+    *placement = Placement::read(&Settings::load());
+    let Some(window) = windows.get_primary_mut() else {
+        return;
+    };
+    let Some(handle) = winit.get_window(window.id()) else {
+        return;
+    };
+    let monitors: Vec<MonitorHandle> = handle.available_monitors().collect();
+    let named = |m: &&MonitorHandle| placement.monitor.is_some() && m.name() == placement.monitor;
+    let under = |m: &&MonitorHandle| placement.position.is_some_and(|p| contains(m, p));
+    let monitor = monitors
+        .iter()
+        .find(named)
+        .or_else(|| monitors.iter().find(under))
+        .cloned()
+        .or_else(|| handle.primary_monitor())
+        .or_else(|| monitors.first().cloned());
+    let Some(monitor) = monitor else {
+        return;
+    };
+    let origin = IVec2::new(monitor.position().x, monitor.position().y);
+    let area = IVec2::new(monitor.size().width as i32, monitor.size().height as i32);
+    let scale = monitor.scale_factor() as f32;
+    let size = placement.size.map(|size| size.min(area.as_vec2() / scale));
+    if let Some(size) = size {
+        window.set_resolution(size.x, size.y);
+    }
+    if let Some(position) = placement.position {
+        // the title bar isn't counted, it only has to be close
+        let outer = size.map_or(IVec2::ZERO, |size| (size * scale).as_ivec2());
+        let position = position.clamp(origin, (origin + area - outer).max(origin));
+        handle.set_outer_position(PhysicalPosition::new(position.x, position.y));
+    }
+    if placement.fullscreen {
+        window.set_mode(WindowMode::BorderlessFullscreen);
+    }
+}
+
+// F11 -> fullscreen on/off
+fn handle_keyboard(key: Keys, mut windows: ResMut<Windows>) {
+    if !key.just_pressed(|k| &k.fullscreen) {
+        return;
+    }
+    if let Some(window) = windows.get_primary_mut() {
+        let mode = match window.mode() {
+            WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+            _ => WindowMode::Windowed,
+        };
+        window.set_mode(mode);
+    }
+}
+
+fn track(
+    mut moved: EventReader<WindowMoved>,
+    mut resized: EventReader<WindowResized>,
+    windows: Res<Windows>,
+    winit: NonSend<WinitWindows>,
+    mut placement: ResMut<Placement>,
+    mut started: Local<bool>, // a window that is never moved is still remembered
+) {
+    let Some(window) = windows.get_primary() else {
+        return;
+    };
+    let fullscreen = window.mode() != WindowMode::Windowed;
+    let changed = moved.iter().count() + resized.iter().count() > 0;
+    if *started && !changed && fullscreen == placement.fullscreen {
+        return;
+    }
+    *started = true;
+    placement.fullscreen = fullscreen;
+    // a fullscreen window covers its monitor, the windowed placement to go back to is kept
+    if !fullscreen {
+        placement.size = Some(Vec2::new(window.width(), window.height()));
+        placement.position = window.position().or(placement.position);
+    }
+    let monitor = winit
+        .get_window(window.id())
+        .and_then(|w| w.current_monitor());
+    if let Some(name) = monitor.and_then(|m| m.name()) {
+        placement.monitor = Some(name);
+    }
+}
+
+// the settings are only read at startup, so they are written once on the way out
+fn save_on_exit(exits: EventReader<AppExit>, placement: Res<Placement>) {
+    if exits.is_empty() {
+        return;
+    }
+    let mut settings = Settings::load();
+    placement.write(&mut settings);
+    if let Err(e) = settings.save() {
+        warn!("{}", e);
+    }
+}
@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+
+use crate::board::Board;
+use crate::hud::HudElement;
+use crate::sim::{BoardView, Keys, State, Tool};
+
+const NEIGHBOUR_COLOR: Color = Color::rgba(1.0, 0.8, 0.0, 0.35);
+
+// cell currently being probed
+#[derive(Resource, Default, PartialEq, Eq)]
+struct Probed(Option<UVec2>);
+
+#[derive(Component)]
+struct ProbeHighlight;
+
+#[derive(Component)]
+struct ProbeText;
+
+pub struct ProbePlugin;
+
+impl Plugin for ProbePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Probed>()
+            .add_startup_system(spawn_text)
+            .add_system(handle_keyboard)
+            .add_system(update_probe)
+            .add_system(draw_probe.after(update_probe));
+    }
+}
+
+fn spawn_text(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("Kid Marker.ttf"),
+                font_size: 20.0,
+                color: Color::ORANGE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                bottom: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+        ProbeText,
+        HudElement,
+    ));
+}
+
+// I -> switch between the draw and probe tools
+fn handle_keyboard(key: Keys, mut tool: ResMut<Tool>) {
+    if key.just_pressed(|k| &k.probe) {
+        *tool = match *tool {
+            Tool::Probe => Tool::Draw,
+            _ => Tool::Probe,
+        };
+    }
+}
+
+// the probe only works while paused, a running board changes under the cursor
+fn update_probe(view: BoardView, tool: Res<Tool>, state: Res<State>, mut probed: ResMut<Probed>) {
+    let Some(screen) = view.screen() else {
+        return;
+    };
+    let cell = if *tool == Tool::Probe && !state.0 {
+        screen.cursor_to_cell()
+    } else {
+        None
+    };
+    if probed.0 != cell {
+        probed.0 = cell;
+    }
+}
+
+fn draw_probe(
+    mut commands: Commands,
+    view: BoardView,
+    probed: Res<Probed>,
+    map: Res<Board>,
+    highlights: Query<Entity, With<ProbeHighlight>>,
+    mut text: Query<&mut Text, With<ProbeText>>,
+) {
+    if !probed.is_changed() && !map.is_changed() {
+        return;
+    }
+    for entity in highlights.iter() {
+        commands.entity(entity).despawn();
+    }
+    let Ok(mut text) = text.get_single_mut() else {
+        return;
+    };
+    // the probed cell can be past the edge for a frame after the grid shrinks
+    let size = map.size();
+    let Some(cell) = probed.0.filter(|c| c.x < size.x && c.y < size.y) else {
+        text.sections[0].value.clear();
+        return;
+    };
+
+    let Some(screen) = view.screen() else {
+        return;
+    };
+    for (x, y) in map.neighbourhood(cell.x, cell.y) {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: NEIGHBOUR_COLOR,
+                    custom_size: Some(screen.cell_size()),
+                    ..default()
+                },
+                transform: Transform::from_translation(
+                    screen.cell_to_world(x as f32, y as f32).extend(1.0),
+                ),
+                ..default()
+            },
+            ProbeHighlight,
+        ));
+    }
+
+    let alive = map.alive(cell.x, cell.y);
+    let n = map.neighbours(cell.x, cell.y);
+    let outcome = match (alive, map.next_alive(cell.x, cell.y)) {
+        // nothing is born on a cell that is still fading out
+        _ if map.dying(cell.x, cell.y) > 0 => "decays",
+        (true, true) => "survives",
+        (true, false) => "dies",
+        (false, true) => "is born",
+        (false, false) => "stays empty",
+    };
+    text.sections[0].value = format!("({}, {}): {} neighbours, {}", cell.x, cell.y, n, outcome);
+}
@@ -0,0 +1,504 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use std::fs;
+
+use crate::board::Board;
+use crate::pattern::{self, Stamped};
+use crate::rle::{self, Pattern};
+use crate::sim::{
+    BoardTransform, ClearBoard, GridConfig, Keys, RestoreBoard, SetCell, ToggleCell,
+    TransformBoard, MAX_GRID, MIN_GRID,
+};
+use crate::toast::Toast;
+
+const RECIPE_FILE: &str = "recipe.txt";
+
+// a single cell edit, None toggles the cell
+#[derive(Clone, Copy)]
+struct Edit {
+    x: u32,
+    y: u32,
+    alive: Option<bool>,
+}
+
+// one editing operation, in the order the user performed it
+// the grouped ones are replayed as a unit, with a single Enter
+#[derive(Clone)]
+enum Step {
+    Clear,
+    Toggle(u32, u32), // single edits, only read from older recipes, see Stroke
+    Set(u32, u32, bool),
+    Stroke(Vec<Edit>), // a drag, a shape, a cut or a click, written as a single edit if it is one
+    Stamp(String, Vec<Edit>), // the cells of a pattern put on the board at once
+    Transform(BoardTransform),
+    Resize(GridConfig),
+    Restore(GridConfig, Pattern), // a whole new board, from a load, a restart or the timeline
+}
+
+// every edit since startup, so a construction can be exported and replayed
+#[derive(Resource, Default)]
+struct Recipe(Vec<Step>);
+
+// steps loaded from a recipe file, waiting to be applied one by one
+#[derive(Resource, Default)]
+struct Replay(Vec<Step>);
+
+// loads recipe.txt for replay, like L
+pub struct LoadRecipe;
+
+pub struct RecipePlugin;
+
+impl Plugin for RecipePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Recipe>()
+            .init_resource::<Replay>()
+            .add_event::<LoadRecipe>()
+            .add_system(record)
+            .add_system(handle_keyboard)
+            .add_system(load_recipe);
+    }
+}
+
+// everything that changes the board, as it comes in
+#[derive(SystemParam)]
+struct Changes<'w, 's> {
+    clears: EventReader<'w, 's, ClearBoard>,
+    restores: EventReader<'w, 's, RestoreBoard>,
+    transforms: EventReader<'w, 's, TransformBoard>,
+    toggles: EventReader<'w, 's, ToggleCell>,
+    sets: EventReader<'w, 's, SetCell>,
+    stamps: EventReader<'w, 's, Stamped>,
+    grid: Res<'w, GridConfig>,
+}
+
+// `open` while the last stroke can still grow: its button has been held since
+fn record(
+    mut changes: Changes,
+    mouse: Res<Input<MouseButton>>,
+    mut recipe: ResMut<Recipe>,
+    mut open: Local<bool>,
+) {
+    // a clear or a new board makes everything before it irrelevant
+    if changes.clears.iter().count() > 0 {
+        recipe.0 = vec![Step::Clear];
+        *open = false;
+    }
+    let restored = changes.restores.iter().last();
+    if let Some(restore) = restored {
+        recipe.0 = vec![Step::Restore(restore.grid, board_pattern(&restore.map))];
+        *open = false;
+    } else if changes.grid.is_changed() && !changes.grid.is_added() {
+        // a restore of another size resizes the grid right after, that's in the restore already
+        let restored =
+            matches!(recipe.0.last(), Some(Step::Restore(grid, _)) if *grid == *changes.grid);
+        if !restored {
+            recipe.0.push(Step::Resize(*changes.grid));
+        }
+        *open = false;
+    }
+    for TransformBoard(transform) in changes.transforms.iter() {
+        recipe.0.push(Step::Transform(*transform));
+        *open = false;
+    }
+    let edits: Vec<Edit> = changes
+        .toggles
+        .iter()
+        .map(|t| Edit {
+            x: t.x,
+            y: t.y,
+            alive: None,
+        })
+        .chain(changes.sets.iter().map(|s| Edit {
+            x: s.x,
+            y: s.y,
+            alive: Some(s.alive),
+        }))
+        .collect();
+    let stamp = changes.stamps.iter().last().map(|stamp| stamp.0.clone());
+    let held = mouse.any_pressed([MouseButton::Left, MouseButton::Right]);
+    if let Some(name) = stamp {
+        recipe.0.push(Step::Stamp(name, edits));
+        *open = false;
+        return;
+    }
+    if edits.is_empty() {
+        *open = *open && held;
+        return;
+    }
+    match recipe.0.last_mut() {
+        Some(Step::Stroke(stroke)) if *open => stroke.extend(edits),
+        _ => recipe.0.push(Step::Stroke(edits)),
+    }
+    *open = held;
+}
+
+// the live cells of a whole board
+fn board_pattern(map: &Board) -> Pattern {
+    pattern::board_pattern(map, (UVec2::ZERO, map.size() - UVec2::ONE))
+}
+
+// "x,y,1" sets a cell alive, "x,y,0" dead, "x,y,t" toggles it
+fn edits_text(edits: &[Edit]) -> String {
+    let edit = |e: &Edit| {
+        let alive = match e.alive {
+            None => "t",
+            Some(true) => "1",
+            Some(false) => "0",
+        };
+        format!("{},{},{}", e.x, e.y, alive)
+    };
+    edits.iter().map(edit).collect::<Vec<_>>().join(" ")
+}
+
+fn parse_edits(words: &[&str]) -> Option<Vec<Edit>> {
+    words
+        .iter()
+        .map(|word| {
+            let mut parts = word.split(',');
+            let x = parts.next()?.parse().ok()?;
+            let y = parts.next()?.parse().ok()?;
+            let alive = match parts.next()? {
+                "t" => None,
+                "1" => Some(true),
+                "0" => Some(false),
+                _ => return None,
+            };
+            parts.next().is_none().then_some(Edit { x, y, alive })
+        })
+        .collect()
+}
+
+fn transform_text(transform: &BoardTransform) -> String {
+    match transform {
+        BoardTransform::RotateClockwise => "rotate".to_string(),
+        BoardTransform::MirrorHorizontal => "mirror horizontal".to_string(),
+        BoardTransform::MirrorVertical => "mirror vertical".to_string(),
+        BoardTransform::Shift { by, wrap } => {
+            let edges = if *wrap { "wrap" } else { "clip" };
+            format!("shift {} {} {}", by.x, by.y, edges)
+        }
+    }
+}
+
+fn parse_transform(words: &[&str]) -> Option<BoardTransform> {
+    Some(match words {
+        ["rotate"] => BoardTransform::RotateClockwise,
+        ["mirror", "horizontal"] => BoardTransform::MirrorHorizontal,
+        ["mirror", "vertical"] => BoardTransform::MirrorVertical,
+        ["shift", x, y, edges] => BoardTransform::Shift {
+            by: IVec2::new(x.parse().ok()?, y.parse().ok()?),
+            wrap: match *edges {
+                "wrap" => true,
+                "clip" => false,
+                _ => return None,
+            },
+        },
+        _ => return None,
+    })
+}
+
+// a hand-edited or damaged size would make an empty or a huge board, as in checkpoints
+fn parse_size(text: &str) -> Result<GridConfig, String> {
+    let bad = || format!("bad size {}", text);
+    let (width, height) = text.split_once('x').ok_or_else(bad)?;
+    let grid = GridConfig {
+        width: width.parse().map_err(|_| bad())?,
+        height: height.parse().map_err(|_| bad())?,
+    };
+    let sides = MIN_GRID..=MAX_GRID;
+    if !sides.contains(&grid.width) || !sides.contains(&grid.height) {
+        return Err(format!(
+            "size {}x{} is not between {2}x{2} and {3}x{3}",
+            grid.width, grid.height, MIN_GRID, MAX_GRID
+        ));
+    }
+    Ok(grid)
+}
+
+fn to_text(steps: &[Step]) -> String {
+    let mut text = String::from("# bevy_conway recipe\n");
+    for step in steps {
+        let line = match step {
+            Step::Clear => "clear".to_string(),
+            Step::Toggle(x, y) => format!("toggle {} {}", x, y),
+            Step::Set(x, y, alive) => format!("set {} {} {}", x, y, *alive as u8),
+            Step::Stroke(edits) => match edits.as_slice() {
+                [Edit { x, y, alive: None }] => format!("toggle {} {}", x, y),
+                [Edit {
+                    x,
+                    y,
+                    alive: Some(alive),
+                }] => format!("set {} {} {}", x, y, *alive as u8),
+                _ => format!("stroke {}", edits_text(edits)),
+            },
+            // the name can have spaces, the colon ends it
+            Step::Stamp(name, edits) => format!("stamp {}: {}", name, edits_text(edits)),
+            Step::Transform(transform) => format!("transform {}", transform_text(transform)),
+            Step::Resize(grid) => format!("resize {}x{}", grid.width, grid.height),
+            // the RLE body without its header and line breaks, the size gives it
+            Step::Restore(grid, pattern) => {
+                let rle = rle::to_text(pattern, "B3/S23");
+                let body: String = rle.lines().skip(1).collect();
+                format!("restore {}x{} {}", grid.width, grid.height, body)
+            }
+        };
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text
+}
+
+fn from_text(text: &str) -> Result<Vec<Step>, String> {
+    let mut steps = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let bad = |what: &str| format!("line {}: bad {} step", i + 1, what);
+        let size = |text: &str| parse_size(text).map_err(|e| format!("line {}: {}", i + 1, e));
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let step = match words.as_slice() {
+            ["clear"] => Step::Clear,
+            ["toggle", x, y] => match (x.parse(), y.parse()) {
+                (Ok(x), Ok(y)) => Step::Toggle(x, y),
+                _ => return Err(format!("line {}: bad coordinates", i + 1)),
+            },
+            ["set", x, y, alive] => match (x.parse(), y.parse(), *alive) {
+                (Ok(x), Ok(y), "0" | "1") => Step::Set(x, y, *alive == "1"),
+                _ => return Err(bad("set")),
+            },
+            ["stroke", edits @ ..] => {
+                Step::Stroke(parse_edits(edits).ok_or_else(|| bad("stroke"))?)
+            }
+            ["stamp", ..] => {
+                let (name, edits) = line["stamp".len()..]
+                    .split_once(':')
+                    .ok_or_else(|| bad("stamp"))?;
+                let edits: Vec<&str> = edits.split_whitespace().collect();
+                let edits = parse_edits(&edits).ok_or_else(|| bad("stamp"))?;
+                Step::Stamp(name.trim().to_string(), edits)
+            }
+            ["transform", transform @ ..] => {
+                Step::Transform(parse_transform(transform).ok_or_else(|| bad("transform"))?)
+            }
+            ["resize", text] => Step::Resize(size(text)?),
+            ["restore", text, body @ ..] => {
+                let grid = size(text)?;
+                let board = grid.board_size();
+                let rle = format!("x = {}, y = {}\n{}", board.x, board.y, body.concat());
+                let pattern = rle::parse(&rle).map_err(|e| format!("line {}: {}", i + 1, e))?;
+                if pattern.size != board {
+                    return Err(bad("restore"));
+                }
+                Step::Restore(grid, pattern)
+            }
+            _ => return Err(format!("line {}: unknown step '{}'", i + 1, line)),
+        };
+        steps.push(step);
+    }
+    Ok(steps)
+}
+
+// what replaying a step sends
+#[derive(SystemParam)]
+struct Replayer<'w, 's> {
+    clears: EventWriter<'w, 's, ClearBoard>,
+    toggles: EventWriter<'w, 's, ToggleCell>,
+    sets: EventWriter<'w, 's, SetCell>,
+    stamps: EventWriter<'w, 's, Stamped>,
+    transforms: EventWriter<'w, 's, TransformBoard>,
+    restores: EventWriter<'w, 's, RestoreBoard>,
+    grid: ResMut<'w, GridConfig>,
+    map: Res<'w, Board>,
+}
+
+impl Replayer<'_, '_> {
+    fn edits(&mut self, edits: &[Edit]) {
+        for &Edit { x, y, alive } in edits {
+            match alive {
+                None => self.toggles.send(ToggleCell { x, y }),
+                Some(alive) => self.sets.send(SetCell { x, y, alive }),
+            }
+        }
+    }
+
+    fn apply(&mut self, step: Step) {
+        match step {
+            Step::Clear => self.clears.send(ClearBoard),
+            Step::Toggle(x, y) => self.toggles.send(ToggleCell { x, y }),
+            Step::Set(x, y, alive) => self.sets.send(SetCell { x, y, alive }),
+            Step::Stroke(edits) => self.edits(&edits),
+            Step::Stamp(name, edits) => {
+                self.edits(&edits);
+                self.stamps.send(Stamped(name));
+            }
+            Step::Transform(transform) => self.transforms.send(TransformBoard(transform)),
+            Step::Resize(grid) => {
+                if *self.grid != grid {
+                    *self.grid = grid;
+                }
+            }
+            // with the rule and boundary in use now
+            Step::Restore(grid, pattern) => {
+                let mut map = self.map.cleared().resized(pattern.size, IVec2::ZERO);
+                for cell in pattern.alive.iter() {
+                    map.set(cell.x, cell.y, true);
+                }
+                self.restores.send(RestoreBoard {
+                    map,
+                    generation: 0,
+                    grid,
+                });
+            }
+        }
+    }
+}
+
+// E     -> export the recipe to recipe.txt
+// Enter -> apply the next replay step, a stroke or a stamp goes in at once
+fn handle_keyboard(
+    key: Keys,
+    recipe: Res<Recipe>,
+    mut replay: ResMut<Replay>,
+    mut replayer: Replayer,
+    mut toasts: EventWriter<Toast>,
+) {
+    if key.just_pressed(|k| &k.export_recipe) {
+        match fs::write(RECIPE_FILE, to_text(&recipe.0)) {
+            Ok(()) => info!(
+                "recipe with {} steps written to {}",
+                recipe.0.len(),
+                RECIPE_FILE
+            ),
+            Err(e) => toasts.send(Toast(format!("could not write {}: {}", RECIPE_FILE, e))),
+        }
+    }
+    if key.just_pressed(|k| &k.replay_step) {
+        if let Some(step) = replay.0.pop() {
+            replayer.apply(step);
+        }
+    }
+}
+
+// L -> load recipe.txt for replay
+fn load_recipe(
+    key: Keys,
+    mut loads: EventReader<LoadRecipe>,
+    mut replay: ResMut<Replay>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if loads.iter().count() == 0 && !key.just_pressed(|k| &k.load_recipe) {
+        return;
+    }
+    match fs::read_to_string(RECIPE_FILE)
+        .map_err(|e| e.to_string())
+        .and_then(|t| from_text(&t))
+    {
+        Ok(mut steps) => {
+            // replay from an empty board, steps are popped from the back
+            if !matches!(steps.first(), Some(Step::Restore(..))) {
+                steps.insert(0, Step::Clear);
+            }
+            steps.reverse();
+            replay.0 = steps;
+        }
+        Err(e) => toasts.send(Toast(format!("could not load {}: {}", RECIPE_FILE, e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECIPE: &str = "# bevy_conway recipe
+clear
+toggle 1 2
+set 3 4 1
+stroke 0,0,1 1,0,0 2,0,t
+stamp Gosper glider gun: 5,5,1 6,5,1
+transform rotate
+transform shift -2 3 clip
+resize 30x40
+restore 5x8 bo$2bo$3o!
+";
+
+    #[test]
+    fn parses_every_step() {
+        let steps = from_text(RECIPE).unwrap();
+        assert_eq!(steps.len(), 9);
+        assert!(matches!(steps[0], Step::Clear));
+        assert!(matches!(steps[1], Step::Toggle(1, 2)));
+        assert!(matches!(steps[2], Step::Set(3, 4, true)));
+        match &steps[3] {
+            Step::Stroke(edits) => {
+                let alive: Vec<_> = edits.iter().map(|e| (e.x, e.y, e.alive)).collect();
+                assert_eq!(
+                    alive,
+                    [(0, 0, Some(true)), (1, 0, Some(false)), (2, 0, None)]
+                );
+            }
+            _ => panic!("not a stroke"),
+        }
+        assert!(matches!(&steps[4], Step::Stamp(name, edits)
+            if name == "Gosper glider gun" && edits.len() == 2));
+        assert!(matches!(
+            steps[5],
+            Step::Transform(BoardTransform::RotateClockwise)
+        ));
+        assert!(matches!(
+            steps[6],
+            Step::Transform(BoardTransform::Shift { by, wrap: false }) if by == IVec2::new(-2, 3)
+        ));
+        assert!(matches!(
+            steps[7],
+            Step::Resize(GridConfig {
+                width: 30,
+                height: 40
+            })
+        ));
+        match &steps[8] {
+            Step::Restore(grid, pattern) => {
+                assert_eq!((grid.width, grid.height), (5, 8));
+                assert_eq!(pattern.size, grid.board_size());
+                assert_eq!(pattern.alive.len(), 5);
+            }
+            _ => panic!("not a restore"),
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let steps = from_text(RECIPE).unwrap();
+        let text = to_text(&steps);
+        assert_eq!(to_text(&from_text(&text).unwrap()), text);
+        // a single edit stroke is written like the old single edits
+        assert!(text.contains("\ntoggle 1 2\nset 3 4 1\n"));
+    }
+
+    #[test]
+    fn rejects_bad_steps() {
+        for line in [
+            "jump 10",
+            "toggle 1",
+            "toggle a 2",
+            "set 1 2 3",
+            "stroke 1,2",
+            "stroke 1,2,1,4",
+            "stamp no colon 1,1,1",
+            "transform spin",
+            "transform shift 1 1 bounce",
+            "resize 30",
+            "resize 0x0",
+            "resize 3x1",
+            "resize 99999x99999",
+            "restore 1x1 o!",
+            "restore 5x8 20$o!",
+            "restore 5x8 b?o!",
+        ] {
+            let text = format!("clear\n{}\n", line);
+            let err = from_text(&text).err();
+            assert!(err.is_some_and(|e| e.starts_with("line 2")), "{}", line);
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use bevy::prelude::UVec2;
+
+// patterns wider or taller than this are rejected instead of allocated
+const MAX_SIDE: u32 = 10_000;
+
+// a pattern read from a Run Length Encoded file, the format Golly and the LifeWiki use
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    pub size: UVec2,
+    pub alive: Vec<UVec2>, // y grows upwards like on the board, (0, 0) is the bottom left corner
+}
+
+impl Pattern {
+    // a quarter turn clockwise, the top row becomes the right column
+    pub fn rotated(&self) -> Pattern {
+        Pattern {
+            size: UVec2::new(self.size.y, self.size.x),
+            alive: self
+                .alive
+                .iter()
+                .map(|c| UVec2::new(c.y, self.size.x - 1 - c.x))
+                .collect(),
+        }
+    }
+
+    // mirrored left to right
+    pub fn flipped_x(&self) -> Pattern {
+        Pattern {
+            size: self.size,
+            alive: self
+                .alive
+                .iter()
+                .map(|c| UVec2::new(self.size.x - 1 - c.x, c.y))
+                .collect(),
+        }
+    }
+
+    // mirrored top to bottom
+    pub fn flipped_y(&self) -> Pattern {
+        Pattern {
+            size: self.size,
+            alive: self
+                .alive
+                .iter()
+                .map(|c| UVec2::new(c.x, self.size.y - 1 - c.y))
+                .collect(),
+        }
+    }
+}
+
+// "x = 3, y = 3, rule = B3/S23", the rule is accepted but not checked here
+// Golly's bounded grids put a comma in the rule ("rule = B3/S23:T100,100"), the rest of it
+// the size, and the rule as written if there is one
+fn parse_header(line: &str) -> Result<(UVec2, Option<String>), String> {
+    let (mut x, mut y) = (None, None);
+    let mut rule: Option<String> = None;
+    for field in line.split(',') {
+        let Some((key, value)) = field.split_once('=') else {
+            // a rule with commas in it, like Golly's "B3,S23"
+            if let Some(rule) = rule.as_mut() {
+                rule.push(',');
+                rule.push_str(field.trim());
+                continue;
+            }
+            return Err(format!("bad header field {}", field.trim()));
+        };
+        let number = || {
+            value
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| format!("bad pattern size {}", value.trim()))
+        };
+        match key.trim() {
+            "x" => x = Some(number()?),
+            "y" => y = Some(number()?),
+            "rule" => rule = Some(value.trim().to_string()),
+            _ => {}
+        }
+    }
+    match (x, y) {
+        (Some(x), Some(y)) => Ok((UVec2::new(x, y), rule)),
+        _ => Err("the header needs both x and y".to_string()),
+    }
+}
+
+pub fn parse(text: &str) -> Result<Pattern, String> {
+    parse_with_rule(text).map(|(pattern, _)| pattern)
+}
+
+// the rule is left as written, it may not be one this app can run
+pub fn parse_with_rule(text: &str) -> Result<(Pattern, Option<String>), String> {
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'));
+    let (size, rule) = parse_header(lines.next().ok_or("missing x = .., y = .. header")?)?;
+    if size.max_element() > MAX_SIDE {
+        return Err(format!("patterns are limited to {0}x{0} cells", MAX_SIDE));
+    }
+
+    // cells are collected top row first, then flipped once the height is known
+    let mut cells = Vec::new();
+    let (mut x, mut row) = (0u32, 0u32);
+    // a run count can be split over two lines, so it survives the line break
+    let mut run: Option<u32> = None;
+    // Golly's multi-state tags above X are two letters, "pA" is state 49, the count goes with
+    // the second one
+    let mut prefixed: Option<u32> = None;
+    'body: for line in lines {
+        for c in line.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                let n = run
+                    .unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|v| v.checked_add(digit));
+                run = Some(n.ok_or("run count too large")?);
+                continue;
+            }
+            if c.is_whitespace() {
+                continue;
+            }
+            let n = match prefixed.take() {
+                Some(n) => n,
+                None => run.take().unwrap_or(1),
+            };
+            // a run of $ only moves down the rows, any other run only along its row
+            let over = match c {
+                '$' => row.saturating_add(n) > MAX_SIDE,
+                _ => x.saturating_add(n) > MAX_SIDE || row >= MAX_SIDE,
+            };
+            if over {
+                return Err(format!("patterns are limited to {0}x{0} cells", MAX_SIDE));
+            }
+            match c {
+                'b' | '.' => x += n,
+                '$' => {
+                    row += n;
+                    x = 0;
+                }
+                'p'..='y' => prefixed = Some(n),
+                '!' => break 'body,
+                // every other state of a multi-state pattern counts as alive
+                c if c.is_ascii_alphabetic() => {
+                    cells.extend((x..x + n).map(|x| UVec2::new(x, row)));
+                    x += n;
+                }
+                c => return Err(format!("unexpected character {:?}", c)),
+            }
+        }
+    }
+
+    // the header should cover every cell, but a sloppy one is not worth rejecting the file
+    let size = cells.iter().fold(size, |size, c| size.max(*c + UVec2::ONE));
+    let alive = cells
+        .into_iter()
+        .map(|c| UVec2::new(c.x, size.y - 1 - c.y))
+        .collect();
+    Ok((Pattern { size, alive }, rule))
+}
+
+// Golly keeps RLE lines under 70 characters
+const LINE_WIDTH: usize = 70;
+
+// the pattern as RLE text with its header, trailing dead cells of each row are left out
+pub fn to_text(pattern: &Pattern, rule: &str) -> String {
+    let mut rows = vec![vec![false; pattern.size.x as usize]; pattern.size.y as usize];
+    for cell in pattern.alive.iter() {
+        // the first RLE row is the top one
+        rows[(pattern.size.y - 1 - cell.y) as usize][cell.x as usize] = true;
+    }
+
+    // (count, tag) runs, empty rows fold into the count of the next '$'
+    let mut runs: Vec<(usize, char)> = Vec::new();
+    let push = |runs: &mut Vec<(usize, char)>, n: usize, tag: char| match runs.last_mut() {
+        Some((count, last)) if *last == tag => *count += n,
+        _ => runs.push((n, tag)),
+    };
+    for (i, row) in rows.iter().enumerate() {
+        if i > 0 {
+            push(&mut runs, 1, '$');
+        }
+        let end = row.iter().rposition(|&alive| alive).map_or(0, |x| x + 1);
+        for &alive in &row[..end] {
+            push(&mut runs, 1, if alive { 'o' } else { 'b' });
+        }
+    }
+    push(&mut runs, 1, '!');
+
+    let mut text = format!(
+        "x = {}, y = {}, rule = {}\n",
+        pattern.size.x, pattern.size.y, rule
+    );
+    let mut line = String::new();
+    for (count, tag) in runs {
+        let run = match count {
+            1 => tag.to_string(),
+            n => format!("{}{}", n, tag),
+        };
+        if line.len() + run.len() > LINE_WIDTH {
+            text.push_str(&line);
+            text.push('\n');
+            line.clear();
+        }
+        line.push_str(&run);
+    }
+    text.push_str(&line);
+    text.push('\n');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLIDER: &str = "#N Glider\nx = 3, y = 3, rule = B3/S23\nbob$2bo$3o!\n";
+
+    fn sorted(pattern: &Pattern) -> Vec<(u32, u32)> {
+        let mut cells: Vec<(u32, u32)> = pattern.alive.iter().map(|c| (c.x, c.y)).collect();
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+    }
+
+    #[test]
+    fn parses_a_glider() {
+        let glider = parse(GLIDER).unwrap();
+        assert_eq!(glider.size, UVec2::new(3, 3));
+        // the first row is the top one
+        assert_eq!(
+            sorted(&glider),
+            vec![(0, 0), (1, 0), (1, 2), (2, 0), (2, 1)]
+        );
+    }
+
+    #[test]
+    fn keeps_the_header_rule() {
+        let (_, rule) = parse_with_rule("x = 3, y = 1, rule = B36/S23\n3o!\n").unwrap();
+        assert_eq!(rule.as_deref(), Some("B36/S23"));
+        let (_, rule) = parse_with_rule("x = 3, y = 1, rule = B3,S23\n3o!\n").unwrap();
+        assert_eq!(rule.as_deref(), Some("B3,S23"));
+        let (_, rule) = parse_with_rule("x = 3, y = 1\n3o!\n").unwrap();
+        assert_eq!(rule, None);
+    }
+
+    #[test]
+    fn round_trips() {
+        let glider = parse(GLIDER).unwrap();
+        let text = to_text(&glider, "B3/S23");
+        assert!(text.starts_with("x = 3, y = 3, rule = B3/S23\n"));
+        let again = parse(&text).unwrap();
+        assert_eq!(again.size, glider.size);
+        assert_eq!(sorted(&again), sorted(&glider));
+
+        // long runs, empty rows and lines past LINE_WIDTH
+        let wide = Pattern {
+            size: UVec2::new(200, 7),
+            alive: (0..200)
+                .step_by(3)
+                .map(|x| UVec2::new(x, x % 7))
+                .chain((50..120).map(|x| UVec2::new(x, 6)))
+                .collect(),
+        };
+        let text = to_text(&wide, "B3/S23");
+        assert!(text.lines().all(|line| line.len() <= LINE_WIDTH));
+        let again = parse(&text).unwrap();
+        assert_eq!(again.size, wide.size);
+        assert_eq!(sorted(&again), sorted(&wide));
+    }
+
+    #[test]
+    fn splits_runs_over_lines() {
+        let pattern = parse("x = 12, y = 1\n1\n2o!").unwrap();
+        assert_eq!(pattern.alive.len(), 12);
+    }
+
+    #[test]
+    fn reads_golly_extras() {
+        // a bounded grid puts a comma in the rule
+        let pattern = parse("x = 2, y = 1, rule = B3/S23:T100,100\n2o!").unwrap();
+        assert_eq!(pattern.alive.len(), 2);
+        // a two letter state is a single cell
+        let pattern = parse("x = 3, y = 1, rule = LifeHistory\npA.B!").unwrap();
+        assert_eq!(sorted(&pattern), vec![(0, 0), (2, 0)]);
+    }
+
+    #[test]
+    fn limits_the_size() {
+        assert!(parse("x = 20000, y = 1\no!").is_err());
+        assert!(parse("x = 1, y = 1\n20000o!").is_err());
+        assert!(parse("x = 1, y = 1\n20000$o!").is_err());
+        // a long run on a deep row is only as wide as it is
+        let deep = parse("x = 9000, y = 9000\n8999$9000o!").unwrap();
+        assert_eq!(deep.alive.len(), 9000);
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        assert!(parse("").is_err());
+        assert!(parse("bo$ob!").is_err());
+        assert!(parse("x = 3\nbo!").is_err());
+        assert!(parse("x = a, y = 3\nbo!").is_err());
+        assert!(parse("x = 3, y = 3\nb?o!").is_err());
+    }
+}
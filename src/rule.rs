@@ -0,0 +1,427 @@
+use bevy::prelude::*;
+use std::fmt;
+
+use crate::sim::Keys;
+
+// a Life-like rule, bit n of `birth` / `survival` is set when n live neighbours cause it
+// with more than 2 `states` it is a Generations rule: a live cell that does not survive decays
+// through states - 2 dying states before it is dead, dying cells are not live neighbours and
+// nothing can be born on them
+// the default is Conway's Game of Life:
+// Any live cell with two or three live neighbours survives.
+// Any dead cell with three live neighbours becomes a live cell.
+// All other live cells die in the next generation. Similarly, all other dead cells stay dead.
+// a rule from a plugin is a `table` instead: one bit per 3x3 block, whether its centre is alive
+// next, blocks are numbered as in `block`
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Ruleset {
+    birth: u16,
+    survival: u16,
+    states: u8,
+    table: Option<[u64; 8]>,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Ruleset {
+            birth: 1 << 3,
+            survival: 1 << 2 | 1 << 3,
+            states: 2,
+            table: None,
+        }
+    }
+}
+
+// bit 3 * (dy + 1) + (dx + 1) is the cell at (dx, dy) from the centre, y going up, so bit 4 is
+// the centre itself, the numbering plugins see
+pub fn block(alive: impl Fn(i32, i32) -> bool) -> u16 {
+    let mut cells = 0;
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            if alive(dx, dy) {
+                cells |= 1 << (3 * (dy + 1) + (dx + 1));
+            }
+        }
+    }
+    cells
+}
+
+// the same block numbered as in Golly's MAP rules: NW, N, NE, W, C, E, SW, S, SE from bit 8 down
+fn golly_index(cells: u16) -> usize {
+    let mut index = 0;
+    for dy in -1..=1i32 {
+        for dx in -1..=1i32 {
+            if cells & 1 << (3 * (dy + 1) + (dx + 1)) != 0 {
+                index |= 1 << (8 - (3 * (1 - dy) + (dx + 1)));
+            }
+        }
+    }
+    index
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// cycled through with G
+const PRESETS: &[(&str, &str)] = &[
+    ("Life", "B3/S23"),
+    ("HighLife", "B36/S23"),
+    ("Day & Night", "B3678/S34678"),
+    ("Seeds", "B2/S"),
+    ("Life without Death", "B3/S012345678"),
+    ("Maze", "B3/S12345"),
+    ("Brian's Brain", "B2/S/3"),
+    ("Star Wars", "B2/S345/4"),
+];
+
+impl Ruleset {
+    // `next` tells for every block in `block`'s numbering whether its centre is alive next
+    pub fn from_table(next: impl Fn(u16) -> bool) -> Ruleset {
+        let mut table = [0u64; 8];
+        for cells in 0..512u16 {
+            if next(cells) {
+                table[cells as usize / 64] |= 1 << (cells % 64);
+            }
+        }
+        Ruleset {
+            birth: 0,
+            survival: 0,
+            states: 2,
+            table: Some(table),
+        }
+    }
+
+    // false for table rules, which only `next_block` can step
+    pub fn is_totalistic(&self) -> bool {
+        self.table.is_none()
+    }
+
+    pub fn next_block(&self, cells: u16) -> bool {
+        match self.table {
+            Some(table) => table[cells as usize / 64] & 1 << (cells % 64) != 0,
+            None => {
+                let neighbours = (cells & !(1 << 4)).count_ones() as u8;
+                self.next_state(cells & 1 << 4 != 0, neighbours)
+            }
+        }
+    }
+
+    // Life-like rules only, see next_block
+    pub fn next_state(&self, alive: bool, neighbours: u8) -> bool {
+        let mask = if alive { self.survival } else { self.birth };
+        mask & (1 << neighbours) != 0
+    }
+
+    // dying states a cell goes through, 0 for Life-like rules
+    pub fn decay_states(&self) -> u8 {
+        self.states - 2
+    }
+
+    // one neighbour count added to or taken out of the births or the survivals,
+    // None for table rules and for B0
+    pub fn toggled(&self, birth: bool, neighbours: u8) -> Option<Ruleset> {
+        if self.table.is_some() || neighbours > 8 || (birth && neighbours == 0) {
+            return None;
+        }
+        let mut rule = *self;
+        match birth {
+            true => rule.birth ^= 1 << neighbours,
+            false => rule.survival ^= 1 << neighbours,
+        }
+        Some(rule)
+    }
+
+    // a two state rule from (birth, survival) bit masks as `masks` gives them,
+    // None for B0 and for counts past 8
+    pub fn life_like(birth: u16, survival: u16) -> Option<Ruleset> {
+        if birth & 1 != 0 || (birth | survival) >> 9 != 0 {
+            return None;
+        }
+        Some(Ruleset {
+            birth,
+            survival,
+            states: 2,
+            table: None,
+        })
+    }
+
+    // (birth, survival) neighbour bit masks
+    pub fn masks(&self) -> (u16, u16) {
+        (self.birth, self.survival)
+    }
+
+    // "B36/S23" in any case, or the older "23/36" survival/birth form,
+    // Generations rules add the number of states: "B2/S/3" ("B2/S/C3" and "/2/3" work too)
+    // table rules are written as Golly's "MAP" and 86 base64 digits of the 512 bits
+    pub fn parse(text: &str) -> Result<Ruleset, String> {
+        if let Some(map) = text.trim().strip_prefix("MAP") {
+            return Ruleset::parse_map(map);
+        }
+        let bad = || format!("rule must look like B3/S23, got {}", text);
+        let digits = |part: &str| -> Result<u16, String> {
+            part.chars().try_fold(0u16, |mask, c| match c.to_digit(10) {
+                Some(n) if n <= 8 => Ok(mask | 1 << n),
+                _ => Err(bad()),
+            })
+        };
+        let parts: Vec<&str> = text.trim().split('/').collect();
+        let (first, second, states) = match parts.as_slice() {
+            [first, second] => (*first, *second, 2),
+            [first, second, states] => {
+                let states = states.trim_start_matches(['C', 'c', 'G', 'g']);
+                match states.parse::<u8>() {
+                    Ok(n) if n >= 2 => (*first, *second, n),
+                    _ => return Err(format!("a rule needs 2 to 255 states, got {}", states)),
+                }
+            }
+            _ => return Err(bad()),
+        };
+        let strip = |part: &str, letter: char| {
+            part.strip_prefix(letter)
+                .or_else(|| part.strip_prefix(letter.to_ascii_lowercase()))
+                .map(str::to_string)
+        };
+        let rule = match (strip(first, 'B'), strip(second, 'S')) {
+            (Some(birth), Some(survival)) => Ruleset {
+                birth: digits(&birth)?,
+                survival: digits(&survival)?,
+                states,
+                table: None,
+            },
+            (None, None) => Ruleset {
+                birth: digits(second)?,
+                survival: digits(first)?,
+                states,
+                table: None,
+            },
+            _ => return Err(bad()),
+        };
+        // the simulation pauses on an empty board, which B0 would fill
+        if rule.birth & 1 != 0 {
+            return Err("rules with B0 are not supported".to_string());
+        }
+        Ok(rule)
+    }
+
+    fn parse_map(digits: &str) -> Result<Ruleset, String> {
+        let bad = || "a MAP rule needs 86 base64 digits".to_string();
+        let digits = digits.trim_end_matches('=');
+        if digits.len() != 86 {
+            return Err(bad());
+        }
+        let mut bits = Vec::with_capacity(516);
+        for c in digits.bytes() {
+            let v = BASE64.iter().position(|&d| d == c).ok_or_else(bad)?;
+            bits.extend((0..6).rev().map(|i| v >> i & 1 != 0));
+        }
+        let rule = Ruleset::from_table(|cells| bits[golly_index(cells)]);
+        if rule.next_block(0) {
+            return Err("rules with B0 are not supported".to_string());
+        }
+        Ok(rule)
+    }
+
+    // every preset, in the order G cycles through them
+    pub fn presets() -> impl Iterator<Item = Ruleset> {
+        PRESETS
+            .iter()
+            .filter_map(|(_, rule)| Ruleset::parse(rule).ok())
+    }
+
+    // the preset called `name`, any case and spacing ("day&night")
+    pub fn named(name: &str) -> Option<Ruleset> {
+        let bare = |name: &str| name.replace(' ', "").to_lowercase();
+        PRESETS
+            .iter()
+            .find(|(preset, _)| bare(preset) == bare(name))
+            .and_then(|(_, rule)| Ruleset::parse(rule).ok())
+    }
+
+    pub fn name(&self) -> Option<&'static str> {
+        PRESETS
+            .iter()
+            .find(|(_, rule)| Ruleset::parse(rule).ok() == Some(*self))
+            .map(|(name, _)| *name)
+    }
+
+    // HUD tag, empty for plain Life
+    pub fn label(&self) -> String {
+        if *self == Ruleset::default() {
+            return String::new();
+        }
+        match self.name() {
+            Some(name) => format!("  [{}]", name),
+            None if self.table.is_some() => "  [MAP rule]".to_string(),
+            None => format!("  [{}]", self),
+        }
+    }
+}
+
+// a rule loaded from a plugin, drawn in its own colors
+#[derive(Clone)]
+pub struct PluginRule {
+    pub name: String,
+    pub rule: Ruleset,
+    pub alive: Color,
+    pub empty: Color,
+}
+
+// rules from plugins/, G cycles through them after the presets
+#[derive(Resource, Default)]
+pub struct PluginRules(pub Vec<PluginRule>);
+
+impl PluginRules {
+    pub fn find(&self, rule: &Ruleset) -> Option<&PluginRule> {
+        self.0.iter().find(|plugin| plugin.rule == *rule)
+    }
+
+    // the presets, then the plugin rules, in the order G goes through them
+    pub fn all(&self) -> Vec<(&str, Ruleset)> {
+        PRESETS
+            .iter()
+            .filter_map(|&(name, rule)| Some((name, Ruleset::parse(rule).ok()?)))
+            .chain(
+                self.0
+                    .iter()
+                    .map(|plugin| (plugin.name.as_str(), plugin.rule)),
+            )
+            .collect()
+    }
+
+    // the rule G switches to from `current`: the next preset, then the plugin rules
+    pub fn after(&self, current: &Ruleset) -> (String, Ruleset) {
+        let rules = self.all();
+        let current = rules.iter().position(|(_, rule)| rule == current);
+        let (name, next) = rules[current.map_or(0, |i| (i + 1) % rules.len())];
+        (name.to_string(), next)
+    }
+
+    // Ruleset::label, plugin rules go by their name
+    pub fn label(&self, rule: &Ruleset) -> String {
+        match self.find(rule) {
+            Some(plugin) => format!("  [{}]", plugin.name),
+            None => rule.label(),
+        }
+    }
+
+    // the label without its brackets, plain Life has none
+    pub fn name(&self, rule: &Ruleset) -> String {
+        let label = self.label(rule);
+        match label.trim().trim_matches(['[', ']']) {
+            "" => "Life".to_string(),
+            name => name.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Ruleset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = |mask: u16| -> String {
+            (0..=8)
+                .filter(|n| mask & (1 << n) != 0)
+                .map(|n| char::from(b'0' + n as u8))
+                .collect()
+        };
+        if self.table.is_some() {
+            let mut bits = [false; 516];
+            for cells in 0..512 {
+                bits[golly_index(cells)] = self.next_block(cells);
+            }
+            let digits: String = bits
+                .chunks(6)
+                .map(|six| six.iter().fold(0, |v, &bit| v << 1 | bit as usize))
+                .map(|v| char::from(BASE64[v]))
+                .collect();
+            return write!(f, "MAP{}", digits);
+        }
+        write!(f, "B{}/S{}", digits(self.birth), digits(self.survival))?;
+        if self.states > 2 {
+            write!(f, "/{}", self.states)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct RulePlugin;
+
+impl Plugin for RulePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Ruleset>()
+            .init_resource::<PluginRules>()
+            .add_system(handle_keyboard);
+    }
+}
+
+// G -> next preset rule, then the plugin rules
+fn handle_keyboard(key: Keys, plugins: Res<PluginRules>, mut ruleset: ResMut<Ruleset>) {
+    if key.just_pressed(|k| &k.next_rule) {
+        let (name, next) = plugins.after(&ruleset);
+        *ruleset = next;
+        info!("rule {} ({})", next, name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_notations() {
+        let life = Ruleset::default();
+        assert_eq!(Ruleset::parse("B3/S23"), Ok(life));
+        assert_eq!(Ruleset::parse(" b3/s23 "), Ok(life));
+        assert_eq!(Ruleset::parse("23/3"), Ok(life));
+        let highlife = Ruleset::parse("B36/S23").unwrap();
+        assert_eq!(highlife.masks(), (1 << 3 | 1 << 6, 1 << 2 | 1 << 3));
+        assert_eq!(highlife.decay_states(), 0);
+        let seeds = Ruleset::parse("B2/S").unwrap();
+        assert_eq!(seeds.masks(), (1 << 2, 0));
+    }
+
+    #[test]
+    fn parses_generations() {
+        let brain = Ruleset::parse("B2/S/3").unwrap();
+        assert_eq!(brain.decay_states(), 1);
+        assert_eq!(Ruleset::parse("B2/S/C3"), Ok(brain));
+        assert_eq!(Ruleset::parse("/2/3"), Ok(brain));
+        assert_eq!(brain.to_string(), "B2/S/3");
+    }
+
+    #[test]
+    fn rejects_bad_rules() {
+        for text in [
+            "",
+            "B3",
+            "B9/S23",
+            "B3/S2x",
+            "S23/B3",
+            "B3/S23/1",
+            "B3/S23/256",
+        ] {
+            assert!(Ruleset::parse(text).is_err(), "{}", text);
+        }
+        assert_eq!(
+            Ruleset::parse("B03/S23"),
+            Err("rules with B0 are not supported".to_string())
+        );
+    }
+
+    #[test]
+    fn writes_what_it_reads() {
+        for rule in Ruleset::presets() {
+            assert_eq!(Ruleset::parse(&rule.to_string()), Ok(rule));
+        }
+        let table = Ruleset::from_table(|cells| {
+            let neighbours = (cells & !(1 << 4)).count_ones();
+            neighbours == 3 || (cells & 1 << 4 != 0 && neighbours == 2)
+        });
+        let map = table.to_string();
+        assert!(map.starts_with("MAP"));
+        assert_eq!(Ruleset::parse(&map), Ok(table));
+        for cells in 0..512 {
+            assert_eq!(
+                table.next_block(cells),
+                Ruleset::default().next_block(cells)
+            );
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::ui::{FocusPolicy, UiSystem};
+
+use crate::menu::Screen;
+use crate::pattern::Placer;
+use crate::rle::{self, Pattern};
+use crate::rule::Ruleset;
+use crate::sim::{BoardView, ClearBoard, Keys, RandomizeBoard};
+
+const BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
+const HOVERED: Color = Color::rgb(0.3, 0.45, 0.25);
+const FONT_SIZE: f32 = 16.0;
+const WIDTH: f32 = 360.0;
+
+// what a "load example" button puts on the board
+pub enum Example {
+    Pattern(&'static str, &'static str), // name and RLE, centered on the board
+    Soup(f64),                           // a random board of this density
+}
+
+impl Example {
+    fn label(&self) -> String {
+        match self {
+            Example::Pattern(name, _) => format!("Load {}", name),
+            Example::Soup(density) => format!("Load a {:.0}% soup", density * 100.0),
+        }
+    }
+}
+
+pub struct RuleDoc {
+    pub rule: &'static str, // as Ruleset::parse takes it, the name comes from the presets
+    pub about: &'static str,
+    pub behaviors: &'static str,
+    pub examples: [Example; 2],
+}
+
+// one per preset, in the same order
+pub const DOCS: [RuleDoc; 8] = [
+    RuleDoc {
+        rule: "B3/S23",
+        about: "Conway's Game of Life. A cell is born with 3 neighbours and survives with 2 or 3.",
+        behaviors: "Soups settle into blinkers, blocks and beehives, with gliders flying off.",
+        examples: [
+            Example::Pattern("glider", "x = 3, y = 3\nbo$2bo$3o!"),
+            Example::Pattern("R-pentomino", "x = 3, y = 3\nb2o$2o$bo!"),
+        ],
+    },
+    RuleDoc {
+        rule: "B36/S23",
+        about: "Life, with births on 6 neighbours too.",
+        behaviors: "Looks like Life on soups, but has a small replicator that copies itself.",
+        examples: [
+            Example::Pattern("replicator", "x = 5, y = 5\n2b3o$bo2bo$o3bo$o2bo$3o!"),
+            Example::Soup(0.3),
+        ],
+    },
+    RuleDoc {
+        rule: "B3678/S34678",
+        about: "Day & Night. A pattern with live and dead cells swapped behaves the same.",
+        behaviors: "Soups split into live and dead areas, the borders between them keep shifting.",
+        examples: [
+            Example::Soup(0.5),
+            Example::Pattern("blob", "x = 5, y = 5\nb3o$5o$5o$5o$b3o!"),
+        ],
+    },
+    RuleDoc {
+        rule: "B2/S",
+        about: "Seeds. Every live cell dies, cells with exactly 2 live neighbours are born.",
+        behaviors: "Almost anything explodes, small seeds fly off as spaceships.",
+        examples: [
+            Example::Pattern("domino", "x = 2, y = 1\n2o!"),
+            Example::Pattern("block", "x = 2, y = 2\n2o$2o!"),
+        ],
+    },
+    RuleDoc {
+        rule: "B3/S012345678",
+        about: "Life without Death. Births are Life's, but live cells never die.",
+        behaviors: "Patterns only grow, often into ladders that keep extending.",
+        examples: [
+            Example::Pattern("R-pentomino", "x = 3, y = 3\nb2o$2o$bo!"),
+            Example::Soup(0.05),
+        ],
+    },
+    RuleDoc {
+        rule: "B3/S12345",
+        about: "Maze. Births are Life's, live cells survive with 1 to 5 neighbours.",
+        behaviors: "Patterns grow into corridors that look like a maze.",
+        examples: [
+            Example::Pattern("R-pentomino", "x = 3, y = 3\nb2o$2o$bo!"),
+            Example::Soup(0.1),
+        ],
+    },
+    RuleDoc {
+        rule: "B2/S/3",
+        about: "Brian's Brain. Live cells always die, through a dying state that can't be born on.",
+        behaviors: "Never settles, soups fill up with small spaceships.",
+        examples: [
+            Example::Soup(0.2),
+            Example::Pattern("block", "x = 2, y = 2\n2o$2o!"),
+        ],
+    },
+    RuleDoc {
+        rule: "B2/S345/4",
+        about: "Star Wars. Births on 2, survival on 3 to 5, and two dying states.",
+        behaviors: "Soups keep churning and send out many kinds of spaceships.",
+        examples: [
+            Example::Soup(0.3),
+            Example::Pattern("block", "x = 2, y = 2\n2o$2o!"),
+        ],
+    },
+];
+
+// the doc on screen, not necessarily the rule in use
+#[derive(Resource, Default)]
+struct Rulebook {
+    show: bool,
+    page: usize,
+}
+
+#[derive(Component)]
+struct RulebookPanel;
+
+#[derive(Component, Clone, Copy)]
+enum DocText {
+    Title,
+    About,
+    Behaviors,
+    Example(usize), // the label of an example button
+}
+
+#[derive(Component, Clone, Copy)]
+enum DocButton {
+    Previous,
+    Next,
+    Use,
+    Example(usize),
+}
+
+// what the buttons act on
+#[derive(SystemParam)]
+struct DocActions<'w, 's> {
+    rule: ResMut<'w, Ruleset>,
+    view: BoardView<'w, 's>,
+    clears: EventWriter<'w, 's, ClearBoard>,
+    placer: Placer<'w, 's>,
+    randomizes: EventWriter<'w, 's, RandomizeBoard>,
+}
+
+impl DocActions<'_, '_> {
+    fn run(&mut self, button: DocButton, book: &mut Rulebook) {
+        let doc = &DOCS[book.page];
+        match button {
+            DocButton::Previous => book.page = (book.page + DOCS.len() - 1) % DOCS.len(),
+            DocButton::Next => book.page = (book.page + 1) % DOCS.len(),
+            DocButton::Use => self.use_rule(doc),
+            DocButton::Example(i) => {
+                self.use_rule(doc);
+                match doc.examples[i] {
+                    Example::Pattern(name, text) => self.place(name, text),
+                    Example::Soup(density) => self.randomizes.send(RandomizeBoard { density }),
+                }
+            }
+        }
+    }
+
+    fn use_rule(&mut self, doc: &RuleDoc) {
+        match Ruleset::parse(doc.rule) {
+            Ok(rule) if rule != *self.rule => *self.rule = rule,
+            Ok(_) => {}
+            Err(e) => warn!("rule {}: {}", doc.rule, e),
+        }
+    }
+
+    // like O with the cursor off the board, on an empty board
+    fn place(&mut self, name: &str, text: &str) {
+        let pattern: Pattern = match rle::parse(text) {
+            Ok(pattern) => pattern,
+            Err(e) => return warn!("example {}: {}", name, e),
+        };
+        let Some(screen) = self.view.screen() else {
+            return;
+        };
+        self.clears.send(ClearBoard);
+        self.placer.place(&pattern, screen.board_size() / 2);
+    }
+}
+
+pub struct RulebookPlugin;
+
+impl Plugin for RulebookPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rulebook>()
+            .add_startup_system(spawn_panel)
+            .add_system(handle_keyboard)
+            // clicks on the panel are taken before the board's systems get to see them
+            .add_system_to_stage(CoreStage::PreUpdate, handle_input.after(UiSystem::Focus))
+            .add_system(update_panel);
+    }
+}
+
+fn spawn_panel(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("Kid Marker.ttf");
+    let text = |size: f32, color: Color| {
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: font.clone(),
+                font_size: size,
+                color,
+            },
+        )
+        .with_style(Style {
+            max_size: Size::new(Val::Px(WIDTH), Val::Undefined),
+            margin: UiRect::all(Val::Px(2.0)),
+            ..default()
+        })
+    };
+    let button = |parent: &mut ChildBuilder, button: DocButton, width: Val, label: Option<&str>| {
+        parent
+            .spawn((
+                ButtonBundle {
+                    style: Style {
+                        size: Size::new(width, Val::Px(FONT_SIZE + 8.0)),
+                        margin: UiRect::all(Val::Px(2.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: BUTTON.into(),
+                    ..default()
+                },
+                button,
+            ))
+            .with_children(|inner| {
+                let mut label_text = text(FONT_SIZE, Color::WHITE);
+                label_text.text.sections[0].value = label.unwrap_or_default().to_string();
+                match button {
+                    DocButton::Example(i) => inner.spawn((label_text, DocText::Example(i))),
+                    _ => inner.spawn(label_text),
+                };
+            });
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(310.0),
+                        top: Val::Px(200.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(6.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.75).into(),
+                visibility: Visibility { is_visible: false },
+                ..default()
+            },
+            Interaction::default(),
+            FocusPolicy::Block,
+            RulebookPanel,
+        ))
+        .with_children(|panel| {
+            panel
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    button(row, DocButton::Previous, Val::Px(30.0), Some("<"));
+                    row.spawn((text(FONT_SIZE + 4.0, Color::YELLOW), DocText::Title));
+                    button(row, DocButton::Next, Val::Px(30.0), Some(">"));
+                });
+            panel.spawn((text(FONT_SIZE, Color::WHITE), DocText::About));
+            panel.spawn((text(FONT_SIZE, Color::GRAY), DocText::Behaviors));
+            button(panel, DocButton::Use, Val::Px(WIDTH), Some("Use this rule"));
+            for i in 0..2 {
+                button(panel, DocButton::Example(i), Val::Px(WIDTH), None);
+            }
+        });
+}
+
+// F8 -> show/hide the notes, opened on the rule in use
+fn handle_keyboard(key: Keys, rule: Res<Ruleset>, mut book: ResMut<Rulebook>) {
+    if !key.just_pressed(|k| &k.rulebook) {
+        return;
+    }
+    book.show = !book.show;
+    let current = DOCS
+        .iter()
+        .position(|doc| Ruleset::parse(doc.rule).ok() == Some(*rule));
+    if let Some(page) = current.filter(|_| book.show) {
+        book.page = page;
+    }
+}
+
+fn handle_input(
+    screen: Res<State<Screen>>,
+    mut mouse: ResMut<Input<MouseButton>>,
+    mut book: ResMut<Rulebook>,
+    buttons: Query<(&Interaction, &DocButton), Changed<Interaction>>,
+    panels: Query<&Interaction, With<RulebookPanel>>,
+    mut actions: DocActions,
+) {
+    if !book.show || *screen.current() != Screen::Board {
+        return;
+    }
+    for (interaction, &button) in buttons.iter() {
+        if *interaction == Interaction::Clicked {
+            actions.run(button, &mut book);
+        }
+    }
+    if panels
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+    {
+        mouse.clear();
+    }
+}
+
+type DocButtons<'a> = (&'a Interaction, &'a mut BackgroundColor);
+
+// hidden on the menus, the texts follow the page
+fn update_panel(
+    book: Res<Rulebook>,
+    screen: Res<State<Screen>>,
+    mut panels: Query<&mut Visibility, With<RulebookPanel>>,
+    mut texts: Query<(&DocText, &mut Text)>,
+    mut buttons: Query<DocButtons, (Changed<Interaction>, With<DocButton>)>,
+) {
+    let show = book.show && *screen.current() == Screen::Board;
+    for mut visibility in panels.iter_mut() {
+        if visibility.is_visible != show {
+            visibility.is_visible = show;
+        }
+    }
+    for (interaction, mut color) in buttons.iter_mut() {
+        *color = match interaction {
+            Interaction::None => BUTTON.into(),
+            _ => HOVERED.into(),
+        };
+    }
+    if !book.is_changed() {
+        return;
+    }
+    let doc = &DOCS[book.page];
+    let name = Ruleset::parse(doc.rule)
+        .ok()
+        .and_then(|rule| rule.name())
+        .unwrap_or(doc.rule);
+    for (&part, mut text) in texts.iter_mut() {
+        text.sections[0].value = match part {
+            DocText::Title => format!("  {}  {}  ", name, doc.rule),
+            DocText::About => doc.about.to_string(),
+            DocText::Behaviors => doc.behaviors.to_string(),
+            DocText::Example(i) => doc.examples[i].label(),
+        };
+    }
+}
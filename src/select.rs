@@ -0,0 +1,379 @@
+use bevy::prelude::*;
+
+use crate::board::{Board, Region};
+use crate::pattern::{self, Aim, Placer, Stamped};
+use crate::rle::Pattern;
+use crate::sim::{BoardClick, BoardView, CellColors, Keys, SetCell, Tool, SIZE};
+use crate::toast::Toast;
+
+const SELECTION_COLOR: Color = Color::rgba(0.3, 0.6, 1.0, 0.25);
+// the ghost is the live color, faded like the library's
+const GHOST_ALPHA: f32 = 0.4;
+// empty cells between tiled copies, PageUp / PageDown change it
+const MAX_TILE_GAP: u32 = 16;
+
+// the rectangle picked with the select tool, and what was last copied out of it
+#[derive(Resource, Default)]
+pub struct Selection {
+    drag: Option<(UVec2, UVec2)>, // from where the button went down to the cell under the cursor
+    region: Option<Region>,
+    clipboard: Option<Pattern>,
+    board: UVec2, // the size of the board the region was picked on
+    tile_gap: u32,
+}
+
+impl Selection {
+    // the picked rectangle, cut down to a grid that has shrunk under it since
+    pub fn region(&self, map: &Board) -> Option<Region> {
+        self.region.and_then(|r| r.clamped(map.size()))
+    }
+}
+
+// tiles a pattern over the selection, or the whole board without one, for agars and test grids
+pub struct TileFill(pub Pattern);
+
+#[derive(Component)]
+struct SelectionOverlay;
+
+#[derive(Component)]
+struct GhostCell;
+
+// sprites kept around between pastes, the ones the clipboard doesn't need are hidden
+#[derive(Resource, Default)]
+struct Ghost(Vec<Entity>);
+
+pub struct SelectPlugin;
+
+impl Plugin for SelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Selection>()
+            .init_resource::<Ghost>()
+            .add_event::<TileFill>()
+            .add_startup_system(spawn_overlay)
+            .add_system(handle_keyboard)
+            .add_system(handle_mouse.after(handle_keyboard))
+            .add_system(handle_tile_keys)
+            .add_system(fill_tiles.after(handle_tile_keys))
+            .add_system_to_stage(CoreStage::PostUpdate, forget_on_resize)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                draw_selection.after(forget_on_resize),
+            )
+            .add_system_to_stage(CoreStage::PostUpdate, draw_ghost);
+    }
+}
+
+fn spawn_overlay(mut commands: Commands) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: SELECTION_COLOR,
+                ..default()
+            },
+            visibility: Visibility { is_visible: false },
+            ..default()
+        },
+        SelectionOverlay,
+    ));
+}
+
+// F9       -> select regions, pressed again back to drawing
+// Ctrl + C -> copy the live cells of the selection
+// Ctrl + X -> copy them and empty the selection
+// Ctrl + V -> paste what was copied last, see handle_mouse
+// while pasting . and , aim the clipboard, see pattern::orient
+fn handle_keyboard(
+    key: Keys,
+    map: Res<Board>,
+    mut click: BoardClick,
+    mut tool: ResMut<Tool>,
+    mut selection: ResMut<Selection>,
+    mut sets: EventWriter<SetCell>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if key.just_pressed(|k| &k.select) {
+        *tool = match *tool {
+            Tool::Select | Tool::Paste => Tool::Draw,
+            _ => Tool::Select,
+        };
+    }
+    if *tool == Tool::Paste {
+        if let Some(clipboard) = selection.clipboard.as_mut() {
+            pattern::orient(&key, clipboard);
+        }
+    }
+    if !key.any_pressed([KeyCode::LControl, KeyCode::RControl]) {
+        return;
+    }
+    if key.just_pressed(|k| &k.paste) {
+        match selection.clipboard {
+            Some(_) => *tool = Tool::Paste,
+            None => toasts.send(Toast(
+                "nothing to paste, copy a selection first".to_string(),
+            )),
+        }
+        return;
+    }
+    let cut = key.just_pressed(|k| &k.cut);
+    if !(cut || key.just_pressed(|k| &k.copy)) || *tool != Tool::Select {
+        return;
+    }
+    let Some(region) = selection.region(&map) else {
+        toasts.send(Toast(
+            "nothing selected, drag a rectangle with F9 first".to_string(),
+        ));
+        return;
+    };
+    let copied = pattern::board_pattern(&map, (region.min, region.max));
+    if cut && !click.refuses() {
+        for cell in copied.alive.iter() {
+            sets.send(SetCell {
+                x: region.min.x + cell.x,
+                y: region.min.y + cell.y,
+                alive: false,
+            });
+        }
+    }
+    info!("copied {} cells", copied.alive.len());
+    selection.clipboard = Some(copied);
+}
+
+// selecting: left drag marks the selection, right click drops it
+// pasting: a click stamps the clipboard centered on the cell under the cursor, like the library,
+// and a right click goes back to selecting
+fn handle_mouse(
+    mut click: BoardClick,
+    aim: Aim,
+    map: Res<Board>,
+    mut tool: ResMut<Tool>,
+    mut selection: ResMut<Selection>,
+    mut placer: Placer,
+    mut stamps: EventWriter<Stamped>,
+) {
+    if *tool != Tool::Select {
+        selection.drag = None;
+    }
+    let Some(screen) = aim.screen() else {
+        return;
+    };
+    let cell = screen.cursor_to_cell();
+    if *tool == Tool::Paste {
+        if click.mouse.just_pressed(MouseButton::Right) {
+            *tool = Tool::Select;
+            return;
+        }
+        let Some(clipboard) = &selection.clipboard else {
+            return;
+        };
+        let Some(cell) = aim.center(clipboard) else {
+            return;
+        };
+        if click.edits() && placer.place(clipboard, cell) {
+            stamps.send(Stamped("selection".to_string()));
+        }
+        return;
+    }
+    if *tool != Tool::Select {
+        return;
+    }
+    if click.mouse.just_pressed(MouseButton::Right) {
+        selection.drag = None;
+        selection.region = None;
+    }
+    // selecting doesn't edit the board, so it isn't locked
+    if let Some(cell) = cell.filter(|_| click.mouse.just_pressed(MouseButton::Left)) {
+        selection.drag = Some((cell, cell));
+    }
+    let Some((from, to)) = selection.drag else {
+        return;
+    };
+    // off the board the selection keeps the last corner it had
+    let to = cell.unwrap_or(to);
+    selection.drag = Some((from, to));
+    if click.mouse.just_released(MouseButton::Left) {
+        selection.drag = None;
+        selection.region = Some(Region::new(from, to));
+        selection.board = map.size();
+    }
+}
+
+// a board resized or restored at another size has moved its cells out from under the region
+// End           -> tile the clipboard, see fill_tiles, the library has its own pattern tiled
+//                  while stamping
+// PageUp / Down -> one empty cell more / less between the copies
+fn handle_tile_keys(
+    key: Keys,
+    tool: Res<Tool>,
+    mut selection: ResMut<Selection>,
+    mut fills: EventWriter<TileFill>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let by = match (
+        key.just_pressed(|k| &k.wider_tiles),
+        key.just_pressed(|k| &k.tighter_tiles),
+    ) {
+        (true, false) => Some(selection.tile_gap + 1),
+        (false, true) => Some(selection.tile_gap.saturating_sub(1)),
+        _ => None,
+    };
+    if let Some(next) = by.map(|next| next.min(MAX_TILE_GAP)) {
+        selection.tile_gap = next;
+        toasts.send(Toast(format!("{} empty cells between tiles", next)));
+    }
+    if !key.just_pressed(|k| &k.tile) || *tool == Tool::Stamp {
+        return;
+    }
+    match &selection.clipboard {
+        Some(clipboard) => fills.send(TileFill(clipboard.clone())),
+        None => toasts.send(Toast(
+            "nothing to tile, copy a selection or pick a library pattern first".to_string(),
+        )),
+    }
+}
+
+// whole copies side by side from the bottom left corner, the cells between them emptied, sent
+// as a single stamp
+fn fill_tiles(
+    mut fills: EventReader<TileFill>,
+    mut click: BoardClick,
+    map: Res<Board>,
+    selection: Res<Selection>,
+    mut sets: EventWriter<SetCell>,
+    mut stamps: EventWriter<Stamped>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let Some(TileFill(pattern)) = fills.iter().last() else {
+        return;
+    };
+    if click.refuses() {
+        return;
+    }
+    if pattern.alive.is_empty() {
+        toasts.send(Toast("nothing to tile, the pattern is empty".to_string()));
+        return;
+    }
+    let whole = Region::new(UVec2::ZERO, map.size() - UVec2::ONE);
+    let area = selection
+        .region
+        .filter(|_| selection.board == map.size())
+        .unwrap_or(whole);
+    let step = pattern.size + UVec2::splat(selection.tile_gap);
+    let room = area.max - area.min + UVec2::ONE;
+    if pattern.size.x > room.x || pattern.size.y > room.y {
+        toasts.send(Toast(
+            "the pattern is bigger than the area to tile".to_string(),
+        ));
+        return;
+    }
+    // copies that would not fit whole are left out
+    let copies = (room - pattern.size) / step + UVec2::ONE;
+    let mut alive = vec![false; (room.x * room.y) as usize];
+    for (tx, ty) in (0..copies.x).flat_map(|tx| (0..copies.y).map(move |ty| (tx, ty))) {
+        for cell in pattern.alive.iter() {
+            let pos = UVec2::new(tx, ty) * step + *cell;
+            alive[(pos.y * room.x + pos.x) as usize] = true;
+        }
+    }
+    for (i, &alive) in alive.iter().enumerate() {
+        let pos = area.min + UVec2::new(i as u32 % room.x, i as u32 / room.x);
+        if map.alive(pos.x, pos.y) != alive {
+            sets.send(SetCell {
+                x: pos.x,
+                y: pos.y,
+                alive,
+            });
+        }
+    }
+    stamps.send(Stamped(format!("{} tiles", copies.x * copies.y)));
+}
+
+fn forget_on_resize(map: Res<Board>, mut selection: ResMut<Selection>) {
+    if selection.region.is_some() && selection.board != map.size() {
+        selection.region = None;
+        selection.drag = None;
+    }
+}
+
+// while it is being dragged the new rectangle replaces the current selection
+fn draw_selection(
+    view: BoardView,
+    tool: Res<Tool>,
+    selection: Res<Selection>,
+    mut overlays: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<SelectionOverlay>>,
+) {
+    let dragged = selection.drag.map(|(from, to)| Region::new(from, to));
+    let region = dragged
+        .or(selection.region)
+        .filter(|_| *tool == Tool::Select);
+    for (mut sprite, mut transform, mut visibility) in overlays.iter_mut() {
+        let shown = view
+            .screen()
+            .and_then(|screen| Some((region?.clamped(screen.board_size())?, screen)));
+        if visibility.is_visible != shown.is_some() {
+            visibility.is_visible = shown.is_some();
+        }
+        let Some((region, screen)) = shown else {
+            continue;
+        };
+        let min = screen.cell_to_world(region.min.x as f32, region.min.y as f32);
+        let max = screen.cell_to_world(region.max.x as f32, region.max.y as f32);
+        let cells = (region.max - region.min + UVec2::ONE).as_vec2();
+        sprite.custom_size = Some(cells * screen.cell_size());
+        transform.translation = ((min + max) / 2.0).extend(1.0);
+    }
+}
+
+// follows the cursor while pasting, laid out the same way Placer::place stamps it
+fn draw_ghost(
+    mut commands: Commands,
+    aim: Aim,
+    tool: Res<Tool>,
+    selection: Res<Selection>,
+    colors: Res<CellColors>,
+    mut ghost: ResMut<Ghost>,
+    mut sprites: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<GhostCell>>,
+) {
+    let screen = aim.screen();
+    let cursor = selection.clipboard.as_ref().and_then(|c| aim.center(c));
+    let cells: Vec<IVec2> = match (&selection.clipboard, cursor, &screen) {
+        (Some(clipboard), Some(cursor), Some(screen)) if *tool == Tool::Paste => {
+            let origin = cursor.as_ivec2() - (clipboard.size / 2).as_ivec2();
+            let board = screen.board_size().as_ivec2();
+            // cells past the edges are dropped when pasted, so they aren't shown either
+            clipboard
+                .alive
+                .iter()
+                .map(|cell| origin + cell.as_ivec2())
+                .filter(|p| p.cmpge(IVec2::ZERO).all() && p.cmplt(board).all())
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+    // new sprites are placed next frame, once they exist
+    while ghost.0.len() < cells.len() {
+        let hidden = SpriteBundle {
+            visibility: Visibility { is_visible: false },
+            ..default()
+        };
+        ghost.0.push(commands.spawn((hidden, GhostCell)).id());
+    }
+    let mut color = colors.alive;
+    color.set_a(GHOST_ALPHA);
+    for (i, &entity) in ghost.0.iter().enumerate() {
+        let Ok((mut sprite, mut transform, mut visibility)) = sprites.get_mut(entity) else {
+            continue;
+        };
+        let cell = cells.get(i);
+        if visibility.is_visible != cell.is_some() {
+            visibility.is_visible = cell.is_some();
+        }
+        let (Some(cell), Some(screen)) = (cell, &screen) else {
+            continue;
+        };
+        sprite.color = color;
+        sprite.custom_size = Some(screen.cell_size() * SIZE);
+        transform.translation = screen
+            .cell_to_world(cell.x as f32, cell.y as f32)
+            .extend(0.6);
+    }
+}
@@ -0,0 +1,60 @@
+use std::fs;
+
+// next to the app, what is remembered between sessions
+const SETTINGS_FILE: &str = "settings.txt";
+
+// "key value" lines, in the order they were first set
+// keys nobody asks for are kept, so each part of the app only rewrites its own
+#[derive(Default)]
+pub struct Settings(Vec<(String, String)>);
+
+impl Settings {
+    // a missing or unreadable file is an empty one, nothing was remembered yet
+    pub fn load() -> Self {
+        let text = fs::read_to_string(SETTINGS_FILE).unwrap_or_default();
+        let entries = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+                (key.to_string(), value.trim().to_string())
+            })
+            .collect();
+        Settings(entries)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let mut text = String::from("# bevy_conway settings\n");
+        for (key, value) in self.0.iter() {
+            text.push_str(&format!("{} {}\n", key, value));
+        }
+        fs::write(SETTINGS_FILE, text)
+            .map_err(|e| format!("could not write {}: {}", SETTINGS_FILE, e))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn set(&mut self, key: &str, value: String) {
+        match self.0.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value,
+            None => self.0.push((key.to_string(), value)),
+        }
+    }
+
+    // the entries whose key starts with `prefix`, the rest of the key and the value
+    pub fn with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = (&'a str, &'a str)> {
+        self.0
+            .iter()
+            .filter_map(move |(k, v)| Some((k.strip_prefix(prefix)?, v.as_str())))
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.0.retain(|(k, _)| k != key);
+    }
+}
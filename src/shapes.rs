@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use crate::sim::{line_cells, BoardClick, BoardView, CellColors, Keys, SetCell, Tool, SIZE};
+
+// the preview is the live color, faded like the library's ghost
+const PREVIEW_ALPHA: f32 = 0.4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShapeKind {
+    #[default]
+    Line,
+    Rectangle,
+    FilledRectangle,
+    Ellipse,
+}
+
+impl ShapeKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ShapeKind::Line => " line",
+            ShapeKind::Rectangle => " rectangle",
+            ShapeKind::FilledRectangle => " filled rectangle",
+            ShapeKind::Ellipse => " ellipse",
+        }
+    }
+
+    // every cell of the shape dragged from `from` to `to`, both corners included
+    pub fn cells(&self, from: UVec2, to: UVec2) -> Vec<UVec2> {
+        let (min, max) = (from.min(to), from.max(to));
+        let box_cells = || (min.x..=max.x).flat_map(move |x| (min.y..=max.y).map(move |y| (x, y)));
+        match self {
+            ShapeKind::Line => line_cells(from, to),
+            ShapeKind::FilledRectangle => box_cells().map(|(x, y)| UVec2::new(x, y)).collect(),
+            ShapeKind::Rectangle => box_cells()
+                .filter(|&(x, y)| x == min.x || x == max.x || y == min.y || y == max.y)
+                .map(|(x, y)| UVec2::new(x, y))
+                .collect(),
+            ShapeKind::Ellipse => ellipse(min, max),
+        }
+    }
+}
+
+// the outline of the ellipse that fits the box, walked along both axes so steep parts have no gaps
+fn ellipse(min: UVec2, max: UVec2) -> Vec<UVec2> {
+    let center = (min + max).as_vec2() / 2.0;
+    let radii = (max - min).as_vec2() / 2.0;
+    let mut cells = HashSet::new();
+    // the other coordinate on the outline, on both sides of the center
+    let along = |t: f32, radius: f32, other: f32| {
+        let s = if radius > 0.0 { t / radius } else { 0.0 };
+        let offset = other * (1.0 - s * s).max(0.0).sqrt();
+        [-offset, offset]
+    };
+    for x in min.x..=max.x {
+        for dy in along(x as f32 - center.x, radii.x, radii.y) {
+            cells.insert(UVec2::new(x, (center.y + dy).round() as u32));
+        }
+    }
+    for y in min.y..=max.y {
+        for dx in along(y as f32 - center.y, radii.y, radii.x) {
+            cells.insert(UVec2::new((center.x + dx).round() as u32, y));
+        }
+    }
+    cells.into_iter().collect()
+}
+
+// the shape being dragged, from the cell the button went down on to the one under the cursor
+#[derive(Resource, Default)]
+pub struct Shaping {
+    pub kind: ShapeKind,
+    drag: Option<(UVec2, UVec2)>,
+}
+
+#[derive(Component)]
+struct PreviewCell;
+
+// sprites kept around between drags, the ones the shape doesn't need are hidden
+#[derive(Resource, Default)]
+struct Preview(Vec<Entity>);
+
+pub struct ShapesPlugin;
+
+impl Plugin for ShapesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Shaping>()
+            .init_resource::<Preview>()
+            .add_system(handle_keyboard)
+            .add_system(handle_mouse.after(handle_keyboard))
+            .add_system_to_stage(CoreStage::PostUpdate, draw_preview);
+    }
+}
+
+// F7 -> line, rectangle, filled rectangle, ellipse, then back to drawing
+fn handle_keyboard(key: Keys, mut tool: ResMut<Tool>, mut shaping: ResMut<Shaping>) {
+    if !key.just_pressed(|k| &k.shapes) {
+        return;
+    }
+    shaping.drag = None;
+    if *tool != Tool::Shape {
+        *tool = Tool::Shape;
+        shaping.kind = ShapeKind::Line;
+        return;
+    }
+    shaping.kind = match shaping.kind {
+        ShapeKind::Line => ShapeKind::Rectangle,
+        ShapeKind::Rectangle => ShapeKind::FilledRectangle,
+        ShapeKind::FilledRectangle => ShapeKind::Ellipse,
+        ShapeKind::Ellipse => {
+            *tool = Tool::Draw;
+            ShapeKind::Line
+        }
+    };
+}
+
+// the shape is only put on the board when the button is let go, a right click drops it
+fn handle_mouse(
+    mut click: BoardClick,
+    view: BoardView,
+    tool: Res<Tool>,
+    mut shaping: ResMut<Shaping>,
+    mut sets: EventWriter<SetCell>,
+) {
+    if *tool != Tool::Shape {
+        shaping.drag = None;
+        return;
+    }
+    let Some(screen) = view.screen() else {
+        return;
+    };
+    let cell = screen.cursor_to_cell();
+    if click.mouse.just_pressed(MouseButton::Right) {
+        shaping.drag = None;
+    }
+    if let Some(cell) = cell {
+        if click.edits() {
+            shaping.drag = Some((cell, cell));
+        }
+    }
+    let Some((from, to)) = shaping.drag else {
+        return;
+    };
+    // off the board the shape keeps the last corner it had
+    let to = cell.unwrap_or(to);
+    shaping.drag = Some((from, to));
+    if !click.mouse.just_released(MouseButton::Left) {
+        return;
+    }
+    shaping.drag = None;
+    for cell in shaping.kind.cells(from, to) {
+        sets.send(SetCell {
+            x: cell.x,
+            y: cell.y,
+            alive: true,
+        });
+    }
+}
+
+fn draw_preview(
+    mut commands: Commands,
+    view: BoardView,
+    shaping: Res<Shaping>,
+    colors: Res<CellColors>,
+    mut preview: ResMut<Preview>,
+    mut sprites: Query<(&mut Sprite, &mut Transform, &mut Visibility), With<PreviewCell>>,
+) {
+    let cells = match (shaping.drag, view.screen()) {
+        (Some((from, to)), Some(_)) => shaping.kind.cells(from, to),
+        _ => Vec::new(),
+    };
+    // new sprites are placed next frame, once they exist
+    while preview.0.len() < cells.len() {
+        let hidden = SpriteBundle {
+            visibility: Visibility { is_visible: false },
+            ..default()
+        };
+        preview.0.push(commands.spawn((hidden, PreviewCell)).id());
+    }
+    let mut color = colors.alive;
+    color.set_a(PREVIEW_ALPHA);
+    for (i, &entity) in preview.0.iter().enumerate() {
+        let Ok((mut sprite, mut transform, mut visibility)) = sprites.get_mut(entity) else {
+            continue;
+        };
+        let cell = cells.get(i);
+        if visibility.is_visible != cell.is_some() {
+            visibility.is_visible = cell.is_some();
+        }
+        let (Some(cell), Some(screen)) = (cell, view.screen()) else {
+            continue;
+        };
+        sprite.color = color;
+        sprite.custom_size = Some(screen.cell_size() * SIZE);
+        transform.translation = screen
+            .cell_to_world(cell.x as f32, cell.y as f32)
+            .extend(0.6);
+    }
+}
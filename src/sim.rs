@@ -1,20 +1,308 @@
-use bevy::time::FixedTimestep;
-use bevy::{prelude::*};
-use rand::Rng;
+use bevy::ecs::schedule::ShouldRun;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet, Instant};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
-const GRID_WIDTH: u32 = 25;
-const GRID_HEIGHT: u32 = 25;
-const SPACE_TOP: u32 = 2;
-const LIFEFORM_COLOR: Color = Color::rgb(0.1, 0.1, 0.1);
-const EMPTY_COLOR: Color = Color::rgb(1.0, 1.0, 1.0);
+use crate::board::{Board, Boundary, FrozenRegions, SimBackend};
+use crate::brush::Brush;
+use crate::command::KeyMacro;
+use crate::density::DensityMap;
+use crate::drift::Drift;
+use crate::edge::EdgeAlarm;
+use crate::hashlife::HashLife;
+use crate::hud::HudElement;
+use crate::rle::Pattern;
+use crate::rule::{PluginRules, Ruleset};
+use crate::shapes::Shaping;
+use crate::speed::{generation_due, Governor, Speed};
+use crate::spray::Spray;
+use crate::stress::Stress;
+use crate::toast::Toast;
+use crate::trace::TraceBackend;
+use crate::worker::StepWorker;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+pub const MIN_GRID: u32 = 5;
+pub const MAX_GRID: u32 = 200;
+// empty cells kept around the live ones when the board is cropped
+const CROP_MARGIN: u32 = 5;
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 enum Element {
-    Lifeform,
+    Lifeform(u8), // shade of its age, see age_shade
+    Dying(u8),    // decay state of a Generations rule, starting at 1
     Empty,
 }
 
-const SIZE: f32 = 0.95; // it gives some padding
+// live cells get a new shade each time their age doubles, the last one is reached at 1023
+const AGE_SHADES: u8 = 10;
+
+fn age_shade(age: u32) -> u8 {
+    (age.saturating_add(1).ilog2() as u8).min(AGE_SHADES)
+}
+
+impl Element {
+    fn at(map: &Board, x: u32, y: u32) -> Element {
+        match (map.alive(x, y), map.dying(x, y)) {
+            (true, _) => Element::Lifeform(age_shade(map.age(x, y))),
+            (false, 0) => Element::Empty,
+            (false, k) => Element::Dying(k),
+        }
+    }
+}
+
+// what the cell sprites are painted with
+#[derive(Resource, Clone, PartialEq)]
+pub struct CellColors {
+    pub alive: Color,
+    pub empty: Color,
+    pub dying: Color, // a dying cell starts here and fades to `empty` as it decays
+    // live cells go through these as they age, newborn first, all of them are `alive` when empty
+    pub age_gradient: Vec<Color>,
+}
+
+impl Default for CellColors {
+    fn default() -> Self {
+        CellColors {
+            alive: Color::rgb(0.1, 0.1, 0.1),
+            empty: Color::rgb(1.0, 1.0, 1.0),
+            dying: Color::rgb(0.2, 0.4, 0.9),
+            // newborn green to deep blue for still lifes that have been there for ages
+            age_gradient: vec![
+                Color::rgb(0.45, 0.8, 0.25),
+                Color::rgb(0.1, 0.55, 0.55),
+                Color::rgb(0.05, 0.1, 0.45),
+            ],
+        }
+    }
+}
+
+impl CellColors {
+    fn of(&self, element: Element, decay_states: u8) -> Color {
+        match element {
+            Element::Lifeform(shade) => {
+                let Some(last) = self.age_gradient.len().checked_sub(1) else {
+                    return self.alive;
+                };
+                let t = shade as f32 / AGE_SHADES as f32 * last as f32;
+                let i = (t as usize).min(last.saturating_sub(1));
+                let from = Vec4::from(self.age_gradient[i]);
+                let to = Vec4::from(self.age_gradient[(i + 1).min(last)]);
+                from.lerp(to, t - i as f32).into()
+            }
+            Element::Dying(k) => {
+                let t = k as f32 / (decay_states as f32 + 1.0);
+                let (from, to) = (Vec4::from(self.dying), Vec4::from(self.empty));
+                from.lerp(to, t).into()
+            }
+            Element::Empty => self.empty,
+        }
+    }
+
+    // the states of a rule with `decay_states` dying ones and the color each is painted with,
+    // live cells as newborns
+    pub fn states(&self, decay_states: u8) -> Vec<(String, Color)> {
+        let dying = (1..=decay_states).map(|k| {
+            let name = match decay_states {
+                1 => "dying".to_string(),
+                _ => format!("dying {}", k),
+            };
+            (name, self.of(Element::Dying(k), decay_states))
+        });
+        let alive = self.of(Element::Lifeform(0), decay_states);
+        std::iter::once(("alive".to_string(), alive))
+            .chain(dying)
+            .chain(std::iter::once(("dead".to_string(), self.empty)))
+            .collect()
+    }
+}
+
+// the keys of every control, any key of a list does it, a control used with Shift, Ctrl or Alt
+// keeps its modifier on whatever key it is bound to
+#[derive(Resource, Clone)]
+pub struct KeyBindings {
+    pub play: Vec<KeyCode>,
+    pub clear: Vec<KeyCode>,
+    pub lock: Vec<KeyCode>,
+    pub jump: Vec<KeyCode>,
+    pub step: Vec<KeyCode>,
+    pub speeds: [Vec<KeyCode>; 5], // 1, 5, 15, 60 and max generations per second
+    pub tap_tempo: Vec<KeyCode>,
+    pub rotate: Vec<KeyCode>,
+    pub mirror: Vec<KeyCode>,
+    pub shift_left: Vec<KeyCode>, // with Ctrl
+    pub shift_right: Vec<KeyCode>,
+    pub shift_up: Vec<KeyCode>,
+    pub shift_down: Vec<KeyCode>,
+    pub boundary: Vec<KeyCode>,
+    pub grow_grid: Vec<KeyCode>,
+    pub shrink_grid: Vec<KeyCode>,
+    pub crop: Vec<KeyCode>,
+    pub next_rule: Vec<KeyCode>,
+    pub about: Vec<KeyCode>,
+    pub analysis: Vec<KeyCode>,
+    pub next_theme: Vec<KeyCode>,
+    pub palette_cycle: Vec<KeyCode>,
+    pub hud_layout: Vec<KeyCode>,
+    pub mirror_window: Vec<KeyCode>,
+    pub timeline: Vec<KeyCode>,
+    pub reverse: Vec<KeyCode>,
+    pub shapes: Vec<KeyCode>,
+    pub rulebook: Vec<KeyCode>,
+    pub select: Vec<KeyCode>,
+    pub copy: Vec<KeyCode>, // with Ctrl
+    pub cut: Vec<KeyCode>,
+    pub paste: Vec<KeyCode>,
+    pub tile: Vec<KeyCode>,
+    pub wider_tiles: Vec<KeyCode>,
+    pub tighter_tiles: Vec<KeyCode>,
+    pub lab: Vec<KeyCode>,
+    pub fullscreen: Vec<KeyCode>,
+    pub paste_file: Vec<KeyCode>, // with Shift or Ctrl it exports the board instead
+    pub overflow: Vec<KeyCode>,
+    pub snap: Vec<KeyCode>,
+    pub turn_pattern: Vec<KeyCode>,
+    pub flip_pattern: Vec<KeyCode>,
+    pub library: Vec<KeyCode>,
+    pub drift: Vec<KeyCode>,
+    pub brush_shape: Vec<KeyCode>,
+    pub spray: Vec<KeyCode>,
+    pub thinner_spray: Vec<KeyCode>,
+    pub denser_spray: Vec<KeyCode>,
+    pub edge_alarm: Vec<KeyCode>,
+    pub chaos: Vec<KeyCode>,
+    pub components: Vec<KeyCode>,
+    pub export_recipe: Vec<KeyCode>,
+    pub replay_step: Vec<KeyCode>,
+    pub load_recipe: Vec<KeyCode>,
+    pub resume: Vec<KeyCode>,
+    pub freeze: Vec<KeyCode>,
+    pub active_region: Vec<KeyCode>,
+    pub sparkline: Vec<KeyCode>,
+    pub trail: Vec<KeyCode>, // with Shift
+    pub panel: Vec<KeyCode>,
+    pub probe: Vec<KeyCode>,
+    pub density: Vec<KeyCode>,
+    pub pause_menu: Vec<KeyCode>,
+    pub menu_up: Vec<KeyCode>,
+    pub menu_down: Vec<KeyCode>,
+    pub menu_pick: Vec<KeyCode>,
+    pub menu_back: Vec<KeyCode>,
+    pub menu_favorite: Vec<KeyCode>,
+    pub macros: Vec<KeyMacro>, // from settings.txt, see command.rs
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            play: vec![KeyCode::Space, KeyCode::S],
+            clear: vec![KeyCode::R],
+            lock: vec![KeyCode::K],
+            jump: vec![KeyCode::J],
+            step: vec![KeyCode::N, KeyCode::Right],
+            speeds: [
+                vec![KeyCode::Key1],
+                vec![KeyCode::Key2],
+                vec![KeyCode::Key3],
+                vec![KeyCode::Key4],
+                vec![KeyCode::Key5],
+            ],
+            tap_tempo: vec![KeyCode::Tab],
+            rotate: vec![KeyCode::T],
+            mirror: vec![KeyCode::M],
+            shift_left: vec![KeyCode::Left],
+            shift_right: vec![KeyCode::Right],
+            shift_up: vec![KeyCode::Up],
+            shift_down: vec![KeyCode::Down],
+            boundary: vec![KeyCode::B],
+            grow_grid: vec![KeyCode::Equals],
+            shrink_grid: vec![KeyCode::Minus],
+            crop: vec![KeyCode::Home],
+            next_rule: vec![KeyCode::G],
+            about: vec![KeyCode::F1],
+            analysis: vec![KeyCode::F2],
+            next_theme: vec![KeyCode::F3],
+            palette_cycle: vec![KeyCode::F12],
+            hud_layout: vec![KeyCode::F4],
+            mirror_window: vec![KeyCode::F5],
+            timeline: vec![KeyCode::F6],
+            reverse: vec![KeyCode::Back],
+            shapes: vec![KeyCode::F7],
+            rulebook: vec![KeyCode::F8],
+            select: vec![KeyCode::F9],
+            copy: vec![KeyCode::C],
+            cut: vec![KeyCode::X],
+            paste: vec![KeyCode::V],
+            tile: vec![KeyCode::End],
+            wider_tiles: vec![KeyCode::PageUp],
+            tighter_tiles: vec![KeyCode::PageDown],
+            lab: vec![KeyCode::F10],
+            fullscreen: vec![KeyCode::F11],
+            paste_file: vec![KeyCode::O],
+            overflow: vec![KeyCode::Insert],
+            snap: vec![KeyCode::Backslash],
+            turn_pattern: vec![KeyCode::Period],
+            flip_pattern: vec![KeyCode::Comma],
+            library: vec![KeyCode::U],
+            drift: vec![KeyCode::W],
+            brush_shape: vec![KeyCode::Z],
+            spray: vec![KeyCode::P],
+            thinner_spray: vec![KeyCode::LBracket],
+            denser_spray: vec![KeyCode::RBracket],
+            edge_alarm: vec![KeyCode::A],
+            chaos: vec![KeyCode::X],
+            components: vec![KeyCode::C],
+            export_recipe: vec![KeyCode::E],
+            replay_step: vec![KeyCode::Return],
+            load_recipe: vec![KeyCode::L],
+            resume: vec![KeyCode::Y],
+            freeze: vec![KeyCode::F],
+            active_region: vec![KeyCode::V],
+            sparkline: vec![KeyCode::H],
+            trail: vec![KeyCode::H],
+            panel: vec![KeyCode::Q],
+            probe: vec![KeyCode::I],
+            density: vec![KeyCode::D],
+            pause_menu: vec![KeyCode::Escape],
+            menu_up: vec![KeyCode::Up],
+            menu_down: vec![KeyCode::Down],
+            menu_pick: vec![KeyCode::Return, KeyCode::Space],
+            menu_back: vec![KeyCode::Escape],
+            menu_favorite: vec![KeyCode::F],
+            macros: Vec::new(),
+        }
+    }
+}
+
+// the keyboard seen through KeyBindings, it derefs to the keyboard for the modifiers
+#[derive(SystemParam)]
+pub struct Keys<'w, 's> {
+    input: Res<'w, Input<KeyCode>>,
+    bindings: Res<'w, KeyBindings>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl Keys<'_, '_> {
+    // one of the keys bound to `control` was just pressed
+    pub fn just_pressed(&self, control: impl Fn(&KeyBindings) -> &Vec<KeyCode>) -> bool {
+        self.input
+            .any_just_pressed(control(&self.bindings).iter().copied())
+    }
+}
+
+impl std::ops::Deref for Keys<'_, '_> {
+    type Target = Input<KeyCode>;
+
+    fn deref(&self) -> &Input<KeyCode> {
+        &self.input
+    }
+}
+
+pub const SIZE: f32 = 0.95; // it gives some padding
 #[derive(Component)]
 pub struct CellGrid {
     element: Element,
@@ -33,41 +321,139 @@ struct CellBundle {
     element: CellGrid,
 }
 
-#[derive(Resource)]
-struct GridMap {
-    vec: Vec<Vec<bool>>,
+// grid dimensions, changing them at runtime respawns the cells and resizes Board
+#[derive(Resource, Clone, Copy, PartialEq, Eq)]
+pub struct GridConfig {
+    pub width: u32,
+    pub height: u32, // includes the rows reserved for the text
 }
 
-fn grid_scale(
-    windows: Res<Windows>,
-    mut query: Query<(&CellGrid, &mut Transform), With<CellGrid>>,
-) {
-    let win = windows.get_primary().unwrap();
-    for (cell, mut transform) in query.iter_mut() {
-        transform.scale = Vec3::new(
-            cell.size / GRID_WIDTH as f32 * win.width(),
-            cell.size / GRID_HEIGHT as f32 * win.height(),
-            0.0,
-        );
+impl Default for GridConfig {
+    fn default() -> Self {
+        GridConfig {
+            width: 25,
+            height: 25,
+        }
     }
 }
 
-fn pos_translation(
-    windows: Res<Windows>,
-    mut query: Query<(&Position, &mut Transform), With<CellGrid>>,
-) {
-    // the bounds are set in order to have a squared window, as well as same GRID_WIDTH / GRID_HEIGHT
-    fn coord_transform(pos: f32, win_bounds: f32, map_bounds: f32) -> f32 {
-        let cell_size = win_bounds / map_bounds;
-        pos / map_bounds * win_bounds - (win_bounds / 2.) + (cell_size / 2.)
+impl GridConfig {
+    // the top rows are reserved for the text, 2 out of every 25 so it gets the same room at any size
+    pub fn space_top(&self) -> u32 {
+        (self.height * 2).div_ceil(25)
+    }
+
+    // the area that has cells
+    pub fn board_size(&self) -> UVec2 {
+        UVec2::new(self.width, self.height - self.space_top())
+    }
+
+    fn size(&self) -> UVec2 {
+        UVec2::new(self.width, self.height)
     }
-    let window = windows.get_primary().unwrap();
+
+    // both sides grown (or shrunk) by `by` cells, clamped to MIN_GRID..=MAX_GRID
+    pub fn resized(&self, by: i32) -> Self {
+        let clamp = |v: u32| (v as i32 + by).clamp(MIN_GRID as i32, MAX_GRID as i32) as u32;
+        GridConfig {
+            width: clamp(self.width),
+            height: clamp(self.height),
+        }
+    }
+}
+
+impl Resource for Board {}
+impl Resource for Boundary {}
+
+impl FromWorld for Board {
+    fn from_world(world: &mut World) -> Self {
+        let board = world
+            .get_resource_or_insert_with(GridConfig::default)
+            .board_size();
+        let boundary = *world.get_resource_or_insert_with(Boundary::default);
+        let rule = *world.get_resource_or_insert_with(Ruleset::default);
+        let mut map = Board::new(board);
+        map.set_boundary(boundary);
+        map.set_rule(rule);
+        map
+    }
+}
+
+// the window and the grid, everything needed to go between cells and screen space
+#[derive(SystemParam)]
+pub struct BoardView<'w, 's> {
+    windows: Res<'w, Windows>,
+    grid: Res<'w, GridConfig>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl<'w, 's> BoardView<'w, 's> {
+    // None until the primary window exists
+    pub fn screen(&self) -> Option<Screen<'_>> {
+        Some(Screen {
+            win: self.windows.get_primary()?,
+            grid: *self.grid,
+        })
+    }
+}
+
+pub struct Screen<'a> {
+    win: &'a Window,
+    grid: GridConfig,
+}
+
+impl<'a> Screen<'a> {
+    // the area that has cells, see GridConfig::board_size
+    pub fn board_size(&self) -> UVec2 {
+        self.grid.board_size()
+    }
+
+    // size in pixels of one grid cell
+    pub fn cell_size(&self) -> Vec2 {
+        Vec2::new(self.win.width(), self.win.height()) / self.grid.size().as_vec2()
+    }
+
+    // world coordinates of the center of cell (x, y)
+    pub fn cell_to_world(&self, x: f32, y: f32) -> Vec2 {
+        fn coord_transform(pos: f32, win_bounds: f32, map_bounds: f32) -> f32 {
+            let cell_size = win_bounds / map_bounds;
+            pos / map_bounds * win_bounds - (win_bounds / 2.) + (cell_size / 2.)
+        }
+        Vec2::new(
+            coord_transform(x, self.win.width(), self.grid.width as f32),
+            coord_transform(y, self.win.height(), self.grid.height as f32),
+        )
+    }
+
+    // cell under the mouse cursor, None outside the board
+    pub fn cursor_to_cell(&self) -> Option<UVec2> {
+        let cursor_pos = self.win.cursor_position()?;
+        let cell = (cursor_pos / self.cell_size()).floor();
+        // the top rows are reserved for the text, there are no cells there
+        let board = self.grid.board_size().as_vec2();
+        if cell.x < 0.0 || cell.y < 0.0 || cell.x >= board.x || cell.y >= board.y {
+            return None;
+        }
+        Some(cell.as_uvec2())
+    }
+}
+
+fn grid_scale(view: BoardView, mut query: Query<(&CellGrid, &mut Transform), With<CellGrid>>) {
+    let Some(screen) = view.screen() else {
+        return;
+    };
+    for (cell, mut transform) in query.iter_mut() {
+        transform.scale = (cell.size * screen.cell_size()).extend(0.0);
+    }
+}
+
+fn pos_translation(view: BoardView, mut query: Query<(&Position, &mut Transform), With<CellGrid>>) {
+    let Some(screen) = view.screen() else {
+        return;
+    };
     for (pos, mut transform) in query.iter_mut() {
-        transform.translation = Vec3::new(
-            coord_transform(pos.x as f32, window.width(), GRID_WIDTH as f32),
-            coord_transform(pos.y as f32, window.height(), GRID_HEIGHT as f32),
-            0.0,
-        );
+        transform.translation = screen.cell_to_world(pos.x as f32, pos.y as f32).extend(0.0);
     }
 }
 
@@ -75,45 +461,202 @@ pub struct SimPlugin;
 
 impl Plugin for SimPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(GridMap {
-            vec: vec![vec![false; GRID_HEIGHT as usize]; GRID_WIDTH as usize],
-        })
-        .insert_resource(State(false))
-        .add_startup_system(setup)
-        .add_startup_system(set_text)
-        .add_system_set_to_stage(
-            CoreStage::PostUpdate,
-            SystemSet::new()
-                .with_system(pos_translation)
-                .with_system(grid_scale),
-        )
-        .add_system_set(
-            SystemSet::new()
-                .with_run_criteria(FixedTimestep::step(0.025))
-                //.with_system(print_vec) debug usage
-                .with_system(handle_sim),
-        )
-        .add_system(handle_keyboard)
-        .add_system(handle_click)
-        .add_system(text_update_system)
-        ;
+        app.init_resource::<GridConfig>()
+            .init_resource::<Boundary>()
+            .init_resource::<Board>()
+            .insert_resource(State(false))
+            .init_resource::<Generation>()
+            .init_resource::<PaintQueue>()
+            .init_resource::<SimStats>()
+            .init_resource::<Tool>()
+            .init_resource::<EditLock>()
+            .init_resource::<PendingEdits>()
+            .init_resource::<StartupScene>()
+            .init_resource::<SimRng>()
+            .init_resource::<CellColors>()
+            .init_resource::<KeyBindings>()
+            .init_resource::<SingleStep>()
+            .init_resource::<LiveBackend>()
+            .init_resource::<StepWorker>()
+            .add_event::<ClearBoard>()
+            .add_event::<ToggleCell>()
+            .add_event::<SetCell>()
+            .add_event::<TransformBoard>()
+            .add_event::<JumpAhead>()
+            .add_event::<RestoreBoard>()
+            .add_event::<RestartBoard>()
+            .add_event::<RandomizeBoard>()
+            .add_startup_system(load_startup_scene)
+            .add_startup_system(setup.after(load_startup_scene))
+            .add_startup_system(set_text)
+            .add_system_set_to_stage(
+                CoreStage::PostUpdate,
+                SystemSet::new()
+                    .with_system(pos_translation)
+                    .with_system(grid_scale),
+            )
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(sim_due)
+                    //.with_system(print_vec) debug usage
+                    .with_system(apply_pending_edits)
+                    .with_system(handle_sim.after(apply_pending_edits)),
+            )
+            .add_system(handle_keyboard)
+            .add_system(pause_worker.after(handle_keyboard))
+            .add_system(clear_board.after(handle_keyboard))
+            .add_system(handle_click)
+            .add_system(edit_cells.after(handle_click).after(clear_board))
+            .add_system(handle_transform_keys)
+            .add_system(handle_grid_keys)
+            .add_system(crop_board.before(restore_board))
+            .add_system(handle_boundary_key)
+            .add_system(
+                sync_settings
+                    .after(handle_boundary_key)
+                    .before(apply_pending_edits),
+            )
+            .add_system(jump_ahead.after(handle_keyboard).after(edit_cells))
+            .add_system(
+                resize_grid
+                    .after(handle_grid_keys)
+                    .after(transform_board)
+                    .after(handle_sim)
+                    .after(jump_ahead)
+                    .after(restore_board),
+            )
+            .add_system(restore_board)
+            .add_system(restart_board.before(restore_board))
+            .add_system(
+                transform_board
+                    .after(edit_cells)
+                    .after(handle_transform_keys),
+            )
+            .add_system(text_update_system)
+            .add_system(apply_paint)
+            .add_system(repaint_cells.after(apply_paint));
     }
 }
 
-fn setup(mut commands: Commands) {
-    for x in 0..GRID_WIDTH {
-        for y in 0..(GRID_HEIGHT - SPACE_TOP){
+// what the board shows at launch
+#[derive(Resource, Clone, Default)]
+pub enum StartupScene {
+    #[default]
+    Empty,
+    Soup {
+        density: f64,
+    }, // each cell alive with probability `density`
+    Density(DensityMap), // a random state following the densities of an image
+    Stress(Stress),      // a worst case for benchmarks, no randomness
+    Pattern(Pattern),    // a library pattern in the middle of the board
+}
+
+impl StartupScene {
+    // whether the scene needs a seed to come out the same twice
+    pub fn is_random(&self) -> bool {
+        matches!(self, StartupScene::Soup { .. } | StartupScene::Density(_))
+    }
+
+    pub fn build(&self, grid: &GridConfig, rng: &mut SimRng) -> Board {
+        self.build_board(grid.board_size(), rng)
+    }
+
+    // the scene on a board of any size, for boards that don't go through GridConfig
+    pub fn build_board(&self, board: UVec2, rng: &mut SimRng) -> Board {
+        let mut map = Board::new(board);
+        match *self {
+            StartupScene::Empty => {}
+            StartupScene::Soup { density } => {
+                for x in 0..board.x {
+                    for y in 0..board.y {
+                        map.set(x, y, rng.0.gen_bool(density));
+                    }
+                }
+            }
+            StartupScene::Density(ref density) => {
+                for (x, y, alive) in density.microstate(board, rng) {
+                    map.set(x, y, alive);
+                }
+            }
+            StartupScene::Stress(stress) => stress.fill(&mut map),
+            // what doesn't fit on a small board is left out
+            StartupScene::Pattern(ref pattern) => {
+                let offset = (board.as_ivec2() - pattern.size.as_ivec2()) / 2;
+                for cell in pattern.alive.iter() {
+                    let at = cell.as_ivec2() + offset;
+                    if at.cmpge(IVec2::ZERO).all() && at.cmplt(board.as_ivec2()).all() {
+                        map.set(at.x as u32, at.y as u32, true);
+                    }
+                }
+            }
+        }
+        map
+    }
+}
+
+// the one rng everything random on the board draws from, so a seed reproduces a run
+// StdRng gives the same stream on every platform for a given rand version
+#[derive(Resource)]
+pub struct SimRng(pub StdRng);
+
+impl SimRng {
+    pub fn new(seed: Option<u64>) -> Self {
+        match seed {
+            Some(seed) => SimRng(StdRng::seed_from_u64(seed)),
+            None => SimRng(StdRng::from_entropy()),
+        }
+    }
+
+    // StdRng has no state to save, so a saved run continues from a seed drawn here instead
+    pub fn reseed(&mut self) -> u64 {
+        let seed = self.0.gen();
+        *self = SimRng::new(Some(seed));
+        seed
+    }
+}
+
+impl Default for SimRng {
+    fn default() -> Self {
+        SimRng::new(None)
+    }
+}
+
+fn load_startup_scene(
+    scene: Res<StartupScene>,
+    grid: Res<GridConfig>,
+    mut rng: ResMut<SimRng>,
+    mut map: ResMut<Board>,
+    mut stats: ResMut<SimStats>,
+) {
+    let (boundary, rule, data) = (map.boundary(), map.rule(), map.data_kind());
+    *map = scene.build(&grid, &mut rng);
+    map.set_boundary(boundary);
+    map.set_rule(rule);
+    // a data channel enabled before startup stays, zeroed
+    if let Some(kind) = data {
+        map.enable_data(kind);
+    }
+    stats.edited(&map);
+}
+
+fn setup(mut commands: Commands, grid: Res<GridConfig>, map: Res<Board>, colors: Res<CellColors>) {
+    spawn_cells(&mut commands, &grid, &map, &colors);
+}
+
+fn spawn_cells(commands: &mut Commands, grid: &GridConfig, map: &Board, colors: &CellColors) {
+    let board = grid.board_size();
+    for x in 0..board.x {
+        for y in 0..board.y {
+            let element = Element::at(map, x, y);
+            let color = colors.of(element, map.rule().decay_states());
             commands
                 .spawn(SpriteBundle {
-                    sprite: Sprite {
-                        color: EMPTY_COLOR,
-                        ..default()
-                    },
+                    sprite: Sprite { color, ..default() },
                     ..default()
                 })
                 .insert(CellBundle {
                     element: CellGrid {
-                        element: Element::Empty,
+                        element,
                         size: SIZE,
                     },
                     pos: Position { x, y },
@@ -122,134 +665,1061 @@ fn setup(mut commands: Commands) {
     }
 }
 
+// left clicks on the board that are allowed to edit it, see EditLock
+#[derive(SystemParam)]
+pub struct BoardClick<'w, 's> {
+    pub mouse: Res<'w, Input<MouseButton>>,
+    key: Res<'w, Input<KeyCode>>,
+    state: Res<'w, State>,
+    lock: ResMut<'w, EditLock>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl BoardClick<'_, '_> {
+    pub fn edits(&mut self) -> bool {
+        self.mouse.just_pressed(MouseButton::Left) && !self.refuses()
+    }
+
+    pub fn refuses(&mut self) -> bool {
+        self.lock.refuses(&self.state, &self.key)
+    }
+}
+
+// every cell on the straight line between two cells, both ends included
+pub fn line_cells(from: UVec2, to: UVec2) -> Vec<UVec2> {
+    let (from, to) = (from.as_ivec2(), to.as_ivec2());
+    let delta = (to - from).abs();
+    let step = (to - from).signum();
+    let mut error = delta.x - delta.y;
+    let mut cell = from;
+    let mut cells = vec![cell.as_uvec2()];
+    while cell != to {
+        if 2 * error > -delta.y {
+            error -= delta.y;
+            cell.x += step.x;
+        }
+        if 2 * error < delta.x {
+            error += delta.x;
+            cell.y += step.y;
+        }
+        cells.push(cell.as_uvec2());
+    }
+    cells
+}
+
+// a mouse drag that started on the board with Tool::Draw
+#[derive(Default)]
+struct Drag {
+    button: Option<MouseButton>, // Left paints, Right erases
+    last: Option<UVec2>,         // None while the cursor is off the board
+    painted: bool,               // `last` already has the value of the stroke
+}
+
+// a left click toggles a cell, a right click empties it
+// dragging paints live cells (or empties them with the right button) along the path of the cursor
+// with a brush wider than a cell, clicks paint and erase the whole brush too
 fn handle_click(
-    mouse_input: Res<Input<MouseButton>>,
-    windows: Res<Windows>,
-    mut query: Query<(&Position, &mut CellGrid, &mut Sprite), With<CellGrid>>,
-    mut map: ResMut<GridMap>,
+    mut click: BoardClick,
+    view: BoardView,
+    tool: Res<Tool>,
+    brush: Res<Brush>,
+    mut drag: Local<Drag>,
+    mut toggles: EventWriter<ToggleCell>,
+    mut sets: EventWriter<SetCell>,
 ) {
-    fn coord_transform(pos: f32, win_bounds: f32, map_bounds: f32) -> u32 {
-        let cell_size = win_bounds / map_bounds;
-        (pos / cell_size).floor() as u32
-    }
-
-    let win = windows.get_primary().expect("no primary window");
-    if mouse_input.just_pressed(MouseButton::Left) {
-        if let Some(cursor_pos) = win.cursor_position() {
-            let x = coord_transform(cursor_pos.x, win.width(), GRID_WIDTH as f32);
-            let y = coord_transform(cursor_pos.y, win.height(), GRID_HEIGHT as f32);
-
-            for (pos, mut cell_grid, mut spr) in query.iter_mut() {
-                if (pos.x == x) & (pos.y == y) {
-                    if cell_grid.element == Element::Empty {
-                        cell_grid.element = Element::Lifeform;
-                        spr.color = LIFEFORM_COLOR;
-                        map.vec[pos.x as usize][pos.y as usize] = true;
-                    } else if cell_grid.element == Element::Lifeform {
-                        cell_grid.element = Element::Empty;
-                        spr.color = EMPTY_COLOR;
-                        map.vec[pos.x as usize][pos.y as usize] = false;
-                    }
-                }
+    let Some(screen) = view.screen() else {
+        return;
+    };
+    if *tool != Tool::Draw {
+        *drag = Drag::default();
+        return;
+    }
+    // released, the stroke is over
+    if drag
+        .button
+        .is_some_and(|button| !click.mouse.pressed(button))
+    {
+        *drag = Drag::default();
+    }
+    let cell = screen.cursor_to_cell();
+    let board = screen.board_size();
+    let buttons = [MouseButton::Left, MouseButton::Right];
+    if let Some(button) = buttons.into_iter().find(|&b| click.mouse.just_pressed(b)) {
+        let Some(cell) = cell else {
+            return;
+        };
+        if click.refuses() {
+            return;
+        }
+        let toggle = button == MouseButton::Left && brush.is_single();
+        if toggle {
+            toggles.send(ToggleCell {
+                x: cell.x,
+                y: cell.y,
+            });
+        } else {
+            for cell in brush.cells(cell, board) {
+                sets.send(SetCell {
+                    x: cell.x,
+                    y: cell.y,
+                    alive: button == MouseButton::Left,
+                });
+            }
+        }
+        *drag = Drag {
+            button: Some(button),
+            last: Some(cell),
+            // a toggled cell is painted too once the drag moves on
+            painted: !toggle,
+        };
+        return;
+    }
+    let Some(button) = drag.button else {
+        return;
+    };
+    if cell == drag.last {
+        return;
+    }
+    let Some(cell) = cell else {
+        drag.last = None;
+        return;
+    };
+    if click.refuses() {
+        return;
+    }
+    // between frames the cursor can skip cells, the line fills them in
+    let from = drag.last.unwrap_or(cell);
+    let skip = usize::from(drag.painted && drag.last.is_some());
+    let mut painted = HashSet::new();
+    for center in line_cells(from, cell).into_iter().skip(skip) {
+        for cell in brush.cells(center, board) {
+            if painted.insert(cell) {
+                sets.send(SetCell {
+                    x: cell.x,
+                    y: cell.y,
+                    alive: button == MouseButton::Left,
+                });
             }
         }
     }
+    drag.last = Some(cell);
+    drag.painted = true;
+}
+
+// when enabled, clicks are ignored while the simulation is playing
+#[derive(Resource, Default)]
+pub struct EditLock {
+    pub enabled: bool,
+    warning: Timer, // running while the "edits locked" warning is on screen
+}
+
+impl EditLock {
+    // whether a click on the board has to be ignored, the warning is shown then
+    // holding Alt overrides the lock
+    pub fn refuses(&mut self, state: &State, key: &Input<KeyCode>) -> bool {
+        let refused = self.enabled && state.0 && !key.any_pressed([KeyCode::LAlt, KeyCode::RAlt]);
+        if refused {
+            self.warning = Timer::from_seconds(1.5, TimerMode::Once);
+        }
+        refused
+    }
+}
+
+// what the left mouse button does on the board
+#[derive(Resource, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Tool {
+    #[default]
+    Draw,
+    Freeze,
+    Spray,
+    Probe,
+    Active,
+    Stamp,  // a pattern from the library, see library.rs
+    Shape,  // lines, rectangles and ellipses, see shapes.rs
+    Select, // a rectangle to copy or cut, see select.rs
+    Paste,
+}
+
+impl Tool {
+    fn label(&self) -> &'static str {
+        match self {
+            Tool::Draw => "",
+            Tool::Freeze => "  [freeze]",
+            Tool::Spray => "  [spray]",
+            Tool::Probe => "  [probe]",
+            Tool::Active => "  [active area]",
+            Tool::Stamp => "  [stamp]",
+            Tool::Shape => "  [shape]",
+            Tool::Select => "  [select]",
+            Tool::Paste => "  [paste]",
+        }
+    }
+}
+
+// flips a single cell between Lifeform and Empty
+pub struct ToggleCell {
+    pub x: u32,
+    pub y: u32,
+}
+
+// forces a single cell alive or dead
+pub struct SetCell {
+    pub x: u32,
+    pub y: u32,
+    pub alive: bool,
+}
+
+#[derive(SystemParam)]
+struct CellEdits<'w, 's> {
+    toggles: EventReader<'w, 's, ToggleCell>,
+    sets: EventReader<'w, 's, SetCell>,
+}
+
+// edits made while playing, applied all at once right before the next generation
+#[derive(Resource, Default)]
+struct PendingEdits(Vec<(u32, u32, bool)>);
+
+impl PendingEdits {
+    fn flush(&mut self, map: &mut Board, stats: &mut SimStats) {
+        if self.0.is_empty() {
+            return;
+        }
+        for (x, y, alive) in self.0.drain(..) {
+            map.set(x, y, alive);
+        }
+        stats.edited(map);
+    }
+}
+
+// the sprite (and CellGrid) change right away as a preview, Board only on a generation boundary
+fn edit_cells(
+    mut edits: CellEdits,
+    mut sprites: DirectSprites,
+    mut map: ResMut<Board>,
+    mut paint: ResMut<PaintQueue>,
+    mut stats: ResMut<SimStats>,
+    mut pending: ResMut<PendingEdits>,
+    state: Res<State>,
+) {
+    // None toggles the cell, Some sets it
+    let edits: Vec<(u32, u32, Option<bool>)> = edits
+        .toggles
+        .iter()
+        .map(|t| (t.x, t.y, None))
+        .chain(edits.sets.iter().map(|s| (s.x, s.y, Some(s.alive))))
+        .collect();
+    if edits.is_empty() {
+        // edits queued right before a pause still go in, a paused board is always up to date
+        if !state.0 {
+            pending.flush(&mut map, &mut stats);
+        }
+        return;
+    }
+    // pasted patterns can edit thousands of cells at once, look them up instead of scanning
+    let cells: HashMap<(u32, u32), Entity> = sprites
+        .query
+        .iter()
+        .map(|(entity, pos, _, _)| ((pos.x, pos.y), entity))
+        .collect();
+    let mut edited = HashSet::new();
+    for (x, y, alive) in edits {
+        let Some(&entity) = cells.get(&(x, y)) else {
+            continue;
+        };
+        let Ok((_, _, mut cell_grid, mut spr)) = sprites.query.get_mut(entity) else {
+            continue;
+        };
+        let alive = alive.unwrap_or(!matches!(cell_grid.element, Element::Lifeform(_)));
+        cell_grid.element = match alive {
+            true => Element::Lifeform(0),
+            false => Element::Empty,
+        };
+        spr.color = sprites.colors.of(cell_grid.element, 0);
+        pending.0.push((x, y, alive));
+        edited.insert(entity);
+    }
+    // a stale queued color would otherwise overwrite the edit
+    paint.forget(&edited);
+    // nothing to race with while paused
+    if !state.0 {
+        pending.flush(&mut map, &mut stats);
+    }
+}
+
+fn apply_pending_edits(
+    mut pending: ResMut<PendingEdits>,
+    mut map: ResMut<Board>,
+    mut stats: ResMut<SimStats>,
+) {
+    pending.flush(&mut map, &mut stats);
 }
 
 // debug usage
-/* fn print_vec(mut map: ResMut<GridMap>) {
+/* fn print_vec(mut map: ResMut<Board>) {
     println!("{}", "-".repeat(80));
     println!("{:?}", map.vec);
 } */
 
+// the cell sprites, recolored through the paint queue to follow the grid
+#[derive(SystemParam)]
+struct CellSprites<'w, 's> {
+    query: Query<'w, 's, (Entity, &'static Position, &'static mut CellGrid)>,
+    paint: ResMut<'w, PaintQueue>,
+    colors: Res<'w, CellColors>,
+}
+
+// the cell sprites for systems that repaint them right away, bypassing the paint queue
+#[derive(SystemParam)]
+struct DirectSprites<'w, 's> {
+    query: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static Position,
+            &'static mut CellGrid,
+            &'static mut Sprite,
+        ),
+    >,
+    colors: Res<'w, CellColors>,
+}
+
+impl CellSprites<'_, '_> {
+    // queues every cell that differs in `next`, aging ones included, returns (births, deaths)
+    fn follow(&mut self, next: &Board) -> (usize, usize) {
+        let (mut births, mut deaths) = (0, 0);
+        let decay_states = next.rule().decay_states();
+        for (entity, pos, mut cell_grid) in self.query.iter_mut() {
+            let element = Element::at(next, pos.x, pos.y);
+            if element == cell_grid.element {
+                continue;
+            }
+            match (cell_grid.element, element) {
+                (Element::Lifeform(_), Element::Lifeform(_)) => {}
+                (_, Element::Lifeform(_)) => births += 1,
+                (Element::Lifeform(_), _) => deaths += 1,
+                _ => {}
+            }
+            cell_grid.element = element;
+            self.paint
+                .push(entity, self.colors.of(element, decay_states));
+        }
+        (births, deaths)
+    }
+}
+
+// one generation requested while paused, stepped right away whatever the speed
+#[derive(Resource, Default)]
+pub struct SingleStep(pub bool);
+
+fn sim_due(single: Res<SingleStep>, time: Res<Time>, speed: ResMut<Speed>) -> ShouldRun {
+    match single.0 {
+        true => ShouldRun::Yes,
+        false => generation_due(time, speed),
+    }
+}
+
+// what steps the generations while playing, picked with --backend
+// None is Board::step_auto, and so is a board the backend can't run
+#[derive(Resource, Default)]
+pub struct LiveBackend(Option<Box<dyn SimBackend + Send + Sync>>);
+
+impl LiveBackend {
+    pub fn new(backend: TraceBackend) -> Self {
+        match backend {
+            TraceBackend::Naive => LiveBackend(None),
+            other => LiveBackend(Some(other.create())),
+        }
+    }
+}
+
+#[derive(SystemParam)]
+struct Stepper<'w, 's> {
+    frozen: Res<'w, FrozenRegions>,
+    backend: ResMut<'w, LiveBackend>,
+    governor: ResMut<'w, Governor>,
+    speed: Res<'w, Speed>,
+    time: Res<'w, Time>,
+    worker: ResMut<'w, StepWorker>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl Stepper<'_, '_> {
+    // the sparse backend keeps its set from one generation to the next, an edit changes the
+    // state hash and has it read the board again
+    fn step(&mut self, map: &mut Board) {
+        match self.backend.0.as_mut() {
+            Some(backend) if backend.supports(map, &self.frozen) => {
+                backend.advance_in_place(map, &self.frozen, 1)
+            }
+            _ => *map = map.step_auto(&self.frozen),
+        }
+    }
+}
+
 fn handle_sim(
-    mut map: ResMut<GridMap>,
-    mut query: Query<(&Position, &mut CellGrid, &mut Sprite), With<CellGrid>>,
-    state: ResMut<State>,
+    mut map: ResMut<Board>,
+    mut sprites: CellSprites,
+    mut state: ResMut<State>,
+    mut generation: ResMut<Generation>,
+    mut stats: ResMut<SimStats>,
+    mut stepper: Stepper,
+    mut single: ResMut<SingleStep>,
 ) {
-    if state.0 {
-        let mut cloned_map_vec = map.vec.clone(); // cloned map so it can be used for processing and then modify the actual map
-        for (pos, mut cell_grid, mut spr) in query.iter_mut() {
-            let mut n = 0; // neighbour counter
-
-            // Conway's Game of Life Main Rules:
-            // Any live cell with two or three live neighbours survives.
-            // Any dead cell with three live neighbours becomes a live cell.
-            // All other live cells die in the next generation. Similarly, all other dead cells stay dead.
-            let x = pos.x as i32;
-            let y = pos.y as i32;
-
-            for i in (x - 1)..(x + 2) {
-                for j in (y - 1)..(y + 2) {
-                    if (i != x || j != y)
-                        && (i < GRID_WIDTH as i32 && j < GRID_HEIGHT as i32)
-                        && (j >= 0)
-                        && (i >= 0)
-                    {
-                        if map.vec[i as usize][j as usize] {
-                            n += 1;
-                        }
+    if stats.stepped != 0 {
+        stats.stepped = 0;
+    }
+    if state.0 || std::mem::take(&mut single.0) {
+        // nothing can be born on an empty board, pause instead of scanning it forever
+        // (dying cells of a Generations rule still have to fade out first)
+        if map.is_empty() {
+            state.0 = false;
+            stats.extinct_at.get_or_insert(generation.0);
+            return;
+        }
+
+        // at max speed as many generations as the governor leaves time for, otherwise one
+        // the grid is stepped on its own, the sprites only follow the last one
+        // with --worker the generations come from its thread while playing, as many as it has
+        // ready (none if it is still stepping, there's no waiting for it)
+        let flat_out = state.0 && stepper.speed.rate.is_none();
+        let started = Instant::now();
+        let budget = stepper.governor.budget(&stepper.time);
+        let mut restart = stepper.frozen.is_changed();
+        let mut stepped = 0;
+        loop {
+            match stepper.worker.0.as_mut().filter(|_| state.0) {
+                Some(worker) => {
+                    match worker.next(&map, &stepper.frozen, std::mem::take(&mut restart)) {
+                        Some(next) => *map = next,
+                        None => break,
                     }
                 }
+                None => stepper.step(&mut map),
             }
-
-            if n < 2 || n > 3 {
-                if cell_grid.element == Element::Lifeform {
-                    cell_grid.element = Element::Empty;
-                    spr.color = EMPTY_COLOR;
-                    cloned_map_vec[pos.x as usize][pos.y as usize] = false;
-                }
+            let done = !flat_out || map.is_empty() || started.elapsed() >= budget;
+            generation.0 += 1;
+            stats.update(&map, generation.0, 0, 0);
+            stepped += 1;
+            if done {
+                break;
             }
+        }
+        stepper.governor.spend(started.elapsed());
+        stats.stepped = stepped;
+        // the sprites only follow the last generation, its births and deaths are what they missed
+        if stepped > 0 {
+            (stats.births, stats.deaths) = sprites.follow(&map);
+        }
 
-            let mut r = rand::thread_rng();
-            let mut g = rand::thread_rng();
-            let mut b = rand::thread_rng();
-
-            if n == 3 {
-                if cell_grid.element == Element::Empty {
-                    cell_grid.element = Element::Lifeform;
-                    spr.color = Color::rgb(
-                        r.gen_range((133.0 / 255.0)..(250.0 / 255.0)),
-                        g.gen_range((211.0 / 255.0)..(250.0 / 255.0)),
-                        b.gen_range((56.0 / 255.0)..(110.0 / 255.0)),
-                    );
-                    cloned_map_vec[pos.x as usize][pos.y as usize] = true;
-                }
-            }
+        if map.is_empty() {
+            state.0 = false;
+        }
+    }
+}
+
+// whatever the worker stepped ahead of a paused board is dropped, play starts it again
+fn pause_worker(state: Res<State>, mut worker: ResMut<StepWorker>) {
+    if let Some(worker) = worker.0.as_mut().filter(|_| !state.0) {
+        worker.stop();
+    }
+}
+
+// generations skipped by J
+const JUMP_GENERATIONS: u64 = 1000;
+
+struct JumpAhead(u64);
+
+// generations of a jump still to run, and the HashLife tree kept from one jump to the next
+#[derive(Default)]
+struct Jump {
+    left: u64,
+    hashlife: HashLife,
+}
+
+// HashLife runs the whole jump at once when it can run the board, otherwise the generations are
+// stepped over as many frames as the governor needs, the cells are only read back once a frame
+fn jump_ahead(
+    mut events: EventReader<JumpAhead>,
+    mut map: ResMut<Board>,
+    mut sprites: CellSprites,
+    mut generation: ResMut<Generation>,
+    mut stats: ResMut<SimStats>,
+    mut stepper: Stepper,
+    mut jump: Local<Jump>,
+) {
+    jump.left += events
+        .iter()
+        .map(|JumpAhead(generations)| generations)
+        .sum::<u64>();
+    if jump.left == 0 {
+        return;
+    }
+    let started = Instant::now();
+    let frozen = &stepper.frozen;
+    let next = if jump.hashlife.supports(&map, frozen) {
+        let left = std::mem::take(&mut jump.left);
+        generation.0 += left;
+        jump.hashlife.advance(&map, frozen, left)
+    } else {
+        let budget = stepper.governor.budget(&stepper.time);
+        let mut next = map.step_auto(frozen);
+        generation.0 += 1;
+        jump.left -= 1;
+        while jump.left > 0 && started.elapsed() < budget {
+            next = next.step_auto(frozen);
+            generation.0 += 1;
+            jump.left -= 1;
+        }
+        next
+    };
+    stepper.governor.spend(started.elapsed());
+    sprites.follow(&next);
+    *map = next;
+    // the skipped generations were never seen, so there is no history to compare against
+    stats.generation = generation.0;
+    stats.edited(&map);
+    if jump.left == 0 {
+        info!("jumped to generation {}", generation.0);
+    }
+}
+
+// max sprite recolors applied per frame, big generations get spread over a few frames
+const PAINT_BUDGET: usize = 4096;
+
+// sprite colors waiting to be applied, the logical grid is already up to date
+// a cell that changes again before its turn only keeps its newest color, so however much the
+// generations outpace the budget there is at most one entry per cell
+#[derive(Resource, Default)]
+struct PaintQueue {
+    order: VecDeque<Entity>, // first queued first, every entity at most once
+    colors: HashMap<Entity, Color>,
+}
+
+impl PaintQueue {
+    fn push(&mut self, entity: Entity, color: Color) {
+        if self.colors.insert(entity, color).is_none() {
+            self.order.push_back(entity);
+        }
+    }
+
+    // the first `n` colors in the queue
+    fn pop(&mut self, n: usize) -> Vec<(Entity, Color)> {
+        let n = n.min(self.order.len());
+        let colors = &mut self.colors;
+        self.order
+            .drain(..n)
+            .filter_map(|entity| Some((entity, colors.remove(&entity)?)))
+            .collect()
+    }
+
+    // drops the queued colors of `entities`, they would overwrite a newer edit
+    fn forget(&mut self, entities: &HashSet<Entity>) {
+        if entities.is_empty() {
+            return;
+        }
+        self.colors.retain(|entity, _| !entities.contains(entity));
+        self.order.retain(|entity| !entities.contains(entity));
+    }
+
+    fn clear(&mut self) {
+        self.order.clear();
+        self.colors.clear();
+    }
+}
+
+fn apply_paint(mut paint: ResMut<PaintQueue>, mut query: Query<&mut Sprite, With<CellGrid>>) {
+    for (entity, color) in paint.pop(PAINT_BUDGET) {
+        if let Ok(mut spr) = query.get_mut(entity) {
+            spr.color = color;
+        }
+    }
+}
+
+// new colors repaint every cell at once, queued paint would still be in the old ones
+fn repaint_cells(mut sprites: DirectSprites, map: Res<Board>, mut paint: ResMut<PaintQueue>) {
+    if !sprites.colors.is_changed() || sprites.colors.is_added() {
+        return;
+    }
+    paint.clear();
+    let decay_states = map.rule().decay_states();
+    for (_, _, cell_grid, mut spr) in sprites.query.iter_mut() {
+        spr.color = sprites.colors.of(cell_grid.element, decay_states);
+    }
+}
+
+// how many past generations are remembered to detect still lifes and oscillators
+const STABILITY_WINDOW: usize = 32;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Stabilization {
+    #[default]
+    Evolving,
+    Still,
+    Oscillating(u32), // period in generations
+    Moving {
+        period: u32,
+        displacement: IVec2,
+    }, // the whole board repeats shifted every period
+    Extinct,
+}
+
+impl Stabilization {
+    pub fn label(&self) -> String {
+        match self {
+            Stabilization::Evolving | Stabilization::Extinct => String::new(),
+            Stabilization::Still => "  still life".to_string(),
+            Stabilization::Oscillating(period) => format!("  period {}", period),
+            Stabilization::Moving {
+                period,
+                displacement,
+            } => format!(
+                "  period {}, moves ({}, {})",
+                period, displacement.x, displacement.y
+            ),
         }
+    }
+}
+
+// what is remembered of a past generation for stabilization detection
+#[derive(Clone, Copy, Debug)]
+struct Fingerprint {
+    hash: u64,
+    shape: u64,
+    origin: UVec2, // bounding box min corner
+}
 
-        map.vec = cloned_map_vec;
+// board statistics refreshed every generation, the one place HUDs and other plugins should read from
+#[derive(Resource, Default, Clone, Debug)]
+pub struct SimStats {
+    pub generation: u64,
+    pub population: usize,
+    pub births: usize,
+    pub deaths: usize,
+    pub bounding_box: Option<(UVec2, UVec2)>,
+    pub stabilization: Stabilization,
+    pub extinct_at: Option<u64>, // generation at which the board died out, cleared once new cells are placed
+    pub components: usize, // connected groups of live cells, filled in by the components plugin
+    pub largest_component: usize,
+    pub hash: u64, // Board::state_hash, equal boards have equal hashes across runs and platforms
+    pub stepped: u64, // generations the last frame stepped in a row, 0 when it didn't step
+    recent: VecDeque<Fingerprint>, // the last STABILITY_WINDOW generations, newest first
+}
+
+impl SimStats {
+    fn update(&mut self, map: &Board, generation: u64, births: usize, deaths: usize) {
+        self.generation = generation;
+        self.population = map.population();
+        self.births = births;
+        self.deaths = deaths;
+        self.bounding_box = map.bounding_box();
+        self.hash = map.state_hash();
+
+        let Some(bounds) = self.bounding_box else {
+            self.extinct_at = Some(generation);
+            self.stabilization = Stabilization::Extinct;
+            self.recent.clear();
+            return;
+        };
+        let now = Fingerprint {
+            hash: self.hash,
+            shape: map.shape_hash(bounds),
+            origin: bounds.0,
+        };
+        // an exact repeat wins over a shifted one, a still life is also a shifted copy of itself
+        self.stabilization = match self.recent.iter().position(|f| f.hash == now.hash) {
+            Some(0) => Stabilization::Still,
+            Some(i) => Stabilization::Oscillating(i as u32 + 1),
+            None => match self.recent.iter().position(|f| f.shape == now.shape) {
+                Some(i) => Stabilization::Moving {
+                    period: i as u32 + 1,
+                    displacement: now.origin.as_ivec2() - self.recent[i].origin.as_ivec2(),
+                },
+                None => Stabilization::Evolving,
+            },
+        };
+        self.recent.push_front(now);
+        self.recent.truncate(STABILITY_WINDOW);
+    }
+
+    // user edits break the generation-to-generation history
+    fn edited(&mut self, map: &Board) {
+        self.population = map.population();
+        self.hash = map.state_hash();
+        self.bounding_box = map.bounding_box();
+        self.stabilization = Stabilization::Evolving;
+        self.recent.clear();
+        if self.population > 0 {
+            self.extinct_at = None;
+        }
     }
 }
 
+// generations stepped since the board was last cleared
+#[derive(Resource, Default)]
+struct Generation(u64);
+
 #[derive(Resource)]
-struct State(bool);
+pub struct State(pub bool);
 
-// play/pause and reset
+// play/pause and reset, the default KeyBindings
 // SPACE || S -> play/pause
 // R          -> clean board
+// K          -> lock/unlock edits while playing
+// J          -> while paused, jump JUMP_GENERATIONS ahead
+// N || RIGHT -> while paused, step a single generation (Ctrl + RIGHT shifts the board instead)
 fn handle_keyboard(
-    key: Res<Input<KeyCode>>,
+    key: Keys,
     mut state: ResMut<State>,
-    commands: Commands,
-    mut map: ResMut<GridMap>,
+    mut lock: ResMut<EditLock>,
+    mut clear: EventWriter<ClearBoard>,
+    mut jumps: EventWriter<JumpAhead>,
+    mut single: ResMut<SingleStep>,
 ) {
-    if key.just_pressed(KeyCode::Space) | key.just_pressed(KeyCode::S) {
+    if key.just_pressed(|k| &k.play) {
         state.0 = !state.0;
     }
-    if key.just_pressed(KeyCode::R) {
-        setup(commands);
-        map.vec = vec![vec![false; GRID_HEIGHT as usize]; GRID_WIDTH as usize];
+    if key.just_pressed(|k| &k.lock) {
+        lock.enabled = !lock.enabled;
+    }
+    if key.just_pressed(|k| &k.clear) {
+        clear.send(ClearBoard);
+    }
+    if key.just_pressed(|k| &k.jump) && !state.0 {
+        jumps.send(JumpAhead(JUMP_GENERATIONS));
+    }
+    let ctrl = key.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    if key.just_pressed(|k| &k.step) && !state.0 && !ctrl {
+        single.0 = true;
     }
 }
 
-#[derive(Component)]
-struct  StateText;
+// replaces the board with a saved one, `grid` is the size it was saved at
+pub struct RestoreBoard {
+    pub map: Board,
+    pub generation: u64,
+    pub grid: GridConfig,
+}
+
+// a board of another size is respawned by resize_grid, otherwise the sprites are repainted
+fn restore_board(
+    mut events: EventReader<RestoreBoard>,
+    mut map: ResMut<Board>,
+    mut sprites: CellSprites,
+    mut generation: ResMut<Generation>,
+    mut stats: ResMut<SimStats>,
+    mut grid: ResMut<GridConfig>,
+    mut pending: ResMut<PendingEdits>,
+) {
+    let Some(restore) = events.iter().last() else {
+        return;
+    };
+    pending.0.clear();
+    if restore.grid.board_size() == map.size() {
+        sprites.follow(&restore.map);
+    }
+    // a grid assigned the same size would still have resize_grid respawn every sprite
+    if *grid != restore.grid {
+        *grid = restore.grid;
+    }
+    *map = restore.map.clone();
+    generation.0 = restore.generation;
+    *stats = SimStats {
+        generation: restore.generation,
+        ..default()
+    };
+    stats.edited(&map);
+}
+
+// back to generation 0 of the startup scene, a soup is drawn again from the rng
+pub struct RestartBoard;
+
+// a new soup at generation 0, each cell alive with probability `density`
+pub struct RandomizeBoard {
+    pub density: f64,
+}
+
+fn restart_board(
+    mut restarts: EventReader<RestartBoard>,
+    mut randomizes: EventReader<RandomizeBoard>,
+    scene: Res<StartupScene>,
+    grid: Res<GridConfig>,
+    map: Res<Board>,
+    mut rng: ResMut<SimRng>,
+    mut restores: EventWriter<RestoreBoard>,
+) {
+    let random = randomizes
+        .iter()
+        .last()
+        .map(|r| StartupScene::Soup { density: r.density });
+    if restarts.iter().count() == 0 && random.is_none() {
+        return;
+    }
+    let mut restart = random.as_ref().unwrap_or(&scene).build(&grid, &mut rng);
+    restart.set_boundary(map.boundary());
+    restart.set_rule(map.rule());
+    if let Some(kind) = map.data_kind() {
+        restart.enable_data(kind);
+    }
+    restores.send(RestoreBoard {
+        map: restart,
+        generation: 0,
+        grid: *grid,
+    });
+}
+
+// empties the board and resets everything derived from it
+pub struct ClearBoard;
+
+fn clear_board(
+    mut events: EventReader<ClearBoard>,
+    mut sprites: DirectSprites,
+    mut map: ResMut<Board>,
+    mut generation: ResMut<Generation>,
+    mut paint: ResMut<PaintQueue>,
+    mut stats: ResMut<SimStats>,
+    mut pending: ResMut<PendingEdits>,
+) {
+    if events.iter().count() == 0 {
+        return;
+    }
+    for (_, _, mut cell_grid, mut spr) in sprites.query.iter_mut() {
+        cell_grid.element = Element::Empty;
+        spr.color = sprites.colors.empty;
+    }
+    map.clear();
+    generation.0 = 0;
+    paint.clear();
+    pending.0.clear();
+    *stats = SimStats::default();
+}
+
+// whole-board operation, cells keep their colors when they move
+#[derive(Clone, Copy)]
+pub enum BoardTransform {
+    Shift { by: IVec2, wrap: bool }, // without wrap cells pushed past an edge are lost
+    RotateClockwise, // around the board center, corners of a non-square board are clipped
+    MirrorHorizontal, // left <-> right
+    MirrorVertical,  // top <-> bottom
+}
 
-fn set_text(
+impl BoardTransform {
+    // cell that ends up at (x, y), None if the spot becomes empty
+    fn source(&self, x: u32, y: u32, size: UVec2) -> Option<UVec2> {
+        let (x, y, w, h) = (x as i32, y as i32, size.x as i32, size.y as i32);
+        let (sx, sy) = match *self {
+            BoardTransform::Shift { by, wrap: true } => {
+                ((x - by.x).rem_euclid(w), (y - by.y).rem_euclid(h))
+            }
+            BoardTransform::Shift { by, wrap: false } => (x - by.x, y - by.y),
+            // doubled coordinates keep the center exact on even sizes
+            BoardTransform::RotateClockwise => (
+                ((h - 1) - 2 * y + (w - 1)).div_euclid(2),
+                (2 * x - (w - 1) + (h - 1)).div_euclid(2),
+            ),
+            BoardTransform::MirrorHorizontal => (w - 1 - x, y),
+            BoardTransform::MirrorVertical => (x, h - 1 - y),
+        };
+        if sx < 0 || sy < 0 || sx >= w || sy >= h {
+            return None;
+        }
+        Some(UVec2::new(sx as u32, sy as u32))
+    }
+}
+
+pub struct TransformBoard(pub BoardTransform);
+
+fn transform_board(
+    mut events: EventReader<TransformBoard>,
+    mut sprites: DirectSprites,
+    mut map: ResMut<Board>,
+    mut paint: ResMut<PaintQueue>,
+    mut pending: ResMut<PendingEdits>,
+    mut stats: ResMut<SimStats>,
+    grid: Res<GridConfig>,
+) {
+    // only the area that has cells moves, the reserved top rows stay empty
+    let size = grid.board_size();
+    for TransformBoard(transform) in events.iter() {
+        // settle everything in flight first so the grid and the sprites agree
+        pending.flush(&mut map, &mut stats);
+        for (entity, color) in paint.pop(usize::MAX) {
+            if let Ok((_, _, _, mut spr)) = sprites.query.get_mut(entity) {
+                spr.color = color;
+            }
+        }
+
+        let empty = sprites.colors.empty;
+        let mut colors = vec![vec![empty; size.y as usize]; size.x as usize];
+        for (_, pos, _, spr) in sprites.query.iter() {
+            colors[pos.x as usize][pos.y as usize] = spr.color;
+        }
+        let old = map.clone();
+        for x in 0..size.x {
+            for y in 0..size.y {
+                let src = transform.source(x, y, size);
+                map.set(x, y, src.is_some_and(|s| old.alive(s.x, s.y)));
+                map.set_age(x, y, src.map_or(0, |s| old.age(s.x, s.y)));
+                map.set_dying(x, y, src.map_or(0, |s| old.dying(s.x, s.y)));
+                map.set_data(x, y, src.and_then(|s| old.data(s.x, s.y)).unwrap_or(0.0));
+            }
+        }
+        for (_, pos, mut cell_grid, mut spr) in sprites.query.iter_mut() {
+            cell_grid.element = Element::at(&map, pos.x, pos.y);
+            spr.color = match transform.source(pos.x, pos.y, size) {
+                Some(src) if cell_grid.element != Element::Empty => {
+                    colors[src.x as usize][src.y as usize]
+                }
+                _ => empty,
+            };
+        }
+        stats.edited(&map);
+    }
+}
+
+// T                  -> rotate the board 90 degrees clockwise
+// M / Shift + M      -> mirror left-right / top-bottom
+// Ctrl + arrows      -> shift the board one cell (with wrap, add Shift to clip instead)
+fn handle_transform_keys(key: Keys, mut transforms: EventWriter<TransformBoard>) {
+    let shift = key.any_pressed([KeyCode::LShift, KeyCode::RShift]);
+    let ctrl = key.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    if key.just_pressed(|k| &k.rotate) {
+        transforms.send(TransformBoard(BoardTransform::RotateClockwise));
+    }
+    if key.just_pressed(|k| &k.mirror) {
+        transforms.send(TransformBoard(if shift {
+            BoardTransform::MirrorVertical
+        } else {
+            BoardTransform::MirrorHorizontal
+        }));
+    }
+    if ctrl {
+        for (pressed, by) in [
+            (key.just_pressed(|k| &k.shift_left), IVec2::NEG_X),
+            (key.just_pressed(|k| &k.shift_right), IVec2::X),
+            (key.just_pressed(|k| &k.shift_up), IVec2::Y),
+            (key.just_pressed(|k| &k.shift_down), IVec2::NEG_Y),
+        ] {
+            if pressed {
+                transforms.send(TransformBoard(BoardTransform::Shift { by, wrap: !shift }));
+            }
+        }
+    }
+}
+
+// B -> switch between a finite and a toroidal board
+fn handle_boundary_key(key: Keys, mut boundary: ResMut<Boundary>) {
+    if key.just_pressed(|k| &k.boundary) {
+        *boundary = match *boundary {
+            Boundary::Finite => Boundary::Toroidal,
+            Boundary::Toroidal => Boundary::Finite,
+        };
+    }
+}
+
+fn sync_settings(
+    boundary: Res<Boundary>,
+    rule: Res<Ruleset>,
+    mut map: ResMut<Board>,
+    mut stats: ResMut<SimStats>,
+) {
+    if map.boundary() != *boundary || map.rule() != *rule {
+        map.set_boundary(*boundary);
+        map.set_rule(*rule);
+        // the same cells evolve differently now, old history would mislead the detection
+        stats.edited(&map);
+    }
+}
+
+// = / - -> grow / shrink the grid by 5 cells on each side
+fn handle_grid_keys(key: Keys, mut grid: ResMut<GridConfig>) {
+    let by = if key.just_pressed(|k| &k.grow_grid) {
+        5
+    } else if key.just_pressed(|k| &k.shrink_grid) {
+        -5
+    } else {
+        return;
+    };
+    let resized = grid.resized(by);
+    if resized != *grid {
+        *grid = resized;
+    }
+}
+
+// Home -> crop the board to its live cells and CROP_MARGIN around them, never growing it
+fn crop_board(
+    key: Keys,
+    grid: Res<GridConfig>,
+    map: Res<Board>,
+    stats: Res<SimStats>,
+    generation: Res<Generation>,
+    mut restores: EventWriter<RestoreBoard>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if !key.just_pressed(|k| &k.crop) {
+        return;
+    }
+    let Some((min, max)) = stats.bounding_box else {
+        toasts.send(Toast("nothing to crop to, the board is empty".to_string()));
+        return;
+    };
+    let alive = max - min + UVec2::ONE;
+    let wanted = (alive + UVec2::splat(2 * CROP_MARGIN)).max(UVec2::splat(MIN_GRID));
+    let mut cropped = GridConfig {
+        width: wanted.x.min(grid.width),
+        height: wanted.y.min(grid.height),
+    };
+    // part of the height goes to the rows kept for the text
+    while cropped.height < grid.height && cropped.board_size().y < wanted.y {
+        cropped.height += 1;
+    }
+    if cropped == *grid {
+        toasts.send(Toast("the board is already cropped".to_string()));
+        return;
+    }
+    let size = cropped.board_size();
+    // the live cells end up in the middle
+    let offset = (size.as_ivec2() - alive.as_ivec2()) / 2 - min.as_ivec2();
+    restores.send(RestoreBoard {
+        map: map.resized(size, offset),
+        generation: generation.0,
+        grid: cropped,
+    });
+    toasts.send(Toast(format!("cropped to {}x{}", size.x, size.y)));
+}
+
+// respawns every cell for the new dimensions, the middle of the board is kept
+fn resize_grid(
     mut commands: Commands,
-    asset_server: Res<AssetServer>
+    grid: Res<GridConfig>,
+    sprites: DirectSprites,
+    mut map: ResMut<Board>,
+    mut paint: ResMut<PaintQueue>,
+    mut pending: ResMut<PendingEdits>,
+    mut stats: ResMut<SimStats>,
 ) {
+    // the startup cells are spawned by `setup`
+    if !grid.is_changed() || grid.is_added() {
+        return;
+    }
+    pending.flush(&mut map, &mut stats);
+    paint.clear();
+    for (entity, ..) in sprites.query.iter() {
+        commands.entity(entity).despawn();
+    }
+    // keep the content centered, growing adds room on every side
+    let offset = (grid.board_size().as_ivec2() - map.size().as_ivec2()) / 2;
+    *map = map.resized(grid.board_size(), offset);
+    spawn_cells(&mut commands, &grid, &map, &sprites.colors);
+    stats.edited(&map);
+}
+
+#[derive(Component)]
+struct StateText;
+
+fn set_text(mut commands: Commands, asset_server: Res<AssetServer>) {
     let font = asset_server.load("Kid Marker.ttf");
     commands.spawn((
         TextBundle::from_sections([
@@ -266,20 +1736,107 @@ fn set_text(
                 font_size: 25.0,
                 color: Color::RED,
             }),
+            TextSection::from_style(TextStyle {
+                font: font.clone(),
+                font_size: 25.0,
+                color: Color::GRAY,
+            }),
+            TextSection::from_style(TextStyle {
+                font: font.clone(),
+                font_size: 25.0,
+                color: Color::YELLOW,
+            }),
         ]),
         StateText,
+        HudElement,
     ));
 }
 
+// modes and modifiers that get a tag in the HUD
+#[derive(SystemParam)]
+struct HudModes<'w, 's> {
+    tool: Res<'w, Tool>,
+    brush: Res<'w, Brush>,
+    shaping: Res<'w, Shaping>,
+    spray: Res<'w, Spray>,
+    drift: Res<'w, Drift>,
+    lock: ResMut<'w, EditLock>,
+    boundary: Res<'w, Boundary>,
+    rule: Res<'w, Ruleset>,
+    plugin_rules: Res<'w, PluginRules>,
+    edge: Res<'w, EdgeAlarm>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
 fn text_update_system(
     state: Res<State>,
-    mut query: Query<&mut Text, With<StateText>>
+    stats: Res<SimStats>,
+    speed: Res<Speed>,
+    time: Res<Time>,
+    mut modes: HudModes,
+    mut query: Query<&mut Text, With<StateText>>,
 ) {
+    let HudModes {
+        tool,
+        brush,
+        shaping,
+        spray,
+        drift,
+        lock,
+        boundary,
+        rule,
+        plugin_rules,
+        edge,
+        ..
+    } = &mut modes;
+    lock.warning.tick(time.delta());
     for mut text in &mut query {
         if state.0 {
-            text.sections[1].value = format!("Playing");
+            text.sections[1].value = format!("Playing ({})", speed.label());
         } else {
-            text.sections[1].value = format!("Stopped");
+            text.sections[1].value = "Stopped".to_string();
+        }
+        text.sections[2].value = match stats.extinct_at {
+            Some(generation) => format!("  extinct at generation {}", generation),
+            // the change since the previous generation shows whether the board grows or shrinks
+            None => format!(
+                "  gen {}  pop {} ({:+})",
+                stats.generation,
+                stats.population,
+                stats.births as i64 - stats.deaths as i64
+            ),
+        };
+        text.sections[2]
+            .value
+            .push_str(&stats.stabilization.label());
+        text.sections[2].value.push_str(tool.label());
+        if **tool == Tool::Spray {
+            text.sections[2]
+                .value
+                .push_str(&format!(" {:.0}%", spray.density * 100.0));
+        }
+        if **tool == Tool::Draw {
+            text.sections[2].value.push_str(&brush.label());
+        }
+        if **tool == Tool::Shape {
+            text.sections[2].value.push_str(shaping.kind.label());
         }
+        if drift.enabled {
+            text.sections[2].value.push_str("  [drift]");
+        }
+        text.sections[2].value.push_str(&plugin_rules.label(rule));
+        text.sections[2].value.push_str(edge.label());
+        if **boundary == Boundary::Toroidal {
+            text.sections[2].value.push_str("  [torus]");
+        }
+        if lock.enabled {
+            text.sections[2].value.push_str("  [locked]");
+        }
+        text.sections[3].value = if lock.warning.finished() {
+            String::new()
+        } else {
+            "  pause or hold Alt to edit".to_string()
+        };
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,70 @@
+use bevy::prelude::UVec2;
+
+use crate::rle::Pattern;
+
+// rustfmt's default width, long lists are wrapped to stay under it
+const LINE_WIDTH: usize = 100;
+
+// `items` four spaces in, as many per line as fit
+fn wrapped(items: &[String]) -> String {
+    let mut text = String::new();
+    let mut line = String::from("   ");
+    for item in items {
+        if line.len() + item.len() + 2 > LINE_WIDTH {
+            text.push_str(&line);
+            text.push('\n');
+            line = String::from("   ");
+        }
+        line.push(' ');
+        line.push_str(item);
+        line.push(',');
+    }
+    text.push_str(&line);
+    text.push('\n');
+    text
+}
+
+// live cells as (x, y), y up like on the board
+fn coordinates(pattern: &Pattern, rule: &str) -> String {
+    let mut cells = pattern.alive.clone();
+    cells.sort_by_key(|c| (c.y, c.x));
+    let items: Vec<String> = cells
+        .iter()
+        .map(|c| format!("({}, {})", c.x, c.y))
+        .collect();
+    format!(
+        "// {}x{}, {}, live cells as (x, y) with (0, 0) the bottom left one\npub const PATTERN: [(u32, u32); {}] = [\n{}];\n",
+        pattern.size.x,
+        pattern.size.y,
+        rule,
+        items.len(),
+        wrapped(&items)
+    )
+}
+
+// one string per row like a plaintext .cells file, the top row first
+fn rows(pattern: &Pattern, rule: &str) -> String {
+    let UVec2 { x: w, y: h } = pattern.size;
+    let mut rows = vec![vec!['.'; w as usize]; h as usize];
+    for cell in pattern.alive.iter() {
+        rows[(h - 1 - cell.y) as usize][cell.x as usize] = 'O';
+    }
+    let rows: String = rows
+        .iter()
+        .map(|row| format!("    \"{}\",\n", row.iter().collect::<String>()))
+        .collect();
+    format!(
+        "// {}x{}, {}, 'O' alive and '.' dead, the top row first\npub const PATTERN: [&str; {}] = [\n{}];\n",
+        w, h, rule, h, rows
+    )
+}
+
+// the pattern as a Rust const to paste into a program using the library,
+// whichever of the two forms is shorter: coordinates for sparse patterns, rows for dense ones
+pub fn to_rust(pattern: &Pattern, rule: &str) -> String {
+    let (coordinates, rows) = (coordinates(pattern, rule), rows(pattern, rule));
+    match coordinates.len() <= rows.len() {
+        true => coordinates,
+        false => rows,
+    }
+}
@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+use crate::panel::BesidePanel;
+use crate::sim::{Keys, SimStats};
+
+// generations kept for the plot, one bar each
+const HISTORY: usize = 150;
+const PLOT_HEIGHT: f32 = 50.0;
+const BAR_WIDTH: f32 = 2.0;
+
+// population per generation, newest first, recorded even while the plot is hidden
+#[derive(Resource, Default)]
+struct PopulationHistory {
+    show: bool,
+    generation: Option<u64>, // of the newest sample
+    samples: VecDeque<usize>,
+}
+
+#[derive(Component)]
+struct SparklinePanel;
+
+#[derive(Component)]
+struct SparklineText;
+
+// the i-th newest sample
+#[derive(Component)]
+struct SparkBar(usize);
+
+pub struct SparklinePlugin;
+
+impl Plugin for SparklinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PopulationHistory>()
+            .add_startup_system(spawn_panel)
+            .add_system(handle_keyboard)
+            // after the generation has been stepped
+            .add_system_to_stage(CoreStage::PostUpdate, sample)
+            .add_system_to_stage(CoreStage::PostUpdate, update_panel.after(sample));
+    }
+}
+
+fn spawn_panel(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(10.0),
+                        right: Val::Px(10.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                visibility: Visibility { is_visible: false },
+                ..default()
+            },
+            SparklinePanel,
+            BesidePanel,
+        ))
+        .with_children(|panel| {
+            // the range above the plot
+            panel.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("Kid Marker.ttf"),
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                SparklineText,
+            ));
+            panel
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(HISTORY as f32 * BAR_WIDTH), Val::Px(PLOT_HEIGHT)),
+                        align_items: AlignItems::FlexStart,
+                        flex_direction: FlexDirection::RowReverse,
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                    ..default()
+                })
+                .with_children(|plot| {
+                    // newest on the right
+                    for i in 0..HISTORY {
+                        plot.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    size: Size::new(Val::Px(BAR_WIDTH), Val::Px(0.0)),
+                                    ..default()
+                                },
+                                background_color: Color::rgb(0.4, 1.0, 0.5).into(),
+                                ..default()
+                            },
+                            SparkBar(i),
+                        ));
+                    }
+                });
+        });
+}
+
+// H -> show/hide the population history
+fn handle_keyboard(key: Keys, mut history: ResMut<PopulationHistory>) {
+    let shift = key.any_pressed([KeyCode::LShift, KeyCode::RShift]);
+    if !shift && key.just_pressed(|k| &k.sparkline) {
+        history.show = !history.show;
+    }
+}
+
+fn sample(stats: Res<SimStats>, mut history: ResMut<PopulationHistory>) {
+    if !stats.is_changed() {
+        return;
+    }
+    match history.generation {
+        // edits while paused replace the newest sample
+        Some(generation) if generation == stats.generation => {
+            if history.samples.front() != Some(&stats.population) {
+                history.samples[0] = stats.population;
+            }
+            return;
+        }
+        // a cleared or restored board starts a new history
+        Some(generation) if generation > stats.generation => history.samples.clear(),
+        _ => {}
+    }
+    history.generation = Some(stats.generation);
+    history.samples.push_front(stats.population);
+    history.samples.truncate(HISTORY);
+}
+
+fn update_panel(
+    history: Res<PopulationHistory>,
+    mut panel: Query<&mut Visibility, With<SparklinePanel>>,
+    mut text: Query<&mut Text, With<SparklineText>>,
+    mut bars: Query<(&SparkBar, &mut Style)>,
+) {
+    if !history.is_changed() {
+        return;
+    }
+    for mut visibility in panel.iter_mut() {
+        visibility.is_visible = history.show;
+    }
+    let Ok(mut text) = text.get_single_mut() else {
+        return;
+    };
+    if !history.show {
+        return;
+    }
+    // scaled to the highest population in view, so small soups still fill the plot
+    let max = history.samples.iter().copied().max().unwrap_or(0);
+    let min = history.samples.iter().copied().min().unwrap_or(0);
+    text.sections[0].value = format!("population {} - {}", min, max);
+    for (bar, mut style) in bars.iter_mut() {
+        let population = history.samples.get(bar.0).copied().unwrap_or(0);
+        let height = match max {
+            0 => 0.0,
+            max => population as f32 / max as f32 * PLOT_HEIGHT,
+        };
+        style.size.height = Val::Px(height);
+    }
+}
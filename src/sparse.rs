@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+
+use crate::board::{Board, Boundary, FrozenRegions, SimBackend};
+use crate::rule::Ruleset;
+
+// only the live cells, a step costs about the population whatever the size of the board
+#[derive(Clone, Default)]
+pub struct SparseBoard {
+    pub alive: HashSet<IVec2>,
+    pub rule: Ruleset,
+    pub bounds: Option<(UVec2, Boundary)>, // None is the unbounded plane
+}
+
+impl SparseBoard {
+    fn from_map(map: &Board) -> Self {
+        let size = map.size();
+        let alive = (0..size.x)
+            .flat_map(|x| (0..size.y).map(move |y| (x, y)))
+            .filter(|&(x, y)| map.alive(x, y))
+            .map(|(x, y)| UVec2::new(x, y).as_ivec2())
+            .collect();
+        SparseBoard {
+            alive,
+            rule: map.rule(),
+            bounds: Some((size, map.boundary())),
+        }
+    }
+
+    // `cell` moved back onto the board, None if it fell off a finite one
+    fn place(&self, cell: IVec2) -> Option<IVec2> {
+        match self.bounds {
+            None => Some(cell),
+            Some((size, Boundary::Toroidal)) => Some(IVec2::new(
+                cell.x.rem_euclid(size.x as i32),
+                cell.y.rem_euclid(size.y as i32),
+            )),
+            Some((size, Boundary::Finite)) => {
+                (cell.cmpge(IVec2::ZERO).all() && cell.cmplt(size.as_ivec2()).all()).then_some(cell)
+            }
+        }
+    }
+
+    pub fn step(&self) -> SparseBoard {
+        // every live cell adds one to each of its neighbours, cells nobody counted stay dead
+        let mut counts: HashMap<IVec2, u8> = HashMap::default();
+        for &cell in self.alive.iter() {
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    if (dx, dy) == (0, 0) {
+                        continue;
+                    }
+                    if let Some(n) = self.place(cell + IVec2::new(dx, dy)) {
+                        *counts.entry(n).or_default() += 1;
+                    }
+                }
+            }
+        }
+        // live cells without neighbours are not in `counts`, but a rule with S0 keeps them
+        let survivors = self
+            .alive
+            .iter()
+            .filter(|cell| !counts.contains_key(*cell) && self.rule.next_state(true, 0))
+            .copied();
+        let alive = counts
+            .iter()
+            .filter(|(cell, &n)| self.rule.next_state(self.alive.contains(*cell), n))
+            .map(|(&cell, _)| cell)
+            .chain(survivors)
+            .collect();
+        SparseBoard {
+            alive,
+            rule: self.rule,
+            bounds: self.bounds,
+        }
+    }
+}
+
+// SparseBoard behind the backend trait, the set is kept between calls like HashLife's tree
+// while nobody edits the board the set is the state and the dense board only follows it: stepped
+// in place, a generation costs the population plus the cells that changed, not the area
+#[derive(Default)]
+pub struct SparseBackend {
+    last: Option<(u64, UVec2, SparseBoard)>, // state hash and size of the last returned board
+}
+
+impl SimBackend for SparseBackend {
+    fn supports(&self, map: &Board, frozen: &FrozenRegions) -> bool {
+        map.rule().decay_states() == 0
+            && map.rule().is_totalistic()
+            && !map.has_data()
+            && frozen.regions.is_empty()
+            && frozen.active.is_none()
+    }
+
+    fn advance(&mut self, map: &Board, frozen: &FrozenRegions, generations: u64) -> Board {
+        let mut next = map.clone();
+        self.advance_in_place(&mut next, frozen, generations);
+        next
+    }
+
+    fn advance_in_place(&mut self, map: &mut Board, _frozen: &FrozenRegions, generations: u64) {
+        let size = map.size();
+        let mut board = match self.last.take() {
+            Some((hash, last_size, board))
+                if hash == map.state_hash()
+                    && last_size == size
+                    && board.rule == map.rule()
+                    && board.bounds == Some((size, map.boundary())) =>
+            {
+                board
+            }
+            _ => SparseBoard::from_map(map),
+        };
+        let prev = board.alive.clone();
+        let aged = generations.min(u32::MAX as u64) as u32;
+        for _ in 0..generations {
+            if board.alive.is_empty() {
+                break;
+            }
+            board = board.step();
+        }
+        // only the cells that changed are written, `set` zeroes their ages
+        for cell in prev.difference(&board.alive) {
+            map.set(cell.x as u32, cell.y as u32, false);
+        }
+        for cell in board.alive.iter() {
+            let (x, y) = (cell.x as u32, cell.y as u32);
+            match prev.contains(cell) {
+                true => map.set_age(x, y, map.age(x, y).saturating_add(aged)),
+                false => map.set(x, y, true),
+            }
+        }
+        self.last = Some((map.state_hash(), size, board));
+    }
+}
@@ -0,0 +1,144 @@
+use bevy::ecs::schedule::ShouldRun;
+use bevy::prelude::*;
+use bevy::utils::Duration;
+
+use crate::sim::Keys;
+
+// generations per second for keys 1..5, None runs as many as fit in a frame, see Governor
+const PRESETS: [Option<f32>; 5] = [Some(1.0), Some(5.0), Some(15.0), Some(60.0), None];
+const TAP_RESET: f64 = 2.0; // seconds without a tap before a new tempo starts
+const TAP_HISTORY: usize = 4;
+// the slowest the window may get while generations run flat out
+const MIN_FPS: f32 = 30.0;
+
+// how often a generation is stepped
+#[derive(Resource)]
+pub struct Speed {
+    pub rate: Option<f32>, // generations per second, None = flat out
+    timer: Timer,
+    taps: Vec<f64>,
+}
+
+impl Speed {
+    pub fn new(rate: Option<f32>) -> Self {
+        let mut speed = Speed {
+            rate: None,
+            timer: Timer::default(),
+            taps: Vec::new(),
+        };
+        speed.set(rate);
+        speed
+    }
+
+    // a rate that is not a positive number of generations per second is refused, the old one stays
+    pub fn set(&mut self, rate: Option<f32>) {
+        if let Some(bad) = rate.filter(|rate| !(rate.is_finite() && *rate > 0.0)) {
+            warn!("ignoring a speed of {} generations per second", bad);
+            return;
+        }
+        self.rate = rate;
+        if let Some(rate) = rate {
+            self.timer = Timer::from_seconds(1.0 / rate, TimerMode::Repeating);
+        }
+    }
+
+    // the first preset faster than the current rate, after max back to the slowest
+    pub fn next_preset(&mut self) {
+        let next = match self.rate {
+            Some(rate) => PRESETS.into_iter().find(|p| !p.is_some_and(|p| p <= rate)),
+            None => None,
+        };
+        self.set(next.unwrap_or(PRESETS[0]));
+    }
+
+    pub fn label(&self) -> String {
+        match self.rate {
+            Some(rate) => format!("{:.0} gen/s", rate),
+            None => "max".to_string(),
+        }
+    }
+}
+
+impl Default for Speed {
+    fn default() -> Self {
+        Speed::new(Some(40.0))
+    }
+}
+
+// wall-clock time the max speed and jumps spend stepping in each frame, so however slow a
+// generation is the window doesn't drop below MIN_FPS (it always gets one generation though)
+#[derive(Resource, Default)]
+pub struct Governor {
+    spent: Duration, // this frame
+    last: Duration,  // the frame before
+}
+
+impl Governor {
+    // a frame at MIN_FPS, minus what the last one took besides stepping and what this one
+    // already stepped
+    pub fn budget(&self, time: &Time) -> Duration {
+        let rest = time.delta().saturating_sub(self.last);
+        Duration::from_secs_f32(1.0 / MIN_FPS)
+            .saturating_sub(rest)
+            .saturating_sub(self.spent)
+    }
+
+    pub fn spend(&mut self, stepping: Duration) {
+        self.spent += stepping;
+    }
+}
+
+pub struct SpeedPlugin;
+
+impl Plugin for SpeedPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Speed>()
+            .init_resource::<Governor>()
+            .add_system_to_stage(CoreStage::First, next_frame)
+            .add_system(handle_keyboard);
+    }
+}
+
+fn next_frame(mut governor: ResMut<Governor>) {
+    governor.last = std::mem::take(&mut governor.spent);
+}
+
+// run criteria for the generation step
+pub fn generation_due(time: Res<Time>, mut speed: ResMut<Speed>) -> ShouldRun {
+    if speed.rate.is_none() || speed.timer.tick(time.delta()).just_finished() {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+// 1..5 -> 1, 5, 15, 60, max generations per second
+// Tab  -> tap tempo, the generation interval follows the tapping rhythm
+fn handle_keyboard(key: Keys, time: Res<Time>, mut speed: ResMut<Speed>) {
+    for (i, rate) in PRESETS.into_iter().enumerate() {
+        if key.just_pressed(|k| &k.speeds[i]) {
+            speed.set(rate);
+        }
+    }
+
+    if key.just_pressed(|k| &k.tap_tempo) {
+        let now = time.elapsed_seconds_f64();
+        if speed
+            .taps
+            .last()
+            .is_some_and(|&last| now - last > TAP_RESET)
+        {
+            speed.taps.clear();
+        }
+        speed.taps.push(now);
+        if speed.taps.len() > TAP_HISTORY {
+            speed.taps.remove(0);
+        }
+        // average interval between the remembered taps
+        let intervals = speed.taps.len() - 1;
+        if intervals > 0 {
+            let interval = (now - speed.taps[0]) / intervals as f64;
+            speed.set(Some((1.0 / interval) as f32));
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::sim::{BoardView, Keys, SetCell, SimRng, Tool};
+
+const SPRAY_RADIUS: i32 = 2;
+const SPRAY_INTERVAL: f32 = 0.1; // seconds between passes while the button is held
+
+// spray-can settings, each pass sets every cell under the brush alive with probability `density`
+#[derive(Resource)]
+pub struct Spray {
+    pub density: f64,
+    timer: Timer,
+}
+
+impl Default for Spray {
+    fn default() -> Self {
+        Spray {
+            density: 0.2,
+            timer: Timer::from_seconds(SPRAY_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+pub struct SprayPlugin;
+
+impl Plugin for SprayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Spray>()
+            .add_system(handle_keyboard)
+            .add_system(handle_mouse);
+    }
+}
+
+// P     -> switch between the draw and spray tools
+// [ / ] -> lower/raise the spray density
+fn handle_keyboard(key: Keys, mut tool: ResMut<Tool>, mut spray: ResMut<Spray>) {
+    if key.just_pressed(|k| &k.spray) {
+        *tool = match *tool {
+            Tool::Spray => Tool::Draw,
+            _ => Tool::Spray,
+        };
+    }
+    if *tool == Tool::Spray {
+        if key.just_pressed(|k| &k.thinner_spray) {
+            spray.density = (spray.density - 0.05).max(0.05);
+        }
+        if key.just_pressed(|k| &k.denser_spray) {
+            spray.density = (spray.density + 0.05).min(1.0);
+        }
+    }
+}
+
+fn handle_mouse(
+    mouse_input: Res<Input<MouseButton>>,
+    view: BoardView,
+    time: Res<Time>,
+    tool: Res<Tool>,
+    mut spray: ResMut<Spray>,
+    mut rng: ResMut<SimRng>,
+    mut sets: EventWriter<SetCell>,
+) {
+    if *tool != Tool::Spray || !mouse_input.pressed(MouseButton::Left) {
+        return;
+    }
+    // the first pass happens right on the click
+    let pass = mouse_input.just_pressed(MouseButton::Left)
+        || spray.timer.tick(time.delta()).just_finished();
+    if !pass {
+        return;
+    }
+    let Some(screen) = view.screen() else {
+        return;
+    };
+    let Some(center) = screen.cursor_to_cell() else {
+        return;
+    };
+    for dx in -SPRAY_RADIUS..=SPRAY_RADIUS {
+        for dy in -SPRAY_RADIUS..=SPRAY_RADIUS {
+            if dx * dx + dy * dy > SPRAY_RADIUS * SPRAY_RADIUS {
+                continue;
+            }
+            let (x, y) = (center.x as i32 + dx, center.y as i32 + dy);
+            if x < 0 || y < 0 || !rng.0.gen_bool(spray.density) {
+                continue;
+            }
+            sets.send(SetCell {
+                x: x as u32,
+                y: y as u32,
+                alive: true,
+            });
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use crate::board::Board;
+
+// room for each seed to grow before it runs into its neighbours
+const SEED_SPACING: u32 = 12;
+
+// the R-pentomino, top row first, in its 3x3 box
+const R_PENTOMINO: [(u32, u32); 5] = [(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)];
+
+// worst cases for the engines and the renderer, the same board every time for a given size
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stress {
+    Stripes, // every other column alive, half the board alive forever
+    Checker, // a checkerboard, every cell changes on the first step
+    Seeds,   // a lattice of R-pentominoes in turning orientations, chaos everywhere for long
+}
+
+impl Stress {
+    pub fn parse(name: &str) -> Option<Stress> {
+        match name {
+            "stripes" => Some(Stress::Stripes),
+            "checker" => Some(Stress::Checker),
+            "seeds" => Some(Stress::Seeds),
+            _ => None,
+        }
+    }
+
+    pub fn fill(&self, map: &mut Board) {
+        let board = map.size();
+        match self {
+            Stress::Stripes => {
+                for x in (0..board.x).step_by(2) {
+                    for y in 0..board.y {
+                        map.set(x, y, true);
+                    }
+                }
+            }
+            Stress::Checker => {
+                for x in 0..board.x {
+                    for y in (x % 2..board.y).step_by(2) {
+                        map.set(x, y, true);
+                    }
+                }
+            }
+            Stress::Seeds => {
+                let columns = board.x / SEED_SPACING;
+                let rows = board.y / SEED_SPACING;
+                for i in 0..columns {
+                    for j in 0..rows {
+                        // the orientations don't line up, so hashlife can't reuse whole rows
+                        let turns = (i * 7 + j * 3) % 4;
+                        for &(x, y) in R_PENTOMINO.iter() {
+                            let (mut x, mut y) = (x, y);
+                            for _ in 0..turns {
+                                (x, y) = (2 - y, x);
+                            }
+                            map.set(i * SEED_SPACING + x, (j + 1) * SEED_SPACING - 1 - y, true);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
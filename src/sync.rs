@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use std::fs;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Mutex;
+
+use crate::library::{self, Library, SyncIndex, INDEX_FILE};
+use crate::settings::Settings;
+use crate::toast::Toast;
+
+// the download runs on its own thread, the window keeps drawing
+#[derive(Resource, Default)]
+struct Download(Option<Mutex<Receiver<Result<String, String>>>>);
+
+pub struct SyncPlugin;
+
+impl Plugin for SyncPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Download>()
+            .add_system(start_download)
+            .add_system(finish_download.after(start_download));
+    }
+}
+
+fn fetch(url: &str) -> Result<String, String> {
+    let response = ureq::get(url)
+        .call()
+        .map_err(|e| format!("could not download {}: {}", url, e))?;
+    response
+        .into_string()
+        .map_err(|e| format!("could not download {}: {}", url, e))
+}
+
+// one download at a time, the url is only read when it is asked for
+fn start_download(
+    mut syncs: EventReader<SyncIndex>,
+    mut download: ResMut<Download>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if syncs.iter().count() == 0 || download.0.is_some() {
+        return;
+    }
+    let Some(url) = Settings::load()
+        .get("library_index_url")
+        .map(str::to_string)
+    else {
+        toasts.send(Toast(
+            "set library_index_url in settings.txt to sync the pattern index".to_string(),
+        ));
+        return;
+    };
+    toasts.send(Toast(format!("downloading {}", url)));
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(fetch(&url));
+    });
+    download.0 = Some(Mutex::new(receiver));
+}
+
+// an index that doesn't parse is not written, the last good one stays
+fn finish_download(
+    mut download: ResMut<Download>,
+    mut library: ResMut<Library>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let Some(receiver) = download.0.as_ref() else {
+        return;
+    };
+    let result = match receiver.lock().map(|r| r.try_recv()) {
+        Ok(Ok(result)) => result,
+        Ok(Err(mpsc::TryRecvError::Empty)) => return,
+        _ => Err("the download stopped".to_string()),
+    };
+    download.0 = None;
+    let synced = result.and_then(|text| {
+        let entries = library::read_index(&text)?;
+        fs::write(INDEX_FILE, &text)
+            .map_err(|e| format!("could not write {}: {}", INDEX_FILE, e))?;
+        Ok(entries)
+    });
+    match synced {
+        Ok(entries) => {
+            toasts.send(Toast(format!(
+                "{} patterns in the synced index",
+                entries.len()
+            )));
+            library.replace_index(entries);
+        }
+        Err(e) => toasts.send(Toast(e)),
+    }
+}
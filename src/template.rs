@@ -0,0 +1,55 @@
+use std::fs;
+use std::path::Path;
+
+const CARGO_TOML: &str = include_str!("../templates/rule/Cargo.toml.in");
+const LIB_RS: &str = include_str!("../templates/rule/lib.rs.in");
+// where --new-rule-template puts the crates, each in a directory of its own
+pub const RULES_DIR: &str = "rules";
+
+// "brians_brain" -> "BriansBrain"
+fn type_name(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map_or(String::new(), |first| {
+                first.to_ascii_uppercase().to_string() + chars.as_str()
+            })
+        })
+        .collect()
+}
+
+// a new rule plugin crate in rules/<name>, ready to build, starting out as plain Life
+// Ok with what to do next, Err if the name is not a crate name or the crate already exists
+pub fn run(name: &str) -> Result<String, String> {
+    let valid = name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && name.starts_with(|c: char| c.is_ascii_lowercase());
+    if !valid {
+        return Err(format!(
+            "rule name must be lowercase letters, digits and _, starting with a letter, got {}",
+            name
+        ));
+    }
+    let dir = Path::new(RULES_DIR).join(name);
+    if dir.exists() {
+        return Err(format!("{} already exists", dir.display()));
+    }
+    let fill = |template: &str| {
+        template
+            .replace("{NAME}", name)
+            .replace("{TYPE}", &type_name(name))
+    };
+    let write = |path: &Path, text: String| {
+        fs::write(path, text).map_err(|e| format!("could not write {}: {}", path.display(), e))
+    };
+    let src = dir.join("src");
+    fs::create_dir_all(&src).map_err(|e| format!("could not create {}: {}", src.display(), e))?;
+    write(&dir.join("Cargo.toml"), fill(CARGO_TOML))?;
+    write(&src.join("lib.rs"), fill(LIB_RS))?;
+    Ok(format!(
+        "wrote {0}, implement Rule::next in {0}/src/lib.rs, then `cargo build --release` in {0} and copy the library into plugins/",
+        dir.display()
+    ))
+}
@@ -0,0 +1,269 @@
+use bevy::prelude::*;
+
+use crate::hud::HudElement;
+use crate::rule::{PluginRules, Ruleset};
+use crate::settings::Settings;
+use crate::sim::{CellColors, Keys};
+use crate::toast::Toast;
+
+// palette cycling: the hue turns this fast, the cells are repainted this often
+const CYCLE_DEGREES_PER_SECOND: f32 = 60.0;
+const CYCLE_FPS: f32 = 15.0;
+
+// everything a theme paints: the cells, the window behind them and the HUD text
+#[derive(Clone)]
+pub struct Palette {
+    pub name: String,
+    pub colors: CellColors,
+    pub background: Color,
+    // HUD text sections: labels, the play state, modes and stats, warnings
+    pub text: [Color; 4],
+}
+
+const DARK_TEXT: [Color; 4] = [Color::WHITE, Color::RED, Color::GRAY, Color::YELLOW];
+
+fn hex(hex: &str) -> Color {
+    Color::hex(hex).unwrap_or(Color::PINK)
+}
+
+// cycled through with F3 after the configured colors
+fn builtin() -> Vec<Palette> {
+    vec![
+        Palette {
+            name: "classic".to_string(),
+            colors: CellColors {
+                alive: Color::WHITE,
+                empty: Color::BLACK,
+                dying: Color::GRAY,
+                age_gradient: Vec::new(),
+            },
+            background: Color::rgb(0.1, 0.1, 0.1),
+            text: DARK_TEXT,
+        },
+        Palette {
+            name: "solarized".to_string(),
+            colors: CellColors {
+                alive: hex("073642"),
+                empty: hex("fdf6e3"),
+                dying: hex("d33682"),
+                age_gradient: vec![hex("859900"), hex("2aa198"), hex("268bd2")],
+            },
+            background: hex("002b36"),
+            text: [hex("93a1a1"), hex("dc322f"), hex("839496"), hex("b58900")],
+        },
+        Palette {
+            name: "neon".to_string(),
+            colors: CellColors {
+                alive: hex("39ff14"),
+                empty: hex("0a0a14"),
+                dying: hex("ff6ec7"),
+                age_gradient: vec![hex("39ff14"), hex("00e5ff"), hex("ff00e6")],
+            },
+            background: Color::BLACK,
+            text: [hex("00e5ff"), hex("ff00e6"), hex("8080a0"), hex("fff200")],
+        },
+        // Okabe-Ito colors, told apart with any kind of color blindness
+        Palette {
+            name: "colorblind-safe".to_string(),
+            colors: CellColors {
+                alive: Color::BLACK,
+                empty: Color::WHITE,
+                dying: hex("cc79a7"),
+                age_gradient: vec![hex("e69f00"), hex("56b4e9"), hex("0072b2")],
+            },
+            background: Color::rgb(0.05, 0.05, 0.05),
+            text: [Color::WHITE, hex("e69f00"), Color::GRAY, hex("f0e442")],
+        },
+    ]
+}
+
+#[derive(Resource)]
+pub struct Theme {
+    pub palettes: Vec<Palette>, // the first one is what the app was configured with
+    pub current: usize,
+}
+
+impl Theme {
+    pub fn new(colors: CellColors, background: Color) -> Self {
+        let configured = Palette {
+            name: "default".to_string(),
+            colors,
+            background,
+            text: DARK_TEXT,
+        };
+        Theme {
+            palettes: std::iter::once(configured).chain(builtin()).collect(),
+            current: 0,
+        }
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palettes[self.current]
+    }
+
+    pub fn next(&mut self) {
+        self.current = (self.current + 1) % self.palettes.len();
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::new(CellColors::default(), Color::rgb(0.05, 0.05, 0.05))
+    }
+}
+
+// "reduced_motion on" in settings.txt, nothing on the board moves that the rule does not move
+#[derive(Resource, Default)]
+pub struct ReducedMotion(pub bool);
+
+// the hue of the cell colors rotating over time while a Generations rule is in use, the dying
+// states fading through it like a demoscene palette
+#[derive(Resource, Default)]
+struct PaletteCycle {
+    on: bool,
+    base: Option<CellColors>, // the colors before cycling, put back when it stops
+    written: Option<CellColors>, // the last rotated ones, anything else came from a new theme
+    hue: f32,
+    since: f32, // seconds since the last repaint
+}
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Theme>()
+            .init_resource::<ReducedMotion>()
+            .init_resource::<PaletteCycle>()
+            .add_startup_system(load_motion)
+            .add_system(handle_keyboard)
+            .add_system(apply_theme.after(handle_keyboard))
+            .add_system(cycle_palette.after(apply_theme));
+    }
+}
+
+fn load_motion(mut motion: ResMut<ReducedMotion>) {
+    motion.0 = Settings::load().get("reduced_motion") == Some("on");
+}
+
+// F3 -> next theme
+// F12 -> palette cycling on/off
+fn handle_keyboard(
+    key: Keys,
+    motion: Res<ReducedMotion>,
+    rule: Res<Ruleset>,
+    mut theme: ResMut<Theme>,
+    mut cycle: ResMut<PaletteCycle>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if key.just_pressed(|k| &k.next_theme) {
+        theme.next();
+        info!("theme {}", theme.palette().name);
+    }
+    if key.just_pressed(|k| &k.palette_cycle) {
+        cycle.on = !cycle.on;
+        let message = match (cycle.on, motion.0, rule.decay_states()) {
+            (false, ..) => "palette cycling off",
+            (true, true, _) => "palette cycling is off with reduced motion",
+            (true, false, 0) => "palette cycling on, for Generations rules",
+            (true, false, _) => "palette cycling on",
+        };
+        toasts.send(Toast(message.to_string()));
+    }
+}
+
+fn rotated(color: Color, degrees: f32) -> Color {
+    match color.as_hsla() {
+        Color::Hsla {
+            hue,
+            saturation,
+            lightness,
+            alpha,
+        } => Color::hsla((hue + degrees) % 360.0, saturation, lightness, alpha),
+        other => other,
+    }
+}
+
+// the empty color stays, it is the board behind the cells
+fn cycle_palette(
+    time: Res<Time>,
+    rule: Res<Ruleset>,
+    motion: Res<ReducedMotion>,
+    mut cycle: ResMut<PaletteCycle>,
+    mut colors: ResMut<CellColors>,
+) {
+    if !cycle.on || motion.0 || rule.decay_states() == 0 {
+        let cycle = &mut *cycle;
+        if let Some(base) = cycle.base.take() {
+            if cycle.written.as_ref() == Some(&*colors) {
+                *colors = base;
+            }
+            cycle.written = None;
+        }
+        return;
+    }
+    if cycle.written.as_ref() != Some(&*colors) {
+        cycle.base = Some(colors.clone());
+    }
+    cycle.since += time.delta_seconds();
+    if cycle.since < 1.0 / CYCLE_FPS {
+        return;
+    }
+    cycle.hue = (cycle.hue + cycle.since * CYCLE_DEGREES_PER_SECOND) % 360.0;
+    cycle.since = 0.0;
+    let Some(base) = cycle.base.as_ref() else {
+        return;
+    };
+    let next = CellColors {
+        alive: rotated(base.alive, cycle.hue),
+        dying: rotated(base.dying, cycle.hue),
+        age_gradient: base
+            .age_gradient
+            .iter()
+            .map(|&color| rotated(color, cycle.hue))
+            .collect(),
+        ..base.clone()
+    };
+    cycle.written = Some(next.clone());
+    *colors = next;
+}
+
+// plugin rules bring their own alive and empty colors without age shading,
+// everything else follows the theme
+fn apply_theme(
+    theme: Res<Theme>,
+    rule: Res<Ruleset>,
+    plugins: Res<PluginRules>,
+    mut colors: ResMut<CellColors>,
+    mut clear: ResMut<ClearColor>,
+    mut texts: Query<&mut Text, With<HudElement>>,
+) {
+    if !theme.is_changed() && !rule.is_changed() {
+        return;
+    }
+    let palette = theme.palette();
+    let next = match plugins.find(&rule) {
+        Some(plugin) => CellColors {
+            alive: plugin.alive,
+            empty: plugin.empty,
+            age_gradient: Vec::new(),
+            ..palette.colors.clone()
+        },
+        None => palette.colors.clone(),
+    };
+    // an unchanged palette would repaint every cell for nothing
+    if colors.alive != next.alive
+        || colors.empty != next.empty
+        || colors.dying != next.dying
+        || colors.age_gradient != next.age_gradient
+    {
+        *colors = next;
+    }
+    if clear.0 != palette.background {
+        clear.0 = palette.background;
+    }
+    for mut text in texts.iter_mut() {
+        for (i, section) in text.sections.iter_mut().enumerate() {
+            section.style.color = palette.text[i.min(palette.text.len() - 1)];
+        }
+    }
+}
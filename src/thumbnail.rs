@@ -0,0 +1,126 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::texture::ImageSampler;
+use bevy::utils::HashMap;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+use crate::board::{Board, StableHasher};
+use crate::pattern;
+use crate::rle::Pattern;
+
+// the longer side of a thumbnail in pixels, smaller patterns get bigger pixels
+const SIDE: u32 = 64;
+// past this many images the cache starts over, the ones still shown are kept by their handles
+const MAX_CACHED: usize = 256;
+
+// white live cells on a transparent square, tinted by whoever shows it
+pub fn render(pattern: &Pattern) -> Image {
+    let longest = pattern.size.max_element().max(1) as f32;
+    let scale = SIDE as f32 / longest;
+    let (width, height) = (
+        ((pattern.size.x as f32 * scale) as u32).max(1),
+        ((pattern.size.y as f32 * scale) as u32).max(1),
+    );
+    // a cell covers at least a pixel, however big the pattern is
+    let span = |from: u32, limit: u32| {
+        let start = ((from as f32 * scale) as u32).min(limit - 1);
+        let end = (((from + 1) as f32 * scale) as u32).clamp(start + 1, limit);
+        start..end
+    };
+    let mut data = vec![0; (width * height * 4) as usize];
+    for cell in pattern.alive.iter() {
+        for x in span(cell.x, width) {
+            // image rows go top to bottom, the pattern's y goes up
+            for y in span(cell.y, height) {
+                let i = (((height - 1 - y) * width + x) * 4) as usize;
+                data[i..i + 4].copy_from_slice(&[255; 4]);
+            }
+        }
+    }
+    let mut image = Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    );
+    image.sampler_descriptor = ImageSampler::nearest();
+    image
+}
+
+// equal for the same cells in any order
+fn content_hash(pattern: &Pattern) -> u64 {
+    let mut cells: Vec<(u32, u32)> = pattern.alive.iter().map(|c| (c.x, c.y)).collect();
+    cells.sort_unstable();
+    let mut hasher = StableHasher::default();
+    (pattern.size.x, pattern.size.y).hash(&mut hasher);
+    cells.hash(&mut hasher);
+    hasher.finish()
+}
+
+// rendered thumbnails by content hash, for the pattern library, the save slot and the explorers
+#[derive(Resource, Default)]
+pub struct Thumbnails(HashMap<u64, Handle<Image>>);
+
+#[derive(SystemParam)]
+pub struct Thumbnailer<'w, 's> {
+    cache: ResMut<'w, Thumbnails>,
+    images: ResMut<'w, Assets<Image>>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl Thumbnailer<'_, '_> {
+    // the image of `pattern` and its size scaled to fit a `fit` pixels square
+    pub fn of(&mut self, pattern: &Pattern, fit: f32) -> (Handle<Image>, Size) {
+        let longest = pattern.size.max_element().max(1) as f32;
+        let scaled = pattern.size.as_vec2() * fit / longest;
+        let size = Size::new(Val::Px(scaled.x.max(1.0)), Val::Px(scaled.y.max(1.0)));
+        let key = content_hash(pattern);
+        if let Some(handle) = self.cache.0.get(&key) {
+            return (handle.clone(), size);
+        }
+        if self.cache.0.len() >= MAX_CACHED {
+            self.cache.0.clear();
+        }
+        let handle = self.images.add(render(pattern));
+        self.cache.0.insert(key, handle.clone());
+        (handle, size)
+    }
+
+    pub fn of_board(&mut self, map: &Board, fit: f32) -> (Handle<Image>, Size) {
+        self.of(&whole_board(map), fit)
+    }
+
+    // an image outside the cache, for a board that changes every frame, see `redraw`
+    pub fn live(&mut self) -> Handle<Image> {
+        let empty = Pattern {
+            size: UVec2::ONE,
+            alive: Vec::new(),
+        };
+        self.images.add(render(&empty))
+    }
+
+    pub fn redraw(&mut self, handle: &Handle<Image>, map: &Board) {
+        if let Some(image) = self.images.get_mut(handle) {
+            *image = render(&whole_board(map));
+        }
+    }
+}
+
+fn whole_board(map: &Board) -> Pattern {
+    pattern::board_pattern(map, (UVec2::ZERO, map.size() - UVec2::ONE))
+}
+
+pub struct ThumbnailPlugin;
+
+impl Plugin for ThumbnailPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Thumbnails>();
+    }
+}
@@ -0,0 +1,642 @@
+use bevy::ecs::system::SystemParam;
+use bevy::input::mouse::MouseWheel;
+use bevy::prelude::*;
+use bevy::ui::{FocusPolicy, UiSystem};
+use bevy::utils::Duration;
+use std::collections::VecDeque;
+
+use crate::board::Board;
+use crate::menu::Screen;
+use crate::pattern::Stamped;
+use crate::sim::{
+    self, GridConfig, Keys, RestoreBoard, SetCell, SimStats, Stabilization, ToggleCell,
+};
+use crate::speed::Speed;
+use crate::toast::Toast;
+
+const BUTTON: Color = Color::rgba(0.15, 0.15, 0.15, 0.8);
+const HOVERED: Color = Color::rgb(0.3, 0.45, 0.25);
+const FONT_SIZE: f32 = 16.0;
+const WIDTH: f32 = 280.0;
+
+const MAX_ENTRIES: usize = 50; // each one keeps a copy of the board
+const ROWS: usize = 12; // shown at once, the wheel scrolls through the rest
+const OBJECT_MERGE: u64 = 10; // objects appearing this close to the previous ones join their entry
+
+// the history played backwards: a full copy of the board every KEYFRAME_EVERY recorded boards and
+// the cells that changed for the ones in between, the oldest copies go past MAX_HISTORY_BYTES
+const KEYFRAME_EVERY: usize = 64;
+const MAX_HISTORY_BYTES: usize = 64 << 20;
+
+enum Kind {
+    Edit(usize), // cells set or toggled by hand
+    Stamp(String),
+    Stable(Stabilization),
+    Extinct,
+    Objects(usize), // more separate objects than the generation before
+}
+
+impl Kind {
+    fn text(&self) -> String {
+        match self {
+            Kind::Edit(1) => "edited a cell".to_string(),
+            Kind::Edit(cells) => format!("edited {} cells", cells),
+            Kind::Stamp(name) => format!("stamped {}", name),
+            Kind::Stable(stabilization) => stabilization.label().trim().to_string(),
+            Kind::Extinct => "extinct".to_string(),
+            Kind::Objects(1) => "a new object".to_string(),
+            Kind::Objects(new) => format!("{} new objects", new),
+        }
+    }
+}
+
+// the board as it was right after the event, to jump back to
+struct Entry {
+    generation: u64,
+    kind: Kind,
+    map: Board,
+    grid: GridConfig,
+}
+
+#[derive(Resource, Default)]
+struct Timeline {
+    show: bool,
+    entries: VecDeque<Entry>, // newest first
+    scroll: usize,            // entries above the first row
+    // what the board was the last time it was looked at
+    generation: u64,
+    objects: usize,
+    evolving: bool,
+    extinct: bool,
+}
+
+impl Timeline {
+    fn push(&mut self, kind: Kind, generation: u64, map: &Board, grid: GridConfig) {
+        self.entries.push_front(Entry {
+            generation,
+            kind,
+            map: map.clone(),
+            grid,
+        });
+        self.entries.truncate(MAX_ENTRIES);
+        self.scroll = 0;
+    }
+}
+
+// a cell that changed state: 0 dead, 1 alive, 1 + k in decay state k
+struct Change {
+    cell: u32, // x * height + y
+    state: u8,
+}
+
+fn cell_state(map: &Board, x: u32, y: u32) -> u8 {
+    match map.alive(x, y) {
+        true => 1,
+        false => match map.dying(x, y) {
+            0 => 0,
+            k => k.saturating_add(1),
+        },
+    }
+}
+
+// the cells that changed since the board before, `generations` later (more than one when a
+// frame stepped several at max speed, they are rewound together as they were shown)
+struct Delta {
+    generations: u64,
+    changes: Vec<Change>,
+}
+
+// a keyframe and the boards recorded after it
+struct Segment {
+    generation: u64, // of the keyframe
+    grid: GridConfig,
+    keyframe: Board,
+    deltas: Vec<Delta>,
+}
+
+impl Segment {
+    fn new(generation: u64, map: &Board, grid: GridConfig) -> Self {
+        Segment {
+            generation,
+            grid,
+            keyframe: map.clone(),
+            deltas: Vec::new(),
+        }
+    }
+
+    fn boards(&self) -> usize {
+        1 + self.deltas.len()
+    }
+
+    // the generation of the newest board
+    fn newest(&self) -> u64 {
+        self.generation + self.deltas.iter().map(|d| d.generations).sum::<u64>()
+    }
+
+    // the cells, their decay and their ages, not the data channels
+    fn bytes(&self) -> usize {
+        let area = self.keyframe.size().x as usize * self.keyframe.size().y as usize;
+        let changes: usize = self.deltas.iter().map(|d| d.changes.len()).sum();
+        area * (2 + std::mem::size_of::<u32>()) + changes * std::mem::size_of::<Change>()
+    }
+
+    // every board of the segment, oldest first, ages counted on as the run did
+    fn unpack(&self) -> Vec<Board> {
+        let height = self.keyframe.size().y;
+        let mut boards = vec![self.keyframe.clone()];
+        for delta in self.deltas.iter() {
+            let prev = &boards[boards.len() - 1];
+            let mut next = prev.clone();
+            for change in delta.changes.iter() {
+                let (x, y) = (change.cell / height, change.cell % height);
+                next.set(x, y, change.state == 1);
+                if change.state > 1 {
+                    next.set_dying(x, y, change.state - 1);
+                }
+            }
+            next.carry_ages(prev, delta.generations);
+            boards.push(next);
+        }
+        boards
+    }
+}
+
+// every generation of the run since the last jump or restore, oldest first, to play backwards
+// with Backspace
+#[derive(Resource, Default)]
+struct History {
+    segments: VecDeque<Segment>,
+    newest: Option<Board>, // what the next generation is diffed against
+    unpacked: Vec<Board>,  // the newest segment while playing backwards
+    bytes: usize,
+    reverse: bool,
+    due: Timer, // the next generation back, at the speed of the forward run
+}
+
+impl History {
+    fn boards(&self) -> usize {
+        self.segments.iter().map(Segment::boards).sum()
+    }
+
+    // from the oldest board to the newest
+    fn generations(&self) -> u64 {
+        match (self.segments.front(), self.segments.back()) {
+            (Some(oldest), Some(newest)) => newest.newest() - oldest.generation + 1,
+            _ => 0,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.segments.clear();
+        self.newest = None;
+        self.unpacked.clear();
+        self.bytes = 0;
+    }
+
+    fn start(&mut self, generation: u64, map: &Board, grid: GridConfig) {
+        let segment = Segment::new(generation, map, grid);
+        self.bytes += segment.bytes();
+        self.segments.push_back(segment);
+        self.newest = Some(map.clone());
+        while self.bytes > MAX_HISTORY_BYTES && self.segments.len() > 1 {
+            if let Some(oldest) = self.segments.pop_front() {
+                self.bytes -= oldest.bytes();
+            }
+        }
+    }
+
+    // a board `generations` after the newest one
+    fn push(&mut self, generations: u64, map: &Board, grid: GridConfig) {
+        self.unpacked.clear();
+        let (Some(segment), Some(prev)) = (self.segments.back_mut(), self.newest.as_ref()) else {
+            return;
+        };
+        let generation = segment.newest() + generations;
+        if segment.boards() >= KEYFRAME_EVERY || prev.size() != map.size() {
+            return self.start(generation, map, grid);
+        }
+        let size = map.size();
+        let mut delta = Vec::new();
+        for x in 0..size.x {
+            for y in 0..size.y {
+                let state = cell_state(map, x, y);
+                if state != cell_state(prev, x, y) {
+                    delta.push(Change {
+                        cell: x * size.y + y,
+                        state,
+                    });
+                }
+            }
+        }
+        self.bytes += delta.len() * std::mem::size_of::<Change>();
+        segment.deltas.push(Delta {
+            generations,
+            changes: delta,
+        });
+        self.newest = Some(map.clone());
+    }
+
+    // a board edited by hand takes the place of the newest generation, as a keyframe
+    fn replace_newest(&mut self, generation: u64, map: &Board, grid: GridConfig) {
+        self.unpacked.clear();
+        if let Some(segment) = self.segments.back_mut() {
+            let before = segment.bytes();
+            if segment.deltas.pop().is_none() {
+                self.segments.pop_back();
+            } else {
+                self.bytes += segment.bytes();
+            }
+            self.bytes -= before;
+        }
+        self.start(generation, map, grid);
+    }
+
+    // drops the newest generation, None with only one left
+    fn step_back(&mut self) -> Option<(u64, Board, GridConfig)> {
+        if self.boards() < 2 {
+            return None;
+        }
+        let segment = self.segments.back()?;
+        if self.unpacked.len() != segment.boards() {
+            self.unpacked = segment.unpack();
+        }
+        self.unpacked.pop();
+        let segment = self.segments.back_mut()?;
+        let before = segment.bytes();
+        if segment.deltas.pop().is_none() {
+            self.segments.pop_back();
+            self.bytes -= before;
+            self.unpacked = self.segments.back()?.unpack();
+        } else {
+            self.bytes = self.bytes + segment.bytes() - before;
+        }
+        let segment = self.segments.back()?;
+        let map = self.unpacked.last()?.clone();
+        self.newest = Some(map.clone());
+        Some((segment.newest(), map, segment.grid))
+    }
+}
+
+#[derive(Component)]
+struct TimelinePanel;
+
+// how deep the history goes and what it takes
+#[derive(Component)]
+struct TimelineTitle;
+
+// row i shows entries[scroll + i]
+#[derive(Component)]
+struct TimelineRow(usize);
+
+// the edits and stamps of this frame
+#[derive(SystemParam)]
+struct Edits<'w, 's> {
+    sets: EventReader<'w, 's, SetCell>,
+    toggles: EventReader<'w, 's, ToggleCell>,
+    stamps: EventReader<'w, 's, Stamped>,
+}
+
+// a click on a row puts the board back the way it was then, paused
+#[derive(SystemParam)]
+struct Jump<'w, 's> {
+    rows: Query<'w, 's, (&'static Interaction, &'static TimelineRow), Changed<Interaction>>,
+    restores: EventWriter<'w, 's, RestoreBoard>,
+    playing: ResMut<'w, sim::State>,
+}
+
+impl Jump<'_, '_> {
+    fn run(&mut self, timeline: &Timeline) {
+        for (interaction, row) in self.rows.iter() {
+            if *interaction != Interaction::Clicked {
+                continue;
+            }
+            if let Some(entry) = timeline.entries.get(timeline.scroll + row.0) {
+                self.restores.send(RestoreBoard {
+                    map: entry.map.clone(),
+                    generation: entry.generation,
+                    grid: entry.grid,
+                });
+                self.playing.0 = false;
+            }
+        }
+    }
+}
+
+pub struct TimelinePlugin;
+
+impl Plugin for TimelinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Timeline>()
+            .init_resource::<History>()
+            .add_startup_system(spawn_panel)
+            .add_system(handle_keyboard)
+            .add_system(play_backwards.after(handle_keyboard))
+            // clicks on the timeline are taken before the board's systems get to see them
+            .add_system_to_stage(CoreStage::PreUpdate, handle_input.after(UiSystem::Focus))
+            // after the stats and the object count are up to date for the frame
+            .add_system_to_stage(CoreStage::Last, record)
+            .add_system_to_stage(CoreStage::Last, record_history)
+            .add_system_to_stage(CoreStage::Last, update_panel.after(record));
+    }
+}
+
+fn spawn_panel(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("Kid Marker.ttf");
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(10.0),
+                        top: Val::Px(200.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(4.0)),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                visibility: Visibility { is_visible: false },
+                ..default()
+            },
+            Interaction::default(),
+            FocusPolicy::Block,
+            TimelinePanel,
+        ))
+        .with_children(|panel| {
+            let style = TextStyle {
+                font: font.clone(),
+                font_size: FONT_SIZE,
+                color: Color::WHITE,
+            };
+            panel.spawn((
+                TextBundle::from_section("Timeline", style.clone()),
+                TimelineTitle,
+            ));
+            for i in 0..ROWS {
+                panel
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(WIDTH), Val::Px(FONT_SIZE + 6.0)),
+                                margin: UiRect::all(Val::Px(1.0)),
+                                padding: UiRect::horizontal(Val::Px(4.0)),
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: BUTTON.into(),
+                            ..default()
+                        },
+                        TimelineRow(i),
+                    ))
+                    .with_children(|row| {
+                        row.spawn(TextBundle::from_section("", style.clone()));
+                    });
+            }
+        });
+}
+
+// F6        -> show/hide the timeline
+// Backspace -> play the recorded generations backwards, pressed again forwards
+fn handle_keyboard(
+    key: Keys,
+    mut timeline: ResMut<Timeline>,
+    mut history: ResMut<History>,
+    mut playing: ResMut<sim::State>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if key.just_pressed(|k| &k.timeline) {
+        timeline.show = !timeline.show;
+    }
+    if !key.just_pressed(|k| &k.reverse) {
+        return;
+    }
+    history.reverse = !history.reverse;
+    playing.0 = !history.reverse;
+    let message = match history.reverse {
+        true => format!(
+            "playing {} generations backwards",
+            history.generations().saturating_sub(1)
+        ),
+        false => "playing forwards".to_string(),
+    };
+    toasts.send(Toast(message));
+}
+
+// a generation back each time one would be stepped forward, at the start of the history the
+// run turns around and plays forwards again, a boomerang
+fn play_backwards(
+    time: Res<Time>,
+    speed: Res<Speed>,
+    mut history: ResMut<History>,
+    mut playing: ResMut<sim::State>,
+    mut restores: EventWriter<RestoreBoard>,
+) {
+    if !history.reverse {
+        return;
+    }
+    // play pressed while going backwards
+    if playing.0 {
+        history.reverse = false;
+        return;
+    }
+    if let Some(rate) = speed.rate {
+        let interval = Duration::from_secs_f32(1.0 / rate);
+        if history.due.duration() != interval {
+            history.due = Timer::new(interval, TimerMode::Repeating);
+        }
+        if !history.due.tick(time.delta()).just_finished() {
+            return;
+        }
+    }
+    match history.step_back() {
+        Some((generation, map, grid)) => restores.send(RestoreBoard {
+            map,
+            generation,
+            grid,
+        }),
+        None => {
+            history.reverse = false;
+            playing.0 = true;
+        }
+    }
+}
+
+// the wheel scrolls the entries while the cursor is over the timeline
+fn handle_input(
+    screen: Res<State<Screen>>,
+    key: Keys,
+    mut mouse: ResMut<Input<MouseButton>>,
+    mut wheel: EventReader<MouseWheel>,
+    mut timeline: ResMut<Timeline>,
+    panels: Query<&Interaction, With<TimelinePanel>>,
+    mut jump: Jump,
+) {
+    if !timeline.show || *screen.current() != Screen::Board {
+        return;
+    }
+    let hovered = panels
+        .iter()
+        .any(|interaction| *interaction != Interaction::None);
+    // Ctrl + scroll sizes the brush
+    let ctrl = key.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    for event in wheel.iter() {
+        if !hovered || ctrl {
+            continue;
+        }
+        let last = timeline.entries.len().saturating_sub(ROWS);
+        timeline.scroll = match event.y > 0.0 {
+            true => timeline.scroll.saturating_sub(1),
+            false => (timeline.scroll + 1).min(last),
+        };
+    }
+    jump.run(&timeline);
+    if hovered {
+        mouse.clear();
+    }
+}
+
+fn record(
+    stats: Res<SimStats>,
+    map: Res<Board>,
+    grid: Res<GridConfig>,
+    mut edits: Edits,
+    mut timeline: ResMut<Timeline>,
+) {
+    let stamp = edits.stamps.iter().last().map(|stamp| stamp.0.clone());
+    let edited = edits.sets.iter().count() + edits.toggles.iter().count();
+    let generation = stats.generation;
+    // a stroke is one entry, and a stamp's cells are not an edit of their own
+    if let Some(name) = stamp {
+        timeline.push(Kind::Stamp(name), generation, &map, *grid);
+    } else if edited > 0 {
+        match timeline.entries.front_mut() {
+            Some(Entry {
+                generation: g,
+                kind: Kind::Edit(cells),
+                ..
+            }) if *g == generation => *cells += edited,
+            _ => timeline.push(Kind::Edit(edited), generation, &map, *grid),
+        }
+    }
+    // edits can reach the board a frame after they were sent, so the copy follows until it steps
+    if map.is_changed() {
+        if let Some(entry) = timeline.entries.front_mut() {
+            let by_hand = matches!(entry.kind, Kind::Edit(_) | Kind::Stamp(_));
+            if by_hand && entry.generation == generation {
+                entry.map = map.clone();
+            }
+        }
+    }
+    // objects drawn by hand aren't counted as new on the next step
+    if generation == timeline.generation {
+        timeline.objects = stats.components;
+        return;
+    }
+    let evolving = stats.stabilization == Stabilization::Evolving;
+    let extinct = stats.extinct_at.is_some();
+    if extinct && !timeline.extinct {
+        timeline.push(Kind::Extinct, generation, &map, *grid);
+    } else if timeline.evolving && !evolving && !extinct {
+        timeline.push(Kind::Stable(stats.stabilization), generation, &map, *grid);
+    }
+    // only while it steps forward, a restored board isn't new objects
+    let stepped = generation == timeline.generation + 1;
+    if stepped && stats.components > timeline.objects {
+        let new = stats.components - timeline.objects;
+        match timeline.entries.front_mut() {
+            Some(Entry {
+                generation: g,
+                kind: Kind::Objects(count),
+                map: copy,
+                ..
+            }) if generation.saturating_sub(*g) <= OBJECT_MERGE => {
+                *count += new;
+                *g = generation;
+                *copy = map.clone();
+            }
+            _ => timeline.push(Kind::Objects(new), generation, &map, *grid),
+        }
+    }
+    timeline.generation = generation;
+    timeline.objects = stats.components;
+    timeline.evolving = evolving;
+    timeline.extinct = extinct;
+}
+
+// a board edited by hand replaces the copy of its generation, the generations the sim stepped
+// this frame are one more board, anything else (a jump, a restore) starts the history over
+fn record_history(
+    stats: Res<SimStats>,
+    map: Res<Board>,
+    grid: Res<GridConfig>,
+    mut history: ResMut<History>,
+) {
+    if history.reverse || !map.is_changed() {
+        return;
+    }
+    let generation = stats.generation;
+    let newest = history.segments.back().map(Segment::newest);
+    match newest {
+        Some(g) if g == generation => history.replace_newest(generation, &map, *grid),
+        Some(g) if stats.stepped > 0 && g + stats.stepped == generation => {
+            history.push(stats.stepped, &map, *grid)
+        }
+        _ => {
+            history.clear();
+            history.start(generation, &map, *grid);
+        }
+    }
+}
+
+// hidden on the menus, newest entry on top
+fn update_panel(
+    timeline: Res<Timeline>,
+    history: Res<History>,
+    screen: Res<State<Screen>>,
+    mut panels: Query<&mut Visibility, With<TimelinePanel>>,
+    mut rows: Query<(&TimelineRow, &Interaction, &mut BackgroundColor, &Children)>,
+    mut titles: Query<&mut Text, With<TimelineTitle>>,
+    mut texts: Query<&mut Text, Without<TimelineTitle>>,
+) {
+    let show = timeline.show && *screen.current() == Screen::Board;
+    for mut visibility in panels.iter_mut() {
+        if visibility.is_visible != show {
+            visibility.is_visible = show;
+        }
+    }
+    if !show {
+        return;
+    }
+    let title = format!(
+        "Timeline  (rewind {} gens, {:.1} MB)",
+        history.generations().saturating_sub(1),
+        history.bytes as f64 / (1 << 20) as f64
+    );
+    for mut text in titles.iter_mut() {
+        if text.sections[0].value != title {
+            text.sections[0].value = title.clone();
+        }
+    }
+    for (row, interaction, mut color, children) in rows.iter_mut() {
+        let entry = timeline.entries.get(timeline.scroll + row.0);
+        let value = entry.map_or(String::new(), |entry| {
+            format!("gen {}  {}", entry.generation, entry.kind.text())
+        });
+        let hovered = *interaction != Interaction::None && entry.is_some();
+        let wanted: BackgroundColor = if hovered { HOVERED } else { BUTTON }.into();
+        if color.0 != wanted.0 {
+            *color = wanted;
+        }
+        let Some(mut text) = children
+            .first()
+            .and_then(|&child| texts.get_mut(child).ok())
+        else {
+            continue;
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
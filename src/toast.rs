@@ -0,0 +1,122 @@
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+
+const TOAST_SECONDS: f32 = 5.0;
+const FONT: &str = "Kid Marker.ttf";
+
+// a recoverable error or notice shown on screen for a few seconds, click to dismiss
+pub struct Toast(pub String);
+
+#[derive(Component)]
+struct ToastNode(Timer);
+
+// column the toasts stack in, bottom center of the window
+#[derive(Resource)]
+struct ToastRoot(Entity);
+
+// font handle checked once for load failures
+#[derive(Resource)]
+struct UiFont {
+    handle: Handle<Font>,
+    reported: bool,
+}
+
+pub struct ToastPlugin;
+
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Toast>()
+            .add_startup_system(setup)
+            .add_system(check_font)
+            .add_system(spawn_toasts)
+            .add_system(expire_toasts);
+    }
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(40.0),
+                    left: Val::Px(0.0),
+                    right: Val::Px(0.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .id();
+    commands.insert_resource(ToastRoot(root));
+    commands.insert_resource(UiFont {
+        handle: asset_server.load(FONT),
+        reported: false,
+    });
+}
+
+// without the font every text in the app is invisible, so at least say so in the log
+fn check_font(
+    asset_server: Res<AssetServer>,
+    mut font: ResMut<UiFont>,
+    mut toasts: EventWriter<Toast>,
+) {
+    if !font.reported && asset_server.get_load_state(&font.handle) == LoadState::Failed {
+        font.reported = true;
+        toasts.send(Toast(format!(
+            "could not load assets/{}, text will not be shown",
+            FONT
+        )));
+    }
+}
+
+fn spawn_toasts(
+    mut commands: Commands,
+    mut events: EventReader<Toast>,
+    root: Res<ToastRoot>,
+    font: Res<UiFont>,
+) {
+    for Toast(message) in events.iter() {
+        warn!("{}", message);
+        let toast = commands
+            .spawn((
+                ButtonBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Px(4.0)),
+                        padding: UiRect::all(Val::Px(8.0)),
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.5, 0.1, 0.1, 0.9).into(),
+                    ..default()
+                },
+                ToastNode(Timer::from_seconds(TOAST_SECONDS, TimerMode::Once)),
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    message.clone(),
+                    TextStyle {
+                        font: font.handle.clone(),
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                    },
+                ));
+            })
+            .id();
+        commands.entity(root.0).add_child(toast);
+    }
+}
+
+fn expire_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut ToastNode, &Interaction)>,
+) {
+    for (entity, mut toast, interaction) in query.iter_mut() {
+        if toast.0.tick(time.delta()).finished() || *interaction == Interaction::Clicked {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::fs;
+
+use crate::board::{Boundary, FrozenRegions, NaiveBackend, ParallelBackend, SimBackend};
+use crate::hashlife::HashLife;
+use crate::rule::Ruleset;
+use crate::sim::{GridConfig, SimRng, StartupScene};
+use crate::sparse::SparseBackend;
+
+// headless runs that record or check the state hash of every generation
+pub enum TraceCommand {
+    Record { path: String, generations: u64 },
+    Verify { path: String },
+}
+
+// what a trace is computed from, the same options the app starts with
+pub struct TraceSetup {
+    pub scene: StartupScene,
+    pub seed: Option<u64>,
+    pub grid: GridConfig,
+    pub boundary: Boundary,
+    pub rule: Ruleset,
+    pub backend: TraceBackend,
+}
+
+// what steps the generations, a trace recorded with one has to verify with the other
+#[derive(Clone, Copy, Default)]
+pub enum TraceBackend {
+    #[default]
+    Naive,
+    HashLife,
+    Sparse,
+    Parallel,
+}
+
+impl TraceBackend {
+    pub fn parse(name: &str) -> Option<TraceBackend> {
+        match name {
+            "naive" => Some(TraceBackend::Naive),
+            "hashlife" => Some(TraceBackend::HashLife),
+            "sparse" => Some(TraceBackend::Sparse),
+            "parallel" => Some(TraceBackend::Parallel),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TraceBackend::Naive => "naive",
+            TraceBackend::HashLife => "hashlife",
+            TraceBackend::Sparse => "sparse",
+            TraceBackend::Parallel => "parallel",
+        }
+    }
+
+    pub fn create(&self) -> Box<dyn SimBackend + Send + Sync> {
+        match self {
+            TraceBackend::Naive => Box::new(NaiveBackend),
+            TraceBackend::HashLife => Box::new(HashLife::default()),
+            TraceBackend::Sparse => Box::new(SparseBackend::default()),
+            TraceBackend::Parallel => Box::new(ParallelBackend),
+        }
+    }
+}
+
+// state hash of generations 0..=generations, starting with the initial board
+fn hashes(setup: &TraceSetup, generations: u64) -> Result<Vec<u64>, String> {
+    let mut rng = SimRng::new(setup.seed);
+    let mut map = setup.scene.build(&setup.grid, &mut rng);
+    map.set_boundary(setup.boundary);
+    map.set_rule(setup.rule);
+    let frozen = FrozenRegions::default();
+    let mut backend = setup.backend.create();
+    if !backend.supports(&map, &frozen) {
+        return Err(
+            "this backend needs a 2-state rule (hashlife also a power of two square board when toroidal)"
+                .to_string(),
+        );
+    }
+    let mut hashes = Vec::new();
+    for _ in 0..=generations {
+        hashes.push(map.state_hash());
+        map = backend.advance(&map, &frozen, 1);
+    }
+    Ok(hashes)
+}
+
+// one "<generation> <hash>" line per generation
+fn to_text(hashes: &[u64]) -> String {
+    let mut text = String::from("# bevy_conway trace\n");
+    for (generation, hash) in hashes.iter().enumerate() {
+        text.push_str(&format!("{} {:016x}\n", generation, hash));
+    }
+    text
+}
+
+fn parse(text: &str) -> Result<Vec<(u64, u64)>, String> {
+    let mut lines = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let bad = || format!("line {}: expected <generation> <hash>", i + 1);
+        let (generation, hash) = line.split_once(' ').ok_or_else(bad)?;
+        lines.push((
+            generation.parse().map_err(|_| bad())?,
+            u64::from_str_radix(hash.trim(), 16).map_err(|_| bad())?,
+        ));
+    }
+    Ok(lines)
+}
+
+// Ok with a summary line, Err with the first divergence or an io/parse error
+pub fn run(command: &TraceCommand, setup: &TraceSetup) -> Result<String, String> {
+    if setup.seed.is_none() && setup.scene.is_random() {
+        return Err("a trace of a random scene needs --seed".to_string());
+    }
+    match command {
+        TraceCommand::Record { path, generations } => {
+            fs::write(path, to_text(&hashes(setup, *generations)?))
+                .map_err(|e| format!("could not write {}: {}", path, e))?;
+            Ok(format!("wrote {} generations to {}", generations, path))
+        }
+        TraceCommand::Verify { path } => {
+            let text =
+                fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path, e))?;
+            let expected = parse(&text).map_err(|e| format!("{}: {}", path, e))?;
+            let Some(&(last, _)) = expected.iter().max_by_key(|(generation, _)| *generation) else {
+                return Err(format!("{} has no generations", path));
+            };
+            let actual = hashes(setup, last)?;
+            for (generation, hash) in expected.iter() {
+                let got = actual[*generation as usize];
+                if got != *hash {
+                    return Err(format!(
+                        "generation {} differs: expected {:016x}, got {:016x}",
+                        generation, hash, got
+                    ));
+                }
+            }
+            Ok(format!("{} generations match {}", expected.len(), path))
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::board::Board;
+use crate::sim::{BoardView, Keys, SimStats, SIZE};
+
+// tint of a cell that just died, it fades out over the next generations
+const TRAIL_COLOR: Color = Color::rgba(1.0, 0.45, 0.1, 0.45);
+// heat kept per generation, a glider's trail is about 15 cells long
+const FADE: f32 = 0.8;
+// below this a cell is cold again
+const MIN_HEAT: f32 = 0.05;
+
+// 1 while a cell is alive, fading by FADE every generation after it died
+#[derive(Resource)]
+struct Heat {
+    show: bool,
+    generation: u64, // of the last update
+    size: UVec2,
+    heat: Vec<f32>, // column-major like the board
+}
+
+impl Default for Heat {
+    fn default() -> Self {
+        Heat {
+            show: true,
+            generation: 0,
+            size: UVec2::ZERO,
+            heat: Vec::new(),
+        }
+    }
+}
+
+// tint over a dead cell that is still warm
+#[derive(Component)]
+struct TrailTint;
+
+// the tint sprite of each warm cell
+#[derive(Resource, Default)]
+struct Tints(HashMap<UVec2, Entity>);
+
+pub struct TrailPlugin;
+
+impl Plugin for TrailPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Heat>()
+            .init_resource::<Tints>()
+            .add_system(handle_keyboard)
+            // after the generation has been stepped
+            .add_system_to_stage(CoreStage::PostUpdate, update_heat)
+            .add_system_to_stage(CoreStage::PostUpdate, draw_trail.after(update_heat));
+    }
+}
+
+// Shift + H -> show/hide the trail of dead cells
+fn handle_keyboard(key: Keys, mut heat: ResMut<Heat>) {
+    let shift = key.any_pressed([KeyCode::LShift, KeyCode::RShift]);
+    if shift && key.just_pressed(|k| &k.trail) {
+        heat.show = !heat.show;
+    }
+}
+
+fn update_heat(map: Res<Board>, stats: Res<SimStats>, mut heat: ResMut<Heat>) {
+    if !map.is_changed() {
+        return;
+    }
+    let size = map.size();
+    // a new board size, a cleared board or a restored earlier generation starts cold
+    if heat.size != size || stats.generation < heat.generation || stats.generation == 0 {
+        heat.size = size;
+        heat.heat = vec![0.0; (size.x * size.y) as usize];
+    }
+    // edits while paused only warm up the new cells, a J jump cools everything down
+    let fade = FADE.powi((stats.generation - heat.generation).min(64) as i32);
+    heat.generation = stats.generation;
+    for x in 0..size.x {
+        for y in 0..size.y {
+            let h = &mut heat.heat[(x * size.y + y) as usize];
+            *h = match map.alive(x, y) {
+                true => 1.0,
+                false if *h * fade < MIN_HEAT => 0.0,
+                false => *h * fade,
+            };
+        }
+    }
+}
+
+fn draw_trail(
+    mut commands: Commands,
+    view: BoardView,
+    map: Res<Board>,
+    heat: Res<Heat>,
+    mut tints: ResMut<Tints>,
+    mut sprites: Query<(&mut Sprite, &mut Transform), With<TrailTint>>,
+) {
+    if !heat.is_changed() {
+        return;
+    }
+    let Some(screen) = view.screen() else {
+        return;
+    };
+    let size = heat.size;
+    let warm = |cell: UVec2| {
+        let inside = cell.x < size.x && cell.y < size.y;
+        let h = if inside {
+            heat.heat[(cell.x * size.y + cell.y) as usize]
+        } else {
+            0.0
+        };
+        // dying cells of a Generations rule already fade out on their own
+        let dead = inside && !map.alive(cell.x, cell.y) && map.dying(cell.x, cell.y) == 0;
+        (heat.show && dead && h > 0.0).then_some(h)
+    };
+    tints.0.retain(|&cell, &mut entity| {
+        let keep = warm(cell).is_some();
+        if !keep {
+            commands.entity(entity).despawn();
+        }
+        keep
+    });
+    if !heat.show {
+        return;
+    }
+    for x in 0..size.x {
+        for y in 0..size.y {
+            let cell = UVec2::new(x, y);
+            let Some(h) = warm(cell) else {
+                continue;
+            };
+            let mut color = TRAIL_COLOR;
+            color.set_a(TRAIL_COLOR.a() * h);
+            let translation = screen.cell_to_world(x as f32, y as f32).extend(0.5);
+            if let Some(&entity) = tints.0.get(&cell) {
+                if let Ok((mut sprite, mut transform)) = sprites.get_mut(entity) {
+                    sprite.color = color;
+                    sprite.custom_size = Some(screen.cell_size() * SIZE);
+                    transform.translation = translation;
+                }
+                continue;
+            }
+            let entity = commands
+                .spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color,
+                            custom_size: Some(screen.cell_size() * SIZE),
+                            ..default()
+                        },
+                        transform: Transform::from_translation(translation),
+                        ..default()
+                    },
+                    TrailTint,
+                ))
+                .id();
+            tints.0.insert(cell, entity);
+        }
+    }
+}
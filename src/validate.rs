@@ -0,0 +1,186 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+use crate::board::{Board, FrozenRegions, SimBackend};
+use crate::sim::{BoardView, SimStats};
+use crate::toast::Toast;
+use crate::trace::TraceBackend;
+
+pub const DIFF_COLOR: Color = Color::rgba(1.0, 0.0, 1.0, 0.8);
+// past this many disagreeing cells only the first ones are highlighted
+pub const MAX_HIGHLIGHTS: usize = 4096;
+
+// --validate <a>,<b>, None runs no validation, the gpu board checks its own readbacks
+#[derive(Resource, Default)]
+pub struct ValidateBackends(pub Option<(TraceBackend, TraceBackend)>);
+
+// every generation stepped by the app is stepped again from the previous board by both backends
+struct Validation {
+    names: [&'static str; 2],
+    backends: [Box<dyn SimBackend + Send + Sync>; 2],
+    last: Option<(u64, Board)>, // generation and board the next step starts from
+    checked: u64,
+    unsupported: bool, // one of the backends can't run the board, generations are skipped meanwhile
+    first_diff: Option<(u64, usize)>, // generation and number of cells of the first disagreement
+    diff: Vec<UVec2>,  // cells the two disagree on in the newest generation
+}
+
+#[derive(Resource, Default)]
+struct ValidationState(Option<Validation>);
+
+#[derive(Component)]
+struct DiffHighlight;
+
+pub struct ValidatePlugin;
+
+impl Plugin for ValidatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ValidateBackends>()
+            .init_resource::<ValidationState>()
+            .add_startup_system(start_validation)
+            // after the generation has been stepped
+            .add_system_to_stage(CoreStage::PostUpdate, validate)
+            .add_system_to_stage(CoreStage::PostUpdate, draw_diff.after(validate))
+            .add_system_to_stage(CoreStage::Last, report_on_exit);
+    }
+}
+
+fn start_validation(backends: Res<ValidateBackends>, mut state: ResMut<ValidationState>) {
+    let Some((a, b)) = backends.0 else {
+        return;
+    };
+    state.0 = Some(Validation {
+        names: [a.name(), b.name()],
+        backends: [a.create(), b.create()],
+        last: None,
+        checked: 0,
+        unsupported: false,
+        first_diff: None,
+        diff: Vec::new(),
+    });
+}
+
+// the cells whose state differs between the two boards
+pub fn disagreeing(a: &Board, b: &Board) -> Vec<UVec2> {
+    let size = a.size();
+    (0..size.x)
+        .flat_map(|x| (0..size.y).map(move |y| UVec2::new(x, y)))
+        .filter(|c| {
+            a.alive(c.x, c.y) != b.alive(c.x, c.y) || a.dying(c.x, c.y) != b.dying(c.x, c.y)
+        })
+        .collect()
+}
+
+// edits, jumps and resizes only move the starting point, a step of one generation is checked
+fn validate(
+    map: Res<Board>,
+    stats: Res<SimStats>,
+    frozen: Res<FrozenRegions>,
+    mut state: ResMut<ValidationState>,
+    mut toasts: EventWriter<Toast>,
+) {
+    // the state only counts as changed when there was a new board, draw_diff relies on that
+    if !map.is_changed() {
+        return;
+    }
+    let Some(v) = state.0.as_mut() else {
+        return;
+    };
+    let last = v.last.replace((stats.generation, map.clone()));
+    let Some(prev) = last
+        .filter(|(generation, _)| *generation + 1 == stats.generation)
+        .map(|(_, prev)| prev)
+        .filter(|prev| prev.size() == map.size())
+    else {
+        return;
+    };
+    let supported = v.backends.iter().all(|b| b.supports(&prev, &frozen));
+    if !supported {
+        if !v.unsupported {
+            toasts.send(Toast(format!(
+                "validation paused, {} or {} can't run this board",
+                v.names[0], v.names[1]
+            )));
+        }
+        v.unsupported = true;
+        return;
+    }
+    v.unsupported = false;
+    let [a, b] = &mut v.backends;
+    let (a, b) = (a.advance(&prev, &frozen, 1), b.advance(&prev, &frozen, 1));
+    v.checked += 1;
+    v.diff = disagreeing(&a, &b);
+    if !v.diff.is_empty() && v.first_diff.is_none() {
+        v.first_diff = Some((stats.generation, v.diff.len()));
+        let report = format!(
+            "{} and {} disagree on {} cells at generation {}",
+            v.names[0],
+            v.names[1],
+            v.diff.len(),
+            stats.generation
+        );
+        warn!("{}", report);
+        toasts.send(Toast(report));
+    }
+}
+
+// the disagreeing cells of the newest generation in magenta
+fn draw_diff(
+    mut commands: Commands,
+    view: BoardView,
+    state: Res<ValidationState>,
+    highlights: Query<Entity, With<DiffHighlight>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    for entity in highlights.iter() {
+        commands.entity(entity).despawn();
+    }
+    let (Some(v), Some(screen)) = (state.0.as_ref(), view.screen()) else {
+        return;
+    };
+    for cell in v.diff.iter().take(MAX_HIGHLIGHTS) {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: DIFF_COLOR,
+                    custom_size: Some(screen.cell_size()),
+                    ..default()
+                },
+                transform: Transform::from_translation(
+                    screen
+                        .cell_to_world(cell.x as f32, cell.y as f32)
+                        .extend(2.0),
+                ),
+                ..default()
+            },
+            DiffHighlight,
+        ));
+    }
+}
+
+// the summary goes to stdout when the app closes
+fn report_on_exit(exits: EventReader<AppExit>, state: Res<ValidationState>) {
+    let (false, Some(v)) = (exits.is_empty(), state.0.as_ref()) else {
+        return;
+    };
+    println!("{}", summary(v.names, v.checked, v.first_diff));
+}
+
+// what is printed on exit, `first_diff` is the generation and number of cells of the first disagreement
+pub fn summary(names: [&str; 2], checked: u64, first_diff: Option<(u64, usize)>) -> String {
+    let verdict = match first_diff {
+        None => "they agreed on every cell".to_string(),
+        Some((generation, cells)) => {
+            format!(
+                "they first disagreed at generation {} on {} cells",
+                generation, cells
+            )
+        }
+    };
+    format!(
+        "validated {} generations of {} against {}, {}",
+        checked, names[0], names[1], verdict
+    )
+}
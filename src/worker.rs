@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::sync::Mutex;
+
+use crate::board::{Board, Boundary, FrozenRegions};
+use crate::rule::Ruleset;
+use crate::trace::TraceBackend;
+
+// generations the worker may get ahead of the board on screen
+const AHEAD: usize = 2;
+
+enum Command {
+    // go on from this board, everything stepped before it is stale
+    Start {
+        run: u64,
+        map: Box<Board>,
+        frozen: FrozenRegions,
+    },
+    Stop,
+}
+
+// steps the board on its own thread while playing, so a slow generation never holds up a frame
+// the worker runs on from the last board it was given and hands each generation back through a
+// bounded channel, an edit on the main side starts it again from the edited board
+pub struct Worker {
+    commands: Sender<Command>,
+    generations: Mutex<Receiver<(u64, Board)>>,
+    run: u64,                 // the newest Start, generations of older runs are dropped
+    follows: Option<Stepped>, // the board the worker's next generation follows
+}
+
+// what tells a board the worker stepped from one changed by an edit, a resize or the settings
+type Stepped = (u64, UVec2, Ruleset, Boundary);
+
+fn stepped(map: &Board) -> Stepped {
+    (map.state_hash(), map.size(), map.rule(), map.boundary())
+}
+
+impl Worker {
+    // wasm has no threads, the board is stepped in the frame there (see speed::Governor)
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn spawn(backend: TraceBackend) -> Self {
+        let (commands, inbox) = mpsc::channel();
+        let (outbox, generations) = mpsc::sync_channel(AHEAD);
+        std::thread::spawn(move || run(backend, inbox, outbox));
+        Worker {
+            commands,
+            generations: Mutex::new(generations),
+            run: 0,
+            follows: None,
+        }
+    }
+
+    // the next generation of `map` if the worker has it ready, None while it is still stepping
+    // `map` changed by something else than the worker since its last generation starts it again
+    pub fn next(&mut self, map: &Board, frozen: &FrozenRegions, restart: bool) -> Option<Board> {
+        if restart || self.follows != Some(stepped(map)) {
+            self.run += 1;
+            self.follows = Some(stepped(map));
+            let start = Command::Start {
+                run: self.run,
+                map: Box::new(map.clone()),
+                frozen: frozen.clone(),
+            };
+            if self.commands.send(start).is_err() {
+                warn!("the stepping thread is gone");
+            }
+        }
+        let generations = self.generations.lock().ok()?;
+        // generations of an older run are still in the channel after a restart
+        let next = generations
+            .try_iter()
+            .find(|(run, _)| *run == self.run)
+            .map(|(_, next)| next)?;
+        self.follows = Some(stepped(&next));
+        Some(next)
+    }
+
+    // paused, whatever the worker has stepped ahead is dropped
+    pub fn stop(&mut self) {
+        if self.follows.take().is_some() {
+            self.run += 1;
+            let _ = self.commands.send(Command::Stop);
+            if let Ok(generations) = self.generations.lock() {
+                generations.try_iter().for_each(drop);
+            }
+        }
+    }
+}
+
+// --worker, None steps the board in the frame
+#[derive(Resource, Default)]
+pub struct StepWorker(pub Option<Worker>);
+
+impl StepWorker {
+    pub fn new(enabled: bool, backend: TraceBackend) -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        return StepWorker(enabled.then(|| Worker::spawn(backend)));
+        #[cfg(target_arch = "wasm32")]
+        StepWorker(None)
+    }
+}
+
+// the worker's loop: blocks for a command while stopped, looks for one between generations
+// while running, and ends when the app drops its side of the channels
+#[cfg(not(target_arch = "wasm32"))]
+fn run(backend: TraceBackend, inbox: Receiver<Command>, outbox: SyncSender<(u64, Board)>) {
+    let mut backend = backend.create();
+    let mut current: Option<(u64, Board, FrozenRegions)> = None;
+    loop {
+        let command = match current {
+            Some(_) => match inbox.try_recv() {
+                Err(mpsc::TryRecvError::Disconnected) => return,
+                received => received.ok(),
+            },
+            None => match inbox.recv() {
+                Ok(command) => Some(command),
+                Err(_) => return,
+            },
+        };
+        match command {
+            Some(Command::Start { run, map, frozen }) => current = Some((run, *map, frozen)),
+            Some(Command::Stop) => current = None,
+            None => {}
+        }
+        let Some((run, map, frozen)) = current.as_mut() else {
+            continue;
+        };
+        *map = match backend.supports(map, frozen) {
+            true => backend.advance(map, frozen, 1),
+            false => map.step_auto(frozen),
+        };
+        // blocks while the app is AHEAD generations behind
+        if outbox.send((*run, map.clone())).is_err() {
+            return;
+        }
+        // an empty board stays empty, there is no point in handing it back again and again
+        if map.is_empty() {
+            current = None;
+        }
+    }
+}